@@ -1,11 +1,18 @@
 use std::{
     collections::hash_map::DefaultHasher,
+    fmt,
     hash::{Hash, Hasher},
-    path::PathBuf,
+    io::Write,
+    path::{Path, PathBuf},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use argon2::Argon2;
 use async_trait::async_trait;
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
 
 use cqupt_ics_core::{Error, Result, cache::CacheBackend};
 
@@ -50,9 +57,64 @@ fn parse_cache_entry(raw: &[u8]) -> Result<(bool, &[u8])> {
     Ok((is_expired, data))
 }
 
+/// 加密条目标记，紧跟在头部之后：[标记(4字节)] + [nonce(12字节)] + [密文]
+const ENCRYPTED_MAGIC: &[u8] = b"CQE1";
+const NONCE_SIZE: usize = 12;
+/// 由口令派生密钥时使用的固定盐
+const PASSPHRASE_SALT: &[u8] = b"cqupt-ics-file-cache-v1";
+/// 本机密钥文件名
+const MACHINE_KEY_FILE: &str = "cache.key";
+
+/// 缓存加密密钥
+#[derive(Clone)]
+pub struct CacheKey([u8; 32]);
+
+impl CacheKey {
+    /// 由用户口令派生密钥（Argon2id）
+    pub fn from_passphrase(passphrase: &str) -> Result<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), PASSPHRASE_SALT, &mut key)
+            .map_err(|e| Error::Config(format!("Failed to derive cache key: {}", e)))?;
+        Ok(Self(key))
+    }
+
+    /// 读取本机密钥文件，不存在时随机生成
+    pub fn machine_local(path: &Path) -> Result<Self> {
+        if let Ok(bytes) = std::fs::read(path)
+            && let Ok(key) = <[u8; 32]>::try_from(bytes.as_slice())
+        {
+            return Ok(Self(key));
+        }
+
+        let key: [u8; 32] = ChaCha20Poly1305::generate_key(&mut OsRng).into();
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(&key))
+            .map_err(|e| Error::Config(format!("Failed to write cache key: {}", e)))?;
+        Ok(Self(key))
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(&Key::from(self.0))
+    }
+}
+
+impl fmt::Debug for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CacheKey(..)")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FileCache {
     cache_dir: PathBuf,
+    /// 设置后缓存内容加密存储
+    key: Option<CacheKey>,
 }
 
 impl FileCache {
@@ -63,7 +125,63 @@ impl FileCache {
             })?;
         }
 
-        Ok(Self { cache_dir })
+        Ok(Self {
+            cache_dir,
+            key: None,
+        })
+    }
+
+    /// 使用指定密钥加密缓存内容
+    ///
+    /// 用其他密钥加密的条目、以及未加密的旧条目在读取时会被丢弃。
+    pub fn with_encryption(mut self, key: CacheKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// 缓存目录
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// 使用缓存目录下的本机密钥加密缓存内容
+    ///
+    /// 密钥与密文位于同一目录，仅能防止其他本地用户读取缓存（Unix 下密钥文件权限为 0600），
+    /// 无法防范能读取整个缓存目录的人；需要更强保护时请使用口令加密。
+    pub fn with_machine_key(self) -> Result<Self> {
+        let key = CacheKey::machine_local(&self.cache_dir.join(MACHINE_KEY_FILE))?;
+        Ok(self.with_encryption(key))
+    }
+
+    /// 加密待写入的数据，未启用加密时原样返回
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let Some(key) = &self.key else {
+            return Ok(data.to_vec());
+        };
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = key
+            .cipher()
+            .encrypt(&nonce, data)
+            .map_err(|_| Error::Internal("Failed to encrypt cache entry".to_string()))?;
+
+        let mut payload = Vec::with_capacity(ENCRYPTED_MAGIC.len() + NONCE_SIZE + ciphertext.len());
+        payload.extend_from_slice(ENCRYPTED_MAGIC);
+        payload.extend_from_slice(&nonce);
+        payload.extend_from_slice(&ciphertext);
+        Ok(payload)
+    }
+
+    /// 解密读取的数据，加密设置或密钥不匹配时返回 None
+    fn open(&self, payload: &[u8]) -> Option<Vec<u8>> {
+        match (&self.key, payload.strip_prefix(ENCRYPTED_MAGIC)) {
+            (None, None) => Some(payload.to_vec()),
+            (Some(key), Some(sealed)) if sealed.len() >= NONCE_SIZE => {
+                let (nonce, ciphertext) = sealed.split_at(NONCE_SIZE);
+                let nonce = Nonce::from(<[u8; NONCE_SIZE]>::try_from(nonce).ok()?);
+                key.cipher().decrypt(&nonce, ciphertext).ok()
+            }
+            _ => None,
+        }
     }
 
     pub fn with_default_dir(app_name: &str) -> Result<Self> {
@@ -135,7 +253,7 @@ impl CacheBackend for FileCache {
                 .map_err(|e| Error::Internal(format!("Failed to create cache directory: {}", e)))?;
         }
 
-        let entry_with_header = create_cache_entry(value, ttl);
+        let entry_with_header = create_cache_entry(&self.seal(value)?, ttl);
 
         let file_path = self.cache_file_path(key);
         tokio::fs::write(file_path, entry_with_header)
@@ -156,15 +274,14 @@ impl CacheBackend for FileCache {
         })?;
 
         match parse_cache_entry(&content) {
-            Ok((is_expired, data)) => {
-                if is_expired {
+            Ok((false, data)) => match self.open(data) {
+                Some(data) => Ok(Some(data)),
+                None => {
                     let _ = self.delete(key).await;
                     Ok(None)
-                } else {
-                    Ok(Some(data.to_vec()))
                 }
-            }
-            Err(_) => {
+            },
+            _ => {
                 let _ = self.delete(key).await;
                 Ok(None)
             }
@@ -193,13 +310,10 @@ impl CacheBackend for FileCache {
         })?;
 
         match parse_cache_entry(&content) {
-            Ok((is_expired, _data)) => {
-                if is_expired {
-                    let _ = tokio::fs::remove_file(file_path).await;
-                    Ok(false)
-                } else {
-                    Ok(true)
-                }
+            Ok((false, data)) if self.open(data).is_some() => Ok(true),
+            Ok(_) => {
+                let _ = tokio::fs::remove_file(file_path).await;
+                Ok(false)
             }
             Err(_) => {
                 let _ = tokio::fs::remove_file(file_path).await;
@@ -253,3 +367,80 @@ impl CacheBackend for FileCache {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str) -> FileCache {
+        let dir =
+            std::env::temp_dir().join(format!("cqupt-ics-cache-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        FileCache::new(dir).unwrap()
+    }
+
+    const TOKEN: &[u8] = br#"{"token":"secret-session-token"}"#;
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[tokio::test]
+    async fn encrypted_round_trip() {
+        let cache = temp_cache("round-trip")
+            .with_encryption(CacheKey::from_passphrase("correct horse").unwrap());
+
+        cache.set_raw("token", TOKEN, TTL).await.unwrap();
+        let on_disk = std::fs::read(cache.cache_file_path("token")).unwrap();
+        assert!(!on_disk.windows(TOKEN.len()).any(|w| w == TOKEN));
+
+        assert_eq!(
+            cache.get_raw("token").await.unwrap().as_deref(),
+            Some(TOKEN)
+        );
+        assert!(cache.exists("token").await.unwrap());
+
+        cache.expire("token", TTL * 2).await.unwrap();
+        assert_eq!(
+            cache.get_raw("token").await.unwrap().as_deref(),
+            Some(TOKEN)
+        );
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn wrong_key_entries_are_discarded() {
+        let dir = temp_cache("wrong-key").cache_dir;
+        let writer = FileCache::new(dir.clone())
+            .unwrap()
+            .with_encryption(CacheKey::from_passphrase("correct horse").unwrap());
+        writer.set_raw("token", TOKEN, TTL).await.unwrap();
+
+        let reader = FileCache::new(dir.clone())
+            .unwrap()
+            .with_encryption(CacheKey::from_passphrase("battery staple").unwrap());
+        assert!(!reader.exists("token").await.unwrap());
+        writer.set_raw("token", TOKEN, TTL).await.unwrap();
+        assert_eq!(reader.get_raw("token").await.unwrap(), None);
+        assert!(!reader.cache_file_path("token").exists());
+
+        // 未加密的缓存也不会读取加密条目，反之亦然
+        let plain = FileCache::new(dir.clone()).unwrap();
+        writer.set_raw("token", TOKEN, TTL).await.unwrap();
+        assert_eq!(plain.get_raw("token").await.unwrap(), None);
+        plain.set_raw("token", TOKEN, TTL).await.unwrap();
+        assert_eq!(writer.get_raw("token").await.unwrap(), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn machine_key_is_persisted() {
+        let cache = temp_cache("machine-key");
+        let path = cache.cache_dir.join(MACHINE_KEY_FILE);
+
+        let first = CacheKey::machine_local(&path).unwrap();
+        let second = CacheKey::machine_local(&path).unwrap();
+        assert_eq!(first.0, second.0);
+
+        std::fs::remove_dir_all(&cache.cache_dir).unwrap();
+    }
+}
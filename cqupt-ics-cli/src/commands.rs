@@ -1,17 +1,70 @@
-use std::{collections::HashMap, fs, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    future::Future,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::Result;
+use chrono::NaiveDate;
 use cqupt_ics_core::{
-    cache::CacheBackend, holiday::HolidayCalendar, ics::IcsGenerator, location::LocationManager,
+    cache::{Cache, CacheBackend},
+    holiday::HolidayCalendar,
+    ics::{self, EventSequences, IcsGenerator},
+    location::LocationManager,
     prelude::*,
 };
 use reqwest::Client;
+use serde::Serialize;
+use tokio::{sync::Semaphore, task::JoinSet};
 
-use crate::{cache::FileCache, registry};
+use crate::{cache::FileCache, config::CliConfig, registry};
 
-const DEFAULT_HOLIDAY_URL: &str = "https://calendars.icloud.com/holidays/cn_zh.ics";
-const HOLIDAY_CACHE_KEY: &str = "holiday:cn_zh";
+/// 默认输出文件名模板
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "cqupt-schedule-{username}-{date}.ics";
 const HOLIDAY_CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 12);
+const SEQUENCE_CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+/// `--output -` 表示写到标准输出
+const STDOUT_OUTPUT: &str = "-";
+
+/// 进度提示，输出到标准输出时改写到标准错误，保持管道干净
+#[derive(Clone, Copy)]
+struct Status {
+    to_stderr: bool,
+}
+
+impl Status {
+    fn for_output(output: Option<&str>) -> Self {
+        Self {
+            to_stderr: output == Some(STDOUT_OUTPUT),
+        }
+    }
+
+    fn say(&self, message: impl Display) {
+        if self.to_stderr {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+}
+
+/// 写出生成的内容，路径为 `-` 时写到标准输出
+fn write_output(status: Status, path: &str, content: &str) -> Result<()> {
+    if path == STDOUT_OUTPUT {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()?;
+    } else {
+        fs::write(path, content)?;
+        status.say(format!("✓ ICS文件已保存到: {}", path));
+    }
+    Ok(())
+}
 
 /// 生成课程表命令参数
 pub struct GenerateParams {
@@ -19,76 +72,638 @@ pub struct GenerateParams {
     pub username: String,
     pub password: String,
     pub start_date: Option<String>,
+    pub term: Option<String>,
     pub output: Option<String>,
+    pub output_dir: Option<String>,
+    pub filename_template: String,
     pub calendar_name: Option<String>,
+    pub calendar_description: Option<String>,
+    pub prodid: Option<String>,
     pub include_teacher: bool,
+    pub description_style: DescriptionStyle,
+    pub html_description: bool,
+    pub omit_empty_description: bool,
+    pub location_detail: LocationDetail,
+    pub use_duration: bool,
+    pub color_by: ColorBy,
+    pub aliases: Vec<String>,
+    pub content_filter: ContentFilter,
     pub reminder_minutes: u32,
+    pub exam_reminder_minutes: Vec<u32>,
+    pub alarm_before: Vec<u32>,
+    pub no_alarm: bool,
     pub holiday_ics: Option<String>,
+    pub holiday_events: bool,
+    pub timeout: Option<u32>,
+    pub provider_options: ProviderOptions,
+    pub exam_provider: Option<String>,
+    pub lesson_times: Option<String>,
+    pub stable_uids: bool,
+    pub split_by: Option<SplitBy>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub validate: bool,
+}
+
+impl GenerateParams {
+    /// 与 `generate` 命令行默认值一致的参数
+    pub fn new(provider_name: String, username: String, password: String) -> Self {
+        Self {
+            provider_name,
+            username,
+            password,
+            start_date: None,
+            term: None,
+            output: None,
+            output_dir: None,
+            filename_template: DEFAULT_FILENAME_TEMPLATE.to_string(),
+            calendar_name: None,
+            calendar_description: None,
+            prodid: None,
+            include_teacher: true,
+            description_style: DescriptionStyle::Full,
+            html_description: false,
+            omit_empty_description: false,
+            location_detail: LocationDetail::Full,
+            use_duration: false,
+            color_by: ColorBy::None,
+            aliases: Vec::new(),
+            content_filter: ContentFilter::All,
+            reminder_minutes: crate::config::DEFAULT_REMINDER_MINUTES,
+            exam_reminder_minutes: Vec::new(),
+            alarm_before: Vec::new(),
+            no_alarm: false,
+            holiday_ics: None,
+            holiday_events: false,
+            timeout: None,
+            provider_options: ProviderOptions::default(),
+            exam_provider: None,
+            lesson_times: None,
+            stable_uids: false,
+            split_by: None,
+            since: None,
+            until: None,
+            validate: false,
+        }
+    }
+}
+
+/// 批量生成文件中的一个账号，对应一行 `provider,username,password,output`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchAccount {
+    pub provider: String,
+    pub username: String,
+    pub password: String,
+    pub output: String,
+}
+
+/// 解析批量生成文件
+///
+/// 每行为 `provider,username,password,output`，字段可用双引号包裹（其中 `""` 表示一个引号），
+/// 以便密码中包含逗号。空行、`#` 开头的注释行以及表头行会被跳过。
+pub fn parse_batch_file(content: &str) -> Result<Vec<BatchAccount>> {
+    let mut accounts = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let fields =
+            parse_csv_line(line).map_err(|e| anyhow::anyhow!("第{}行: {}", index + 1, e))?;
+        let [provider, username, password, output]: [String; 4] =
+            fields.try_into().map_err(|fields: Vec<String>| {
+                anyhow::anyhow!(
+                    "第{}行: 需要 4 个字段 (provider,username,password,output)，实际 {} 个",
+                    index + 1,
+                    fields.len()
+                )
+            })?;
+        if accounts.is_empty() && provider == "provider" && username == "username" {
+            continue;
+        }
+        accounts.push(BatchAccount {
+            provider,
+            username,
+            password,
+            output,
+        });
+    }
+    Ok(accounts)
+}
+
+/// 拆分一行 CSV，支持双引号包裹的字段
+fn parse_csv_line(line: &str) -> Result<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        anyhow::bail!("引号未闭合");
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+/// 以最多 `concurrency` 个并发任务处理全部账号，结果顺序与输入一致
+pub async fn batch_generate<F, Fut>(
+    accounts: Vec<BatchAccount>,
+    concurrency: usize,
+    generate: F,
+) -> Vec<(BatchAccount, Result<()>)>
+where
+    F: Fn(BatchAccount) -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (index, account) in accounts.iter().cloned().enumerate() {
+        let semaphore = semaphore.clone();
+        let task = generate(account);
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            (index, task.await)
+        });
+    }
+
+    let mut results: Vec<Option<Result<()>>> = accounts.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(e) => tracing::error!("批量生成任务异常退出: {}", e),
+        }
+    }
+    accounts
+        .into_iter()
+        .zip(results)
+        .map(|(account, result)| {
+            let result = result.unwrap_or_else(|| Err(anyhow::anyhow!("任务异常退出")));
+            (account, result)
+        })
+        .collect()
+}
+
+/// 批量生成课程表命令，任一账号失败时返回错误
+pub async fn generate_batch_command(file: &str, concurrency: usize) -> Result<()> {
+    let accounts = parse_batch_file(&fs::read_to_string(file)?)?;
+    if accounts.is_empty() {
+        anyhow::bail!("批量文件中没有账号: {}", file);
+    }
+    let config = CliConfig::load_default()?;
+    let reminder_minutes = config.reminder_minutes(None);
+    let calendar_name = config.calendar_name(None);
+
+    let results = batch_generate(accounts, concurrency, |account| {
+        let params = GenerateParams {
+            output: Some(account.output),
+            reminder_minutes,
+            calendar_name: calendar_name.clone(),
+            ..GenerateParams::new(account.provider, account.username, account.password)
+        };
+        generate_command(params)
+    })
+    .await;
+
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+    println!();
+    for (account, result) in &results {
+        match result {
+            Ok(()) => println!(
+                "✓ {} ({}) -> {}",
+                account.username, account.provider, account.output
+            ),
+            Err(e) => println!("✗ {} ({}): {}", account.username, account.provider, e),
+        }
+    }
+    println!(
+        "批量生成完成: {} 个成功, {} 个失败",
+        results.len() - failed,
+        failed
+    );
+    if failed > 0 {
+        anyhow::bail!("{} 个账号生成失败", failed);
+    }
+    Ok(())
 }
 
 /// 生成课程表命令
 pub async fn generate_command(params: GenerateParams) -> Result<()> {
+    // 在请求 provider 之前检查提醒参数
+    let alarms = alarm_options(&params)?;
     tracing::info!(
         "开始生成课程表: provider={}, 用户={}",
         params.provider_name,
         params.username,
     );
 
-    let semester = params
-        .start_date
-        .map(|date_str| {
+    let semester = match (params.start_date, params.term) {
+        (Some(date_str), _) => {
             tracing::info!("使用指定的学期开始日期: {}", date_str);
-            Semester::from_date_str(&date_str)
-                .map_err(|e| anyhow::anyhow!("Invalid start date: {}", e))
-        })
-        .transpose()?;
+            Some(Semester::from_date_str(&date_str)?)
+        }
+        (None, Some(term)) => {
+            let semester = SemesterDetector::from_term_code(&term)?;
+            tracing::info!(
+                "学期 {} 推算开始日期: {}",
+                term,
+                semester.start_date.format("%Y-%m-%d")
+            );
+            Some(semester)
+        }
+        (None, None) => None,
+    };
 
     // 创建请求对象
     let mut request = CourseRequest {
         credentials: Credentials {
             username: params.username.clone(),
-            password: params.password,
+            password: params.password.into(),
             extra: HashMap::new(),
         },
         semester,
+        timeout: params.timeout,
+        options: ProviderOptions {
+            lesson_times: params
+                .lesson_times
+                .as_deref()
+                .map(load_lesson_times)
+                .transpose()?,
+            ..params.provider_options
+        },
+    };
+
+    let date_window = match (&params.since, &params.until) {
+        (None, None) => None,
+        (since, until) => Some((
+            since
+                .as_deref()
+                .map(parse_cli_date)
+                .transpose()?
+                .unwrap_or(NaiveDate::MIN),
+            until
+                .as_deref()
+                .map(parse_cli_date)
+                .transpose()?
+                .unwrap_or(NaiveDate::MAX),
+        )),
     };
 
+    let status = Status::for_output(params.output.as_deref());
     let provider = registry::get_provider(&params.provider_name)
         .ok_or_else(|| anyhow::anyhow!("未知的provider: {}", params.provider_name))?;
     // 获取课程数据
-    println!("验证用户凭据...");
+    status.say("验证用户凭据...");
     let mut response = provider.get_courses(&mut request).await?;
-    println!("✓ 凭据验证成功");
-    println!("✓ 成功获取 {} 门课程", response.courses.len());
+    status.say("✓ 凭据验证成功");
+    if let Some(name) = &params.exam_provider {
+        let exam_provider = registry::get_provider(name)
+            .ok_or_else(|| anyhow::anyhow!("未知的provider: {}", name))?;
+        let mut exam_request = CourseRequest {
+            credentials: request.credentials.clone(),
+            semester: Some(response.semester.clone()),
+            timeout: request.timeout,
+            options: ProviderOptions {
+                include_classes: false,
+                include_exams: true,
+                include_custom: false,
+                lesson_times: request.options.lesson_times.clone(),
+            },
+        };
+        // 考试来源失败不影响课表生成，记为警告
+        match exam_provider.get_courses(&mut exam_request).await {
+            Ok(exams) => {
+                let merged = response.merge_exams(exams);
+                status.say(format!("✓ 已从 {} 合并 {} 场考试", name, merged));
+            }
+            Err(e) => {
+                tracing::warn!("从 {} 获取考试失败: {}", name, e);
+                response
+                    .warnings
+                    .push(format!("从 {} 获取考试失败: {}", name, e));
+            }
+        }
+    }
+    status.say(format_stats(&response.stats()));
+    for warning in &response.warnings {
+        status.say(format!("⚠ {}", warning));
+    }
 
     let calendar = load_holiday_calendar(params.holiday_ics.as_ref()).await?;
     calendar.apply_to_response(&mut response);
-    println!("✓ 已根据节假日调休更新课程表");
+    status.say("✓ 已根据节假日调休更新课程表");
+    response.sort();
+    print_conflicts(status, &response);
     // 生成ICS文件
-    println!("生成ICS日历文件...");
+    status.say("生成ICS日历文件...");
     let options = IcsOptions {
-        calendar_name: params
-            .calendar_name
-            .or_else(|| Some(format!("{}的课程表", params.username))),
+        calendar_name: Some(match params.calendar_name {
+            // 不含占位符的名称原样使用
+            Some(name) if !name.contains('{') => name,
+            Some(template) => render_template(
+                &template,
+                &params.username,
+                &params.provider_name,
+                &response.semester,
+            ),
+            None => format!("{}的课程表", params.username),
+        }),
+        calendar_description: params.calendar_description,
+        calendar_id: Some(ics::calendar_id(
+            &params.provider_name,
+            &params.username,
+            &response.semester,
+        )),
+        prodid: params.prodid,
         include_description: true,
+        description_style: params.description_style,
+        html_description: params.html_description,
+        omit_empty_description: params.omit_empty_description,
         include_teacher: params.include_teacher,
-        reminder_minutes: Some(params.reminder_minutes),
+        location_detail: params.location_detail,
+        use_duration: params.use_duration,
+        color_by: params.color_by,
+        include_holiday_events: params.holiday_events,
+        stable_uids: params.stable_uids,
+        content_filter: params.content_filter,
+        date_window,
+        fixed_dtstamp: None,
+        dtstamp_from_response: false,
+        name_aliases: params
+            .aliases
+            .iter()
+            .map(|alias| parse_alias(alias))
+            .collect::<Result<_>>()?,
+        ..alarms
     };
 
-    let generator = IcsGenerator::new(options);
-    let ics_content = generator.generate(&response)?;
+    // 按实际学期区分缓存，避免跨学期的 SEQUENCE 互相覆盖
+    request.semester = Some(response.semester.clone());
+    let year = response.semester.year();
+    let generator =
+        IcsGenerator::new(options).with_holiday_events(calendar.to_events(year..=year + 1));
 
     // 确定输出文件名
-    let start_date_str = response.semester.start_date.format("%Y-%m-%d").to_string();
-    let output_file = params
-        .output
-        .unwrap_or_else(|| format!("cqupt-schedule-{}-{}.ics", params.username, start_date_str));
+    let output_file = match params.output {
+        Some(output) => output,
+        None => {
+            let file_name = render_template(
+                &params.filename_template,
+                &params.username,
+                &params.provider_name,
+                &response.semester,
+            );
+            resolve_output_path(params.output_dir.as_deref(), &file_name)?
+                .to_string_lossy()
+                .into_owned()
+        }
+    };
+
+    if let Some(split_by) = params.split_by {
+        if output_file == STDOUT_OUTPUT {
+            anyhow::bail!("--split-by writes multiple files and cannot be used with --output -");
+        }
+        let calendars = generator.generate_split(&response, split_by)?;
+        let mut keys: Vec<_> = calendars.keys().collect();
+        keys.sort();
+        for key in keys {
+            if params.validate {
+                IcsGenerator::validate(&calendars[key])?;
+            }
+            write_output(
+                status,
+                &split_output_path(&output_file, key),
+                &calendars[key],
+            )?;
+        }
+        return Ok(());
+    }
+
+    let ics_content = if params.stable_uids {
+        generate_with_sequences(
+            &generator,
+            &response,
+            &semester_cache_key(&params.provider_name, "ics:sequence", &request),
+        )
+        .await?
+    } else {
+        generator.generate(&response)?
+    };
+    if params.validate {
+        IcsGenerator::validate(&ics_content)?;
+        status.say("✓ ICS校验通过");
+    }
 
     // 写入文件
-    fs::write(&output_file, ics_content)?;
-    println!("✓ ICS文件已保存到: {}", output_file);
+    write_output(status, &output_file, &ics_content)
+}
 
-    Ok(())
+/// 课表概况，如 "✓ 15门课 / 3场考试 / 覆盖第1-16周"
+fn format_stats(stats: &ResponseStats) -> String {
+    let mut parts = vec![format!("{}门课", stats.classes)];
+    if stats.exams > 0 {
+        parts.push(format!("{}场考试", stats.exams));
+    }
+    if stats.custom > 0 {
+        parts.push(format!("{}个自定义日程", stats.custom));
+    }
+    if let Some((first, last)) = stats.week_span {
+        parts.push(format!("覆盖第{}-{}周", first, last));
+    }
+    format!("✓ {}", parts.join(" / "))
+}
+
+/// 读取自定义作息时间文件
+fn load_lesson_times(path: &str) -> Result<LessonSchedule> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("读取作息时间文件 {} 失败: {}", path, e))?;
+    Ok(LessonSchedule::from_json(&content)?)
+}
+
+/// 替换文件名或日历名称模板中的占位符
+fn render_template(template: &str, username: &str, provider: &str, semester: &Semester) -> String {
+    template
+        .replace("{username}", username)
+        .replace("{provider}", provider)
+        .replace(
+            "{date}",
+            &semester.start_date.format("%Y-%m-%d").to_string(),
+        )
+        .replace("{term}", &semester.term_label())
+}
+
+/// 拼接输出目录与文件名，所在目录不存在时创建
+fn resolve_output_path(output_dir: Option<&str>, file_name: &str) -> Result<PathBuf> {
+    let path = match output_dir {
+        Some(dir) => Path::new(dir).join(file_name),
+        None => PathBuf::from(file_name),
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow::anyhow!("创建输出目录 {} 失败: {}", parent.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// 使用缓存中上次生成的记录生成日历，内容变化的事件递增 SEQUENCE
+async fn generate_with_sequences(
+    generator: &IcsGenerator,
+    response: &CourseResponse,
+    cache_key: &str,
+) -> Result<String> {
+    let cache = FileCache::with_default_dir("cqupt-ics")
+        .map_err(|e| anyhow::anyhow!("初始化缓存失败: {}", e))?;
+    let mut sequences: EventSequences = match cache.get(cache_key).await {
+        Ok(sequences) => sequences.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("读取事件序号缓存失败，重新计数: {}", e);
+            EventSequences::default()
+        }
+    };
+
+    let ics_content = generator.generate_with_sequences(response, &mut sequences)?;
+    cache
+        .set(cache_key, &sequences, SEQUENCE_CACHE_TTL)
+        .await
+        .map_err(|e| anyhow::anyhow!("写入事件序号缓存失败: {}", e))?;
+    Ok(ics_content)
+}
+
+/// 解析命令行日期，支持 `today`
+fn parse_cli_date(value: &str) -> Result<NaiveDate> {
+    if value.eq_ignore_ascii_case("today") {
+        return Ok(chrono::Local::now().date_naive());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| anyhow::anyhow!("Invalid date '{}': {}", value, e))
+}
+
+/// 拆分日历的文件路径：在扩展名前追加分组名，如 `schedule-exams.ics`
+///
+/// 分组名可能来自课程类型等上游数据，其中的路径分隔符与文件名保留字符替换为 `_`。
+fn split_output_path(path: &str, key: &str) -> String {
+    let key = sanitize_file_component(key);
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, key, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, key),
+    };
+    path.with_file_name(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// 将任意文本转为可安全用作文件名一部分的字符串，不会产生路径分隔或 `..`
+fn sanitize_file_component(text: &str) -> String {
+    let sanitized: String = text
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let sanitized = sanitized.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    if sanitized.is_empty() {
+        "_".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// 由提醒相关参数得到只含提醒设置的 ICS 选项
+///
+/// `--no-alarm` 不输出任何 VALARM，与其他提醒参数同时指定时报错。
+fn alarm_options(params: &GenerateParams) -> Result<IcsOptions> {
+    if params.no_alarm
+        && !(params.alarm_before.is_empty() && params.exam_reminder_minutes.is_empty())
+    {
+        anyhow::bail!("--no-alarm 不能与 --alarm-before 或 --exam-reminder-minutes 同时使用");
+    }
+    let specs = |minutes: &[u32]| minutes.iter().copied().map(ReminderSpec::minutes).collect();
+    Ok(IcsOptions {
+        reminder_minutes: (!params.no_alarm).then_some(params.reminder_minutes),
+        reminders: specs(&params.alarm_before),
+        exam_reminders: specs(&params.exam_reminder_minutes),
+        ..Default::default()
+    })
+}
+
+/// 解析 `原始名称=显示名称` 形式的课程别名
+fn parse_alias(value: &str) -> Result<(String, String)> {
+    value
+        .split_once('=')
+        .map(|(raw, display)| (raw.trim(), display.trim()))
+        .filter(|(raw, display)| !raw.is_empty() && !display.is_empty())
+        .map(|(raw, display)| (raw.to_string(), display.to_string()))
+        .ok_or_else(|| anyhow::anyhow!("Invalid alias '{}', expected raw=display", value))
+}
+
+/// 打印时间冲突的课程
+fn print_conflicts(status: Status, response: &CourseResponse) {
+    let conflicts = response.conflicts();
+    if conflicts.is_empty() {
+        return;
+    }
+    status.say(format!("⚠ 检测到课程冲突 {} 处:", conflicts.len()));
+    for (i, j) in conflicts {
+        let (a, b) = (&response.courses[i], &response.courses[j]);
+        status.say(format!(
+            "  - {} ({}) 与 {} ({})",
+            a.name,
+            a.start_time.format("%Y-%m-%d %H:%M"),
+            b.name,
+            b.start_time.format("%Y-%m-%d %H:%M")
+        ));
+    }
+}
+
+/// 重新应用节假日调休命令
+pub async fn reapply_holidays_command(
+    input: String,
+    output: Option<String>,
+    calendar_name: Option<String>,
+    holiday_ics: Option<String>,
+) -> Result<()> {
+    let status = Status::for_output(output.as_deref());
+    let content = fs::read_to_string(&input)?;
+    let mut response = cqupt_ics_core::ics::parse(&content)?;
+    status.say(format!(
+        "✓ 从 {} 读取 {} 个事件",
+        input,
+        response.courses.len()
+    ));
+
+    let calendar = load_holiday_calendar(holiday_ics.as_ref()).await?;
+    calendar.apply_to_response(&mut response);
+    status.say("✓ 已根据节假日调休更新课程表");
+    response.sort();
+
+    let options = IcsOptions {
+        calendar_name: calendar_name.or(IcsOptions::default().calendar_name),
+        ..Default::default()
+    };
+    let ics_content = IcsGenerator::new(options).generate(&response)?;
+
+    let output_file = output.unwrap_or_else(|| match input.strip_suffix(".ics") {
+        Some(stem) => format!("{}.reapplied.ics", stem),
+        None => format!("{}.reapplied.ics", input),
+    });
+    write_output(status, &output_file, &ics_content)
 }
 
 async fn load_holiday_calendar(holiday_path: Option<&String>) -> Result<HolidayCalendar> {
@@ -108,47 +723,14 @@ async fn load_holiday_calendar(holiday_path: Option<&String>) -> Result<HolidayC
 
     let cache = FileCache::with_default_dir("cqupt-ics")
         .map_err(|e| anyhow::anyhow!("初始化缓存失败: {}", e))?;
-
-    if let Some(bytes) = cache
-        .get_raw(HOLIDAY_CACHE_KEY)
-        .await
-        .map_err(|e| anyhow::anyhow!("读取节假日缓存失败: {}", e))?
-    {
-        tracing::debug!("命中节假日调休缓存");
-        return HolidayCalendar::from_bytes(&bytes)
-            .map_err(|e| anyhow::anyhow!("解析节假日ICS失败: {}", e));
-    }
-
     let client = Client::builder()
         .user_agent("cqupt-ics-cli/holiday-loader")
         .build()
         .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
 
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("请求节假日ICS失败: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "获取节假日ICS失败: HTTP {}",
-            response.status()
-        ));
-    }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| anyhow::anyhow!("读取节假日ICS内容失败: {}", e))?;
-    let data = bytes.to_vec();
-
-    cache
-        .set_raw(HOLIDAY_CACHE_KEY, &data, HOLIDAY_CACHE_TTL)
+    HolidayCalendar::from_url(&client, &url, &cache, HOLIDAY_CACHE_TTL)
         .await
-        .map_err(|e| anyhow::anyhow!("写入节假日缓存失败: {}", e))?;
-
-    HolidayCalendar::from_bytes(&data).map_err(|e| anyhow::anyhow!("解析节假日ICS失败: {}", e))
+        .map_err(|e| anyhow::anyhow!("加载节假日ICS失败: {}", e))
 }
 
 /// 验证凭据命令
@@ -160,10 +742,12 @@ pub async fn validate_command(
     let request = CourseRequest {
         credentials: Credentials {
             username: username.clone(),
-            password,
+            password: password.into(),
             extra: HashMap::new(),
         },
         semester: None,
+        timeout: None,
+        options: Default::default(),
     };
 
     let provider = registry::get_provider(&provider_name)
@@ -176,17 +760,191 @@ pub async fn validate_command(
     Ok(())
 }
 
+/// 查询下一节课命令
+pub async fn next_command(
+    provider_name: String,
+    username: String,
+    password: String,
+    start_date: Option<String>,
+    holiday_ics: Option<String>,
+) -> Result<()> {
+    let mut request = CourseRequest {
+        credentials: Credentials {
+            username,
+            password: password.into(),
+            extra: HashMap::new(),
+        },
+        semester: start_date
+            .as_deref()
+            .map(Semester::from_date_str)
+            .transpose()?,
+        timeout: None,
+        options: Default::default(),
+    };
+
+    let provider = registry::get_provider(&provider_name)
+        .ok_or_else(|| anyhow::anyhow!("未知的provider: {}", provider_name))?;
+    let mut response = provider.get_courses(&mut request).await?;
+    let calendar = load_holiday_calendar(holiday_ics.as_ref()).await?;
+    calendar.apply_to_response(&mut response);
+
+    let now = chrono::Utc::now().with_timezone(&response.generated_at.timezone());
+    match response.next_occurrence(now) {
+        Some((course, start)) => println!(
+            "下一节课：{} @ {} {}",
+            course.name,
+            start.format("%Y-%m-%d %H:%M"),
+            course.location.as_deref().unwrap_or_default()
+        ),
+        None => println!("本学期已没有剩余课程"),
+    }
+
+    Ok(())
+}
+
+/// 显示配置文件路径命令
+pub fn config_path_command() -> Result<()> {
+    println!("{}", CliConfig::default_path()?.display());
+    Ok(())
+}
+
+/// 显示当前生效配置命令
+pub fn config_show_command() -> Result<()> {
+    let path = CliConfig::default_path()?;
+    if !path.exists() {
+        println!("# 配置文件 {} 不存在，使用默认值", path.display());
+    }
+    let config = CliConfig::load(&path)?;
+    print!("{}", toml::to_string_pretty(&config)?);
+    Ok(())
+}
+
+/// 诊断时单个接口的超时
+const DOCTOR_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 输出一项检查结果，返回是否通过
+fn report_check(ok: bool, label: &str, detail: impl Display) -> bool {
+    println!("  {} {}: {}", if ok { "✓" } else { "✗" }, label, detail);
+    ok
+}
+
+/// 探测接口根地址是否可达，返回 HTTP 状态码
+async fn probe_api_root(client: &Client, api_root: &str) -> Result<reqwest::StatusCode> {
+    let response = client
+        .get(api_root)
+        .send()
+        .await
+        .map_err(|e| anyhow::anyhow!("无法连接: {}", e))?;
+    Ok(response.status())
+}
+
+/// 诊断缓存、配置与接口连通性命令
+pub async fn doctor_command() -> Result<()> {
+    println!("环境诊断:");
+    let mut failures = 0;
+
+    let config = CliConfig::default_path().and_then(|path| {
+        CliConfig::load(&path)?;
+        Ok(path)
+    });
+    let ok = match config {
+        Ok(path) => report_check(true, "配置文件", path.display()),
+        Err(e) => report_check(false, "配置文件", e),
+    };
+    failures += usize::from(!ok);
+
+    let cache = async {
+        let cache = FileCache::with_default_dir("cqupt-ics")?;
+        cache
+            .set_raw("doctor:probe", b"ok", Duration::from_secs(60))
+            .await?;
+        let read_back = cache.get_raw("doctor:probe").await?;
+        cache.delete("doctor:probe").await?;
+        if read_back.as_deref() != Some(b"ok".as_slice()) {
+            return Err(anyhow::anyhow!("写入后读取的内容不一致"));
+        }
+        Ok::<_, anyhow::Error>(cache.cache_dir().to_path_buf())
+    };
+    let ok = match cache.await {
+        Ok(dir) => report_check(true, "缓存目录可写", dir.display()),
+        Err(e) => report_check(false, "缓存目录可写", e),
+    };
+    failures += usize::from(!ok);
+
+    let mut providers: Vec<_> = match registry::list_providers() {
+        Ok(providers) => providers.collect(),
+        Err(e) => {
+            report_check(false, "已注册provider", e);
+            failures += 1;
+            Vec::new()
+        }
+    };
+    providers.sort_by_key(|provider| provider.name());
+    if !providers.is_empty() {
+        let names: Vec<_> = providers.iter().map(|provider| provider.name()).collect();
+        report_check(true, "已注册provider", names.join(", "));
+    }
+
+    let client = Client::builder()
+        .timeout(DOCTOR_PROBE_TIMEOUT)
+        .build()
+        .map_err(|e| anyhow::anyhow!("创建HTTP客户端失败: {}", e))?;
+    for provider in providers {
+        let Some(api_root) = provider.api_root() else {
+            continue;
+        };
+        let label = format!("{} 接口连通性", provider.name());
+        let ok = match probe_api_root(&client, api_root).await {
+            Ok(status) if status.is_server_error() => report_check(
+                false,
+                &label,
+                format!("{} 返回 {}（夜间宵禁时接口可能暂停服务）", api_root, status),
+            ),
+            Ok(status) => report_check(true, &label, format!("{} 返回 {}", api_root, status)),
+            Err(e) => report_check(false, &label, format!("{} {}", api_root, e)),
+        };
+        failures += usize::from(!ok);
+    }
+
+    let semester = SemesterDetector::create_current_semester();
+    report_check(
+        true,
+        "当前学期",
+        format!(
+            "{}（开始于 {}）",
+            semester.term_label(),
+            semester.start_date.format("%Y-%m-%d")
+        ),
+    );
+
+    if failures > 0 {
+        return Err(anyhow::anyhow!("诊断发现 {} 项问题", failures));
+    }
+    println!("✓ 所有检查均已通过");
+    Ok(())
+}
+
 /// 列出provider命令
 pub async fn providers_command() -> Result<()> {
     println!("可用的数据provider:");
 
-    let providers: Vec<_> = registry::list_providers().collect();
+    let providers: Vec<_> = registry::list_providers()?.collect();
 
     if providers.is_empty() {
         println!("  暂无可用的数据provider");
     } else {
-        for (name, description) in providers {
-            println!("  {} - {}", name, description);
+        for provider in providers {
+            let recurrence = if provider.capabilities().recurrence_native {
+                "重复规则"
+            } else {
+                "单次事件"
+            };
+            println!(
+                "  {} - {} [{}]",
+                provider.name(),
+                provider.description(),
+                recurrence
+            );
         }
     }
 
@@ -194,8 +952,13 @@ pub async fn providers_command() -> Result<()> {
 }
 
 /// 列出位置映射命令
-pub async fn location_list_command() -> Result<()> {
+pub async fn location_list_command(json: bool) -> Result<()> {
     let manager = LocationManager::default();
+    if json {
+        println!("{}", manager.export_to_json()?);
+        return Ok(());
+    }
+
     let mappings = manager.get_all_mappings();
 
     println!("位置映射列表:");
@@ -213,10 +976,20 @@ pub async fn location_list_command() -> Result<()> {
 }
 
 /// 标准化位置名称命令
-pub async fn location_normalize_command(location: String) -> Result<()> {
+pub async fn location_normalize_command(location: String, json: bool) -> Result<()> {
     let manager = LocationManager::default();
     let normalized = manager.normalize_location(&location);
 
+    if json {
+        let output = NormalizedLocation {
+            details: manager.get_location_details(&location),
+            original: &location,
+            normalized,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
     println!("原始位置: {}", location);
     println!("标准化位置: {}", normalized);
 
@@ -236,16 +1009,39 @@ pub async fn location_normalize_command(location: String) -> Result<()> {
     Ok(())
 }
 
+/// `location normalize --json` 的输出
+#[derive(Serialize)]
+struct NormalizedLocation<'a> {
+    original: &'a str,
+    normalized: String,
+    details: Option<&'a LocationMapping>,
+}
+
+/// 解释位置匹配规则命令
+pub async fn location_explain_command(location: String) -> Result<()> {
+    let manager = LocationManager::default();
+    let explanation = manager.explain_location(&location);
+
+    println!("原始位置: {}", location);
+    println!("命中规则: {}", explanation.matched_rule);
+    println!("教室号: {}", explanation.room_number);
+    println!("地点: {}", explanation.title);
+    println!("坐标: {}, {}", explanation.coords.0, explanation.coords.1);
+
+    Ok(())
+}
+
 /// 导入位置映射命令
 pub async fn location_import_command(file: String) -> Result<()> {
     let content = fs::read_to_string(&file)?;
-    let mut manager = LocationManager::new();
-    manager.load_from_json(&content)?;
+    let defaults = LocationManager::default().get_all_mappings().len();
+    let manager = LocationManager::load_from_json_merge(&content)?;
 
     println!(
-        "✓ 成功从 {} 导入 {} 个位置映射",
+        "✓ 成功从 {} 导入位置映射，合并默认映射后共 {} 个（默认 {} 个，导入的映射优先）",
         file,
-        manager.get_all_mappings().len()
+        manager.get_all_mappings().len(),
+        defaults
     );
 
     Ok(())
@@ -261,3 +1057,235 @@ pub async fn location_export_command(file: String) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_line_skips_empty_parts() {
+        let stats = ResponseStats {
+            classes: 15,
+            exams: 3,
+            week_span: Some((1, 16)),
+            ..Default::default()
+        };
+        assert_eq!(format_stats(&stats), "✓ 15门课 / 3场考试 / 覆盖第1-16周");
+        assert_eq!(format_stats(&ResponseStats::default()), "✓ 0门课");
+    }
+
+    #[test]
+    fn split_paths_append_group_key() {
+        assert_eq!(
+            split_output_path("out/schedule.ics", "exams"),
+            "out/schedule-exams.ics"
+        );
+        assert_eq!(split_output_path("schedule", "classes"), "schedule-classes");
+        // 分组名中的路径成分被替换，输出仍在原目录
+        assert_eq!(
+            split_output_path("out/schedule.ics", "../../etc/passwd"),
+            "out/schedule-_.._etc_passwd.ics"
+        );
+        assert_eq!(
+            split_output_path("out/schedule.ics", ".."),
+            "out/schedule-_.ics"
+        );
+        assert_eq!(
+            split_output_path("schedule.ics", "实验:A|B"),
+            "schedule-实验_A_B.ics"
+        );
+    }
+
+    const BATCH_FILE: &str = "provider,username,password,output\n\
+        mock,2023000001,secret,a.ics\n\
+        \n\
+        # 用户名为空的账号会认证失败\n\
+        mock,,secret,b.ics\n\
+        mock,2023000003,\"p,a\"\"ss\",c.ics\n";
+
+    #[test]
+    fn batch_file_is_parsed() {
+        let accounts = parse_batch_file(BATCH_FILE).unwrap();
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(accounts[1].username, "");
+        assert_eq!(accounts[2].password, "p,a\"ss");
+        assert_eq!(accounts[2].output, "c.ics");
+
+        assert!(parse_batch_file("mock,2023000001,secret").is_err());
+        assert!(parse_batch_file("mock,2023000001,\"secret,a.ics").is_err());
+    }
+
+    #[tokio::test]
+    async fn batch_reports_each_account() {
+        let accounts = parse_batch_file(BATCH_FILE).unwrap();
+        let results = batch_generate(accounts, 2, |account| async move {
+            if account.username.is_empty() {
+                anyhow::bail!("用户名不能为空");
+            }
+            Ok(())
+        })
+        .await;
+
+        let outcomes: Vec<_> = results
+            .iter()
+            .map(|(account, result)| (account.output.as_str(), result.is_ok()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [("a.ics", true), ("b.ics", false), ("c.ics", true)]
+        );
+    }
+
+    #[test]
+    fn alias_arguments_are_parsed() {
+        assert_eq!(
+            parse_alias("高等数学A(1) = 高数").unwrap(),
+            ("高等数学A(1)".to_string(), "高数".to_string())
+        );
+        assert!(parse_alias("高数").is_err());
+        assert!(parse_alias("=高数").is_err());
+    }
+
+    #[test]
+    fn alarm_flags_control_valarm_count() {
+        let params = |alarm_before: Vec<u32>, no_alarm: bool| GenerateParams {
+            alarm_before,
+            no_alarm,
+            ..GenerateParams::new("mock".to_string(), "u".to_string(), String::new())
+        };
+        let start =
+            Semester::from_date_str("2025-09-08").unwrap().start_date + chrono::Duration::hours(8);
+        let response = CourseResponse {
+            courses: vec![Course {
+                name: "高等数学".to_string(),
+                start_time: start,
+                end_time: start + chrono::Duration::minutes(100),
+                ..Default::default()
+            }],
+            semester: Semester::from_date_str("2025-09-08").unwrap(),
+            generated_at: start,
+            warnings: Vec::new(),
+        };
+        let alarms = |params: &GenerateParams| {
+            let ics = IcsGenerator::new(alarm_options(params).unwrap())
+                .generate(&response)
+                .unwrap();
+            ics.matches("BEGIN:VALARM").count()
+        };
+
+        assert_eq!(alarms(&params(Vec::new(), false)), 1);
+        let multiple = params(vec![1440, 15], false);
+        assert_eq!(alarms(&multiple), 2);
+        assert_eq!(
+            alarm_options(&multiple).unwrap().reminders,
+            [ReminderSpec::minutes(1440), ReminderSpec::minutes(15)]
+        );
+        assert_eq!(alarms(&params(Vec::new(), true)), 0);
+        assert!(alarm_options(&params(vec![15], true)).is_err());
+    }
+
+    #[tokio::test]
+    async fn probe_reports_status_or_connection_error() {
+        use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let client = Client::new();
+        let status = probe_api_root(&client, &format!("http://{}/", addr))
+            .await
+            .unwrap();
+        assert_eq!(status, reqwest::StatusCode::NOT_FOUND);
+
+        // 端口已关闭时报告连接失败
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+        assert!(
+            probe_api_root(&client, &format!("http://{}/", closed_addr))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn location_list_json_round_trips() {
+        let manager = LocationManager::default();
+        let json = manager.export_to_json().unwrap();
+
+        let mut loaded = LocationManager::new();
+        loaded.load_from_json(&json).unwrap();
+
+        let mappings = manager.get_all_mappings();
+        assert_eq!(loaded.get_all_mappings().len(), mappings.len());
+        for (original, mapping) in mappings {
+            let restored = loaded.get_location_details(original).unwrap();
+            assert_eq!(restored.normalized, mapping.normalized);
+            assert_eq!(restored.building, mapping.building);
+            assert_eq!(restored.room, mapping.room);
+            assert_eq!(restored.campus, mapping.campus);
+        }
+    }
+
+    #[test]
+    fn lesson_times_file_is_validated() {
+        let dir = std::env::temp_dir().join(format!("cqupt-ics-lessons-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, lessons: &[[&str; 2]]| {
+            let path = dir.join(name);
+            fs::write(&path, serde_json::to_string(lessons).unwrap()).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let mut lessons: Vec<[&str; 2]> = (8..20).map(|_| ["08:00", "08:45"]).collect();
+        lessons[0] = ["07:50", "08:35"];
+
+        let schedule = load_lesson_times(&write("valid.json", &lessons)).unwrap();
+        assert_eq!(schedule.get(1), Some((7 * 60 + 50, 8 * 60 + 35)));
+        assert_eq!(schedule.get(12), Some((8 * 60, 8 * 60 + 45)));
+        assert_eq!(schedule.get(13), None);
+
+        lessons[3] = ["11:55", "11:15"];
+        let error = load_lesson_times(&write("reversed.json", &lessons)).unwrap_err();
+        assert!(error.to_string().contains("lesson 4"));
+        assert!(load_lesson_times(&write("short.json", &lessons[..11])).is_err());
+        assert!(load_lesson_times(&write("bad.json", &[["8点", "9点"]; 12])).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn filename_template_and_output_dir() {
+        let autumn = Semester::from_date_str("2025-09-08").unwrap();
+        assert_eq!(
+            render_template(DEFAULT_FILENAME_TEMPLATE, "2023000000", "redrock", &autumn),
+            "cqupt-schedule-2023000000-2025-09-08.ics"
+        );
+        let spring = Semester::from_date_str("2026-03-02").unwrap();
+        assert_eq!(
+            render_template("{provider}/{term}-{username}.ics", "u", "wecqupt", &spring),
+            "wecqupt/2025-2026-2-u.ics"
+        );
+
+        assert_eq!(
+            resolve_output_path(None, "a.ics").unwrap(),
+            PathBuf::from("a.ics")
+        );
+        let dir = std::env::temp_dir().join(format!("cqupt-ics-output-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let nested = dir.join("exports");
+        let path = resolve_output_path(nested.to_str(), "a.ics").unwrap();
+        assert!(nested.is_dir());
+        assert_eq!(path, nested.join("a.ics"));
+        let path = resolve_output_path(dir.to_str(), "wecqupt/a.ics").unwrap();
+        assert!(dir.join("wecqupt").is_dir());
+        assert_eq!(path, dir.join("wecqupt").join("a.ics"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
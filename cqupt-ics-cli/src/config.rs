@@ -0,0 +1,142 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// 未配置时的课程提醒时间（分钟）
+pub const DEFAULT_REMINDER_MINUTES: u32 = 15;
+
+/// 命令行默认配置，读取自 `~/.config/cqupt-ics/config.toml`
+///
+/// 命令行参数优先于配置文件。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CliConfig {
+    /// 默认数据provider
+    pub provider: Option<String>,
+    /// 默认提醒时间（分钟）
+    pub reminder_minutes: Option<u32>,
+    /// 日历名称模板，支持 {username} {provider} {date} {term} 占位符
+    pub calendar_name: Option<String>,
+    /// 默认输出目录
+    pub output_dir: Option<String>,
+}
+
+impl CliConfig {
+    /// 配置文件的默认位置
+    pub fn default_path() -> Result<PathBuf> {
+        #[cfg(target_os = "windows")]
+        let base = std::env::var_os("APPDATA").map(PathBuf::from);
+        #[cfg(not(target_os = "windows"))]
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+        base.map(|dir| dir.join("cqupt-ics").join("config.toml"))
+            .ok_or_else(|| anyhow::anyhow!("无法确定配置文件目录"))
+    }
+
+    /// 读取配置文件，文件不存在时返回默认配置
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("解析配置文件 {} 失败: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow::anyhow!(
+                "读取配置文件 {} 失败: {}",
+                path.display(),
+                e
+            )),
+        }
+    }
+
+    /// 从默认位置读取配置，无法确定配置目录（如未设置 HOME）时视为没有配置
+    pub fn load_default() -> Result<Self> {
+        match Self::default_path() {
+            Ok(path) => Self::load(&path),
+            Err(e) => {
+                tracing::debug!("{}，使用默认配置", e);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// 命令行未指定时使用配置中的 provider
+    pub fn provider(&self, flag: Option<String>) -> Result<String> {
+        flag.or_else(|| self.provider.clone())
+            .ok_or_else(|| anyhow::anyhow!("未指定provider，请使用 --provider 或在配置文件中设置"))
+    }
+
+    /// 命令行未指定时使用配置中的提醒时间
+    pub fn reminder_minutes(&self, flag: Option<u32>) -> u32 {
+        flag.or(self.reminder_minutes)
+            .unwrap_or(DEFAULT_REMINDER_MINUTES)
+    }
+
+    /// 命令行未指定时使用配置中的日历名称模板
+    pub fn calendar_name(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.calendar_name.clone())
+    }
+
+    /// 命令行未指定输出目录与输出文件时使用配置中的输出目录
+    pub fn output_dir(&self, flag: Option<String>, output: Option<&str>) -> Option<String> {
+        match output {
+            Some(_) => flag,
+            None => flag.or_else(|| self.output_dir.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_override_config_values() {
+        let dir = std::env::temp_dir().join(format!("cqupt-ics-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+        fs::write(
+            &path,
+            r#"
+provider = "redrock"
+reminder_minutes = 30
+calendar_name = "{username} {term}"
+output_dir = "exports"
+"#,
+        )
+        .unwrap();
+        let config = CliConfig::load(&path).unwrap();
+
+        // 未指定参数时使用配置
+        assert_eq!(config.provider(None).unwrap(), "redrock");
+        assert_eq!(config.reminder_minutes(None), 30);
+        assert_eq!(
+            config.calendar_name(None).as_deref(),
+            Some("{username} {term}")
+        );
+        assert_eq!(config.output_dir(None, None).as_deref(), Some("exports"));
+
+        // 命令行参数优先
+        assert_eq!(config.provider(Some("mock".into())).unwrap(), "mock");
+        assert_eq!(config.reminder_minutes(Some(5)), 5);
+        assert_eq!(
+            config.calendar_name(Some("课表".into())).as_deref(),
+            Some("课表")
+        );
+        assert_eq!(config.output_dir(None, Some("a.ics")), None);
+
+        // 文件不存在时使用默认值
+        let missing = CliConfig::load(&dir.join("missing.toml")).unwrap();
+        assert_eq!(missing, CliConfig::default());
+        assert!(missing.provider(None).is_err());
+        assert_eq!(missing.reminder_minutes(None), DEFAULT_REMINDER_MINUTES);
+
+        fs::write(&path, "unknown = 1\n").unwrap();
+        assert!(CliConfig::load(&path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,6 @@
 pub mod cache;
 pub mod commands;
+pub mod config;
 pub mod registry;
 
 pub use cache::FileCache;
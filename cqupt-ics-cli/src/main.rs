@@ -1,9 +1,13 @@
 mod cache;
 mod commands;
+mod config;
 mod registry;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use cqupt_ics_core::{
+    ColorBy, ContentFilter, DescriptionStyle, LocationDetail, ProviderOptions, SplitBy,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser)]
@@ -19,13 +23,15 @@ struct Cli {
     verbose: bool,
 }
 
+// 命令行参数只解析一次，不必为变体大小装箱
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// 获取课程表并生成ICS文件
     Generate {
-        /// 数据provider (jwzxdirect, redrock, wecqupt)
+        /// 数据provider (redrock, wecqupt, mock, file)，未指定时读取配置文件
         #[arg(short, long)]
-        provider: String,
+        provider: Option<String>,
 
         /// 用户名/学号
         #[arg(short, long)]
@@ -39,21 +45,158 @@ enum Commands {
         #[arg(short = 's', long)]
         start_date: Option<String>,
 
-        /// 输出文件路径
-        #[arg(short, long)]
+        /// 学期代码（格式：学年-学期，如 2024-1 为 2024-2025 学年秋季学期），按惯例推算开始日期
+        #[arg(long, conflicts_with = "start_date")]
+        term: Option<String>,
+
+        /// 输出文件路径（`-` 表示输出到标准输出）
+        #[arg(short, long, conflicts_with_all = ["output_dir", "filename_template"])]
         output: Option<String>,
 
-        /// 日历名称
+        /// 输出目录，不存在时自动创建
+        #[arg(long)]
+        output_dir: Option<String>,
+
+        /// 文件名模板，支持 {username} {provider} {date} {term} 占位符
+        #[arg(long, default_value = commands::DEFAULT_FILENAME_TEMPLATE)]
+        filename_template: String,
+
+        /// 日历名称，支持 {username} {provider} {date} {term} 占位符
         #[arg(long)]
         calendar_name: Option<String>,
 
+        /// 日历描述（X-WR-CALDESC）
+        #[arg(long)]
+        calendar_description: Option<String>,
+
+        /// 自定义 PRODID
+        #[arg(long)]
+        prodid: Option<String>,
+
         /// 是否包含教师信息
         #[arg(long, default_value = "true")]
         include_teacher: bool,
 
-        /// 提醒时间（分钟）
-        #[arg(long, default_value = "15")]
-        reminder_minutes: u32,
+        /// 课程描述样式 (full, compact, none)
+        #[arg(long, default_value = "full")]
+        description_style: DescriptionStyle,
+
+        /// 额外输出 HTML 格式的课程描述（X-ALT-DESC）
+        #[arg(long)]
+        html_description: bool,
+
+        /// 课程没有任何详情时不输出描述，而非 "暂无课程详情"
+        #[arg(long)]
+        omit_empty_description: bool,
+
+        /// 上课地点详细程度 (plain-name, name-with-geo, full)，非 Apple 客户端可用 plain-name
+        #[arg(long, default_value = "full")]
+        location_detail: LocationDetail,
+
+        /// 用 DURATION 代替 DTEND 表示课程时长
+        #[arg(long)]
+        use_duration: bool,
+
+        /// 事件颜色 (none, type, course-name)，course-name 为每门课分配固定颜色
+        #[arg(long, default_value = "none")]
+        color_by: ColorBy,
+
+        /// 课程名称别名，格式为 原始名称=显示名称，可重复指定；原始名称也可为前缀
+        #[arg(long = "alias")]
+        aliases: Vec<String>,
+
+        /// 只导出指定类型的事件 (exams, classes, custom)
+        #[arg(long, default_value = "all")]
+        only: ContentFilter,
+
+        /// 提醒时间（分钟），默认 15
+        #[arg(long)]
+        reminder_minutes: Option<u32>,
+
+        /// 考试提醒时间（分钟，可用逗号分隔多个），默认沿用课程提醒
+        #[arg(long, value_delimiter = ',')]
+        exam_reminder_minutes: Vec<u32>,
+
+        /// 课程提醒时间（分钟），可重复指定多个，如 --alarm-before 1440 --alarm-before 15
+        #[arg(long, conflicts_with = "reminder_minutes")]
+        alarm_before: Vec<u32>,
+
+        /// 不添加任何提醒
+        #[arg(long, conflicts_with_all = ["reminder_minutes", "exam_reminder_minutes", "alarm_before"])]
+        no_alarm: bool,
+
+        /// 节假日调休ICS文件路径
+        #[arg(long)]
+        holiday_ics: Option<String>,
+
+        /// 将放假日输出为全天事件
+        #[arg(long)]
+        holiday_events: bool,
+
+        /// 获取课程的超时时间（秒）
+        #[arg(long)]
+        timeout: Option<u32>,
+
+        /// 不获取考试安排（redrock 可少发一次请求）
+        #[arg(long)]
+        no_exams: bool,
+
+        /// 不获取自定义日程
+        #[arg(long)]
+        no_custom: bool,
+
+        /// 从另一个provider获取考试并合并（共用同一账号密码）
+        #[arg(long)]
+        exam_provider: Option<String>,
+
+        /// 自定义作息时间JSON文件，如 [["08:00","08:45"], ...]，共12节
+        #[arg(long)]
+        lesson_times: Option<String>,
+
+        /// 使用稳定 UID，并在课程变化时递增 SEQUENCE 以便订阅端更新
+        #[arg(long)]
+        stable_uids: bool,
+
+        /// 按分组拆分为多个日历文件 (type, course-type)，文件名追加分组名
+        #[arg(long, conflicts_with = "stable_uids")]
+        split_by: Option<SplitBy>,
+
+        /// 只导出该日期及之后的课程（YYYY-MM-DD 或 today）
+        #[arg(long)]
+        since: Option<String>,
+
+        /// 只导出该日期及之前的课程（YYYY-MM-DD 或 today）
+        #[arg(long)]
+        until: Option<String>,
+
+        /// 写出前解析生成的ICS，检查事件是否缺少必需属性
+        #[arg(long)]
+        validate: bool,
+    },
+
+    /// 按文件中的账号批量生成课程表
+    GenerateBatch {
+        /// 账号文件，每行为 provider,username,password,output，字段可用双引号包裹
+        #[arg(short, long)]
+        file: String,
+
+        /// 同时生成的账号数
+        #[arg(long, default_value = "4")]
+        concurrency: usize,
+    },
+
+    /// 读取已生成的ICS文件并重新应用节假日调休
+    ReapplyHolidays {
+        /// 输入ICS文件路径
+        input: String,
+
+        /// 输出文件路径（默认在输入文件名后追加 .reapplied，`-` 表示输出到标准输出）
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// 日历名称
+        #[arg(long)]
+        calendar_name: Option<String>,
 
         /// 节假日调休ICS文件路径
         #[arg(long)]
@@ -75,25 +218,80 @@ enum Commands {
         password: String,
     },
 
+    /// 查询下一节课
+    Next {
+        /// 数据provider
+        #[arg(short, long)]
+        provider: String,
+
+        /// 用户名/学号
+        #[arg(short, long)]
+        username: String,
+
+        /// 密码
+        #[arg(short = 'P', long)]
+        password: String,
+
+        /// 学期开始日期（格式：YYYY-MM-DD）
+        #[arg(short = 's', long)]
+        start_date: Option<String>,
+
+        /// 节假日调休ICS文件路径
+        #[arg(long)]
+        holiday_ics: Option<String>,
+    },
+
     /// 列出可用的数据provider
     Providers,
 
+    /// 诊断缓存、配置与接口连通性
+    Doctor,
+
     /// 位置管理相关命令
     Location {
         #[command(subcommand)]
         action: LocationCommands,
     },
+
+    /// 配置文件相关命令
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// 显示配置文件路径
+    Path,
+
+    /// 显示当前生效的配置
+    Show,
 }
 
 #[derive(Subcommand)]
 enum LocationCommands {
     /// 列出所有位置映射
-    List,
+    List {
+        /// 以JSON格式输出（与导出格式一致）
+        #[arg(long)]
+        json: bool,
+    },
 
     /// 标准化位置名称
     Normalize {
         /// 原始位置名称
         location: String,
+
+        /// 以JSON格式输出
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// 显示位置命中的地理规则
+    Explain {
+        /// 原始位置名称
+        location: String,
     },
 
     /// 从JSON文件导入位置映射
@@ -111,7 +309,6 @@ enum LocationCommands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    registry::init();
     let cli = Cli::parse();
 
     // 设置日志级别
@@ -122,7 +319,7 @@ async fn main() -> Result<()> {
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| format!("cqupt_ics_cli={}", log_level).into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
 
     match cli.command {
@@ -131,41 +328,128 @@ async fn main() -> Result<()> {
             username,
             password,
             start_date,
+            term,
             output,
+            output_dir,
+            filename_template,
             calendar_name,
+            calendar_description,
+            prodid,
             include_teacher,
+            description_style,
+            html_description,
+            omit_empty_description,
+            location_detail,
+            use_duration,
+            color_by,
+            aliases,
+            only,
             reminder_minutes,
+            exam_reminder_minutes,
+            alarm_before,
+            no_alarm,
             holiday_ics,
+            holiday_events,
+            timeout,
+            no_exams,
+            no_custom,
+            exam_provider,
+            lesson_times,
+            stable_uids,
+            split_by,
+            since,
+            until,
+            validate,
         } => {
+            let config = config::CliConfig::load_default()?;
             commands::generate_command(commands::GenerateParams {
-                provider_name: provider,
+                provider_name: config.provider(provider)?,
                 username,
                 password,
                 start_date,
+                term,
+                output_dir: config.output_dir(output_dir, output.as_deref()),
                 output,
-                calendar_name,
+                filename_template,
+                calendar_name: config.calendar_name(calendar_name),
+                calendar_description,
+                prodid,
                 include_teacher,
-                reminder_minutes,
+                description_style,
+                html_description,
+                omit_empty_description,
+                location_detail,
+                use_duration,
+                color_by,
+                aliases,
+                content_filter: only,
+                reminder_minutes: config.reminder_minutes(reminder_minutes),
+                exam_reminder_minutes,
+                alarm_before,
+                no_alarm,
                 holiday_ics,
+                holiday_events,
+                timeout,
+                provider_options: ProviderOptions {
+                    include_exams: !no_exams,
+                    include_custom: !no_custom,
+                    ..Default::default()
+                },
+                exam_provider,
+                lesson_times,
+                stable_uids,
+                split_by,
+                since,
+                until,
+                validate,
             })
             .await
         }
 
+        Commands::GenerateBatch { file, concurrency } => {
+            commands::generate_batch_command(&file, concurrency).await
+        }
+
+        Commands::ReapplyHolidays {
+            input,
+            output,
+            calendar_name,
+            holiday_ics,
+        } => commands::reapply_holidays_command(input, output, calendar_name, holiday_ics).await,
+
         Commands::Validate {
             provider,
             username,
             password,
         } => commands::validate_command(provider, username, password).await,
 
+        Commands::Next {
+            provider,
+            username,
+            password,
+            start_date,
+            holiday_ics,
+        } => commands::next_command(provider, username, password, start_date, holiday_ics).await,
+
+        Commands::Doctor => commands::doctor_command().await,
+
         Commands::Providers => commands::providers_command().await,
 
         Commands::Location { action } => match action {
-            LocationCommands::List => commands::location_list_command().await,
-            LocationCommands::Normalize { location } => {
-                commands::location_normalize_command(location).await
+            LocationCommands::List { json } => commands::location_list_command(json).await,
+            LocationCommands::Normalize { location, json } => {
+                commands::location_normalize_command(location, json).await
+            }
+            LocationCommands::Explain { location } => {
+                commands::location_explain_command(location).await
             }
             LocationCommands::Import { file } => commands::location_import_command(file).await,
             LocationCommands::Export { file } => commands::location_export_command(file).await,
         },
+
+        Commands::Config { action } => match action {
+            ConfigCommands::Path => commands::config_path_command(),
+            ConfigCommands::Show => commands::config_show_command(),
+        },
     }
 }
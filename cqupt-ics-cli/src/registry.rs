@@ -1,47 +1,74 @@
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
-use cqupt_ics_core::{
-    cache::CacheManager,
-    prelude::{redrock::RedrockProvider, wecqupt::WecquptProvider, *},
-};
+use anyhow::Result;
+use cqupt_ics_core::{prelude::*, providers::file::FileLookup};
 
-use crate::cache::FileCache;
+use crate::cache::{CacheKey, FileCache};
 
 pub static REGISTRY: OnceLock<ProviderRegistry> = OnceLock::new();
 
-pub(crate) fn init() {
-    let mut p = ProviderRegistry::new();
-    let file_cache = FileCache::with_default_dir("cqupt-ics").unwrap();
-    p.register(
-        Wrapper::new(
-            RedrockProvider::new(),
-            CacheManager::new(file_cache.clone()),
-        )
-        .into_static(),
-    );
-
-    p.register(
-        Wrapper::new(
-            WecquptProvider::new(),
-            CacheManager::new(file_cache.clone()),
-        )
-        .into_static(),
-    );
-
+/// 初始化 provider 注册表，缓存目录或加密密钥不可用时返回错误
+pub(crate) fn init() -> Result<()> {
+    let mut file_cache = FileCache::with_default_dir("cqupt-ics")
+        .map_err(|e| anyhow::anyhow!("初始化缓存失败: {}", e))?;
+    // 可选的缓存加密：优先使用口令，其次使用本机密钥
+    if let Ok(passphrase) = std::env::var("CQUPT_ICS_CACHE_PASSPHRASE") {
+        let key = CacheKey::from_passphrase(&passphrase)
+            .map_err(|e| anyhow::anyhow!("生成缓存加密密钥失败: {}", e))?;
+        file_cache = file_cache.with_encryption(key);
+    } else if std::env::var_os("CQUPT_ICS_CACHE_ENCRYPT").is_some() {
+        file_cache = file_cache
+            .with_machine_key()
+            .map_err(|e| anyhow::anyhow!("读取本机缓存密钥失败: {}", e))?;
+    }
+    // 定时任务可设置等待宵禁结束的最长分钟数
+    let curfew_wait = std::env::var("CQUPT_ICS_CURFEW_WAIT")
+        .ok()
+        .and_then(|minutes| minutes.parse::<u64>().ok())
+        .map(|minutes| std::time::Duration::from_secs(minutes * 60));
+    let curfew_window = std::env::var("CQUPT_ICS_CURFEW_WINDOW")
+        .ok()
+        .map(|window| {
+            window
+                .parse()
+                .map_err(|e| anyhow::anyhow!("CQUPT_ICS_CURFEW_WINDOW 无效: {}", e))
+        })
+        .transpose()?;
+    // 命令行在本地运行：开启演示用的 mock，file provider 的用户名即文件路径
+    let options = RegistryOptions {
+        include_mock: true,
+        file_provider: Some(FileLookup::Username),
+        curfew_wait,
+        curfew_window,
+        ..Default::default()
+    };
     REGISTRY
-        .set(p)
+        .set(build_registry(file_cache, options).expect("default registry options are valid"))
         .unwrap_or_else(|_| panic!("Failed to initialize provider registry"));
+    Ok(())
+}
+
+/// 首次使用时初始化注册表，使不依赖 provider 的命令不受缓存等初始化错误影响
+fn registry() -> Result<&'static ProviderRegistry> {
+    if REGISTRY.get().is_none() {
+        init()?;
+    }
+    Ok(REGISTRY.get().unwrap())
 }
 
 pub(crate) fn get_provider(
     name: &str,
-) -> Option<&'static dyn cqupt_ics_core::providers::ProviderWrapper> {
-    if REGISTRY.get().is_none() {
-        init();
+) -> Option<Arc<dyn cqupt_ics_core::providers::ProviderWrapper>> {
+    match registry() {
+        Ok(registry) => registry.get_provider(name),
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
     }
-    REGISTRY.get().unwrap().get_provider(name)
 }
 
-pub(crate) fn list_providers() -> impl Iterator<Item = (&'static str, &'static str)> {
-    REGISTRY.get().unwrap().list_providers()
+pub(crate) fn list_providers()
+-> Result<impl Iterator<Item = &'static dyn cqupt_ics_core::providers::ProviderWrapper>> {
+    Ok(registry()?.providers())
 }
@@ -45,6 +45,47 @@ pub trait Cache: CacheBackend {
 /// 为所有实现了 CacheBackend 的类型自动实现 Cache
 impl<T: CacheBackend> Cache for T {}
 
+/// 仅用于测试的内存缓存，忽略过期时间；克隆后共享同一份数据
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Default, Clone)]
+pub struct MemoryCache {
+    entries: std::sync::Arc<std::sync::Mutex<std::collections::HashMap<String, Vec<u8>>>>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn set_raw(&self, key: &str, value: &[u8], _ttl: Duration) -> Result<()> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.entries.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.entries.lock().unwrap().contains_key(key))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.entries.lock().unwrap().clear();
+        Ok(())
+    }
+
+    async fn expire(&self, _key: &str, _ttl: Duration) -> Result<()> {
+        Ok(())
+    }
+}
+
 /// 缓存管理器，提供统一的缓存接口
 #[derive(Clone)]
 pub struct CacheManager<C: CacheBackend> {
@@ -1,5 +1,22 @@
+use serde::Serialize;
 use thiserror::Error;
 
+/// 认证失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthFailReason {
+    /// 账号或密码错误
+    BadCredentials,
+    /// 账号被锁定或登录过于频繁
+    Locked,
+    /// 需要验证码
+    CaptchaRequired,
+    /// 凭据已过期
+    Expired,
+    /// 其他原因
+    Unknown,
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("HTTP request failed: {0}")]
@@ -14,6 +31,10 @@ pub enum Error {
     #[error("Provider error: {provider} - {message}")]
     Provider { provider: String, message: String },
 
+    /// 上游响应无法按预期结构解析，通常意味着接口已变更
+    #[error("Failed to parse {provider} response: {context}")]
+    Deserialize { provider: String, context: String },
+
     #[error("Invalid configuration: {0}")]
     Config(String),
 
@@ -23,8 +44,11 @@ pub enum Error {
     #[error("Location not found: {0}")]
     LocationNotFound(String),
 
-    #[error("Authentication failed for provider: {0}")]
-    Authentication(String),
+    #[error("Authentication failed for provider: {message}")]
+    Authentication {
+        reason: AuthFailReason,
+        message: String,
+    },
 
     #[error("Network timeout")]
     Timeout,
@@ -39,4 +63,14 @@ pub enum Error {
     Internal(String),
 }
 
+impl Error {
+    /// 构造认证失败错误
+    pub fn authentication(reason: AuthFailReason, message: impl Into<String>) -> Self {
+        Self::Authentication {
+            reason,
+            message: message.into(),
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
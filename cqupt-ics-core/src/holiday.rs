@@ -4,10 +4,14 @@ use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
     fs::File,
     io::{BufReader, Read},
+    ops::RangeInclusive,
     path::Path,
 };
 
-use crate::{Course, CourseResponse, Error, Result, Semester};
+use crate::{Course, CourseResponse, Error, Result, Semester, cache::CacheBackend};
+
+/// 默认的节假日调休日历地址
+pub const DEFAULT_HOLIDAY_URL: &str = "https://calendars.icloud.com/holidays/cn_zh.ics";
 
 /// 节假日调休信息
 #[derive(Debug, Clone)]
@@ -15,6 +19,19 @@ pub struct HolidayCalendar {
     rest_days: BTreeSet<NaiveDate>,
     rest_to_makeup: HashMap<NaiveDate, NaiveDate>,
     makeup_days: BTreeSet<NaiveDate>,
+    /// 放假日对应的节日名称
+    rest_names: BTreeMap<NaiveDate, String>,
+}
+
+/// 放假时段，用于生成全天事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HolidayEvent {
+    /// 节日名称
+    pub name: String,
+    /// 开始日期
+    pub start: NaiveDate,
+    /// 结束日期（含）
+    pub end: NaiveDate,
 }
 
 impl HolidayCalendar {
@@ -37,8 +54,54 @@ impl HolidayCalendar {
         Self::from_reader(cursor)
     }
 
+    /// 从网络加载节假日ICS，原始内容按地址缓存 `ttl` 时长
+    ///
+    /// 缓存命中时不发起请求；下载或读取失败返回 `Error::Provider`。
+    pub async fn from_url(
+        client: &reqwest::Client,
+        url: &str,
+        cache: &dyn CacheBackend,
+        ttl: std::time::Duration,
+    ) -> Result<Self> {
+        let cache_key = format!("holiday:{}", url);
+        if let Some(bytes) = cache.get_raw(&cache_key).await? {
+            tracing::debug!("命中节假日调休缓存");
+            return Self::from_bytes(&bytes);
+        }
+
+        let provider_error = |message: String| Error::Provider {
+            provider: "holiday".to_string(),
+            message,
+        };
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| provider_error(format!("请求节假日ICS失败: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(provider_error(format!(
+                "获取节假日ICS失败: HTTP {}",
+                response.status()
+            )));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| provider_error(format!("读取节假日ICS内容失败: {}", e)))?;
+
+        // 先解析再缓存，避免缓存无效内容
+        let calendar = Self::from_bytes(&bytes)?;
+        cache.set_raw(&cache_key, &bytes, ttl).await?;
+        Ok(calendar)
+    }
+
     /// 从读取器中加载节假日ICS
     pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        Self::from_reader_with(reader, &EventClassifier::default())
+    }
+
+    /// 从读取器中加载节假日ICS，使用自定义的事件分类规则
+    pub fn from_reader_with<R: Read>(reader: R, classifier: &EventClassifier) -> Result<Self> {
         let parser = IcalParser::new(BufReader::new(reader));
         let mut groups: BTreeMap<String, HolidayGroup> = BTreeMap::new();
 
@@ -47,7 +110,7 @@ impl HolidayCalendar {
                 calendar.map_err(|err| Error::Config(format!("节假日ICS解析失败: {}", err)))?;
 
             for event in calendar.events {
-                let Some(kind) = classify_event(&event) else {
+                let Some(kind) = classifier.classify(&event) else {
                     continue;
                 };
 
@@ -63,7 +126,12 @@ impl HolidayCalendar {
 
                 let entry = groups.entry(key).or_default();
                 match kind {
-                    HolidayEventKind::Rest => entry.rest.extend(dates),
+                    HolidayEventKind::Rest => {
+                        if entry.name.is_none() {
+                            entry.name = event_property(&event, "SUMMARY").map(holiday_name);
+                        }
+                        entry.rest.extend(dates)
+                    }
                     HolidayEventKind::Makeup => entry.makeup.extend(dates),
                 }
             }
@@ -77,6 +145,35 @@ impl HolidayCalendar {
         self.apply_to_courses(&mut response.courses, &response.semester);
     }
 
+    /// 按年份范围列出放假时段
+    ///
+    /// 连续且名称相同的放假日合并为一个时段。
+    pub fn to_events(&self, years: RangeInclusive<i32>) -> Vec<HolidayEvent> {
+        let mut events: Vec<HolidayEvent> = Vec::new();
+        for &date in self
+            .rest_days
+            .iter()
+            .filter(|date| years.contains(&date.year()))
+        {
+            let name = self
+                .rest_names
+                .get(&date)
+                .map(String::as_str)
+                .unwrap_or("放假");
+            match events.last_mut() {
+                Some(last) if last.name == name && last.end.succ_opt() == Some(date) => {
+                    last.end = date;
+                }
+                _ => events.push(HolidayEvent {
+                    name: name.to_string(),
+                    start: date,
+                    end: date,
+                }),
+            }
+        }
+        events
+    }
+
     /// 是否为放假日
     pub fn is_rest_day(&self, date: NaiveDate) -> bool {
         self.rest_days.contains(&date)
@@ -104,8 +201,10 @@ impl HolidayCalendar {
     }
 
     /// 将节假日调整应用到课程列表
+    ///
+    /// 已包含调休补课的列表视为处理过，重复调用不会再生成补课。
     pub fn apply_to_courses(&self, courses: &mut Vec<Course>, semester: &Semester) {
-        if courses.is_empty() {
+        if courses.is_empty() || courses.iter().any(|course| course.is_makeup) {
             return;
         }
         let len = courses.len();
@@ -125,7 +224,7 @@ impl HolidayCalendar {
             let original_end: DateTime<FixedOffset> = courses[i].end_time;
             let mut off_weeks = vec![];
             for week in weeks.iter().copied() {
-                let occurrence_date: NaiveDate = occurrence_date_for(semester, week, weekday);
+                let occurrence_date = semester.occurrence_date(week, weekday).date_naive();
                 if self.rest_days.contains(&occurrence_date) {
                     if let Some(makeup_date) = self.rest_to_makeup.get(&occurrence_date) {
                         let occurrence_start =
@@ -160,6 +259,7 @@ impl HolidayCalendar {
         let mut rest_days = BTreeSet::new();
         let mut rest_to_makeup = HashMap::new();
         let mut makeup_days = BTreeSet::new();
+        let mut rest_names = BTreeMap::new();
 
         for group in groups.values() {
             if group.rest.is_empty() {
@@ -167,6 +267,11 @@ impl HolidayCalendar {
             }
 
             rest_days.extend(&group.rest);
+            if let Some(name) = &group.name {
+                for date in &group.rest {
+                    rest_names.entry(*date).or_insert_with(|| name.clone());
+                }
+            }
             makeup_days.extend(&group.makeup);
 
             if group.makeup.is_empty() {
@@ -275,42 +380,116 @@ impl HolidayCalendar {
             rest_days,
             rest_to_makeup,
             makeup_days,
+            rest_names,
         })
     }
 }
 
 #[derive(Default)]
 struct HolidayGroup {
+    name: Option<String>,
     rest: BTreeSet<NaiveDate>,
     makeup: BTreeSet<NaiveDate>,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum HolidayEventKind {
+/// 从放假事件标题中提取节日名称，例如 "国庆节（休）" -> "国庆节"
+fn holiday_name(summary: &str) -> String {
+    let name = summary
+        .trim()
+        .trim_end_matches(['(', ')', '（', '）', '休', ' ', '\t'])
+        .trim_end_matches("放假")
+        .trim();
+    if name.is_empty() {
+        "放假".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// 节假日ICS事件的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HolidayEventKind {
+    /// 放假
     Rest,
+    /// 调休上班
     Makeup,
 }
 
-fn classify_event(event: &IcalEvent) -> Option<HolidayEventKind> {
-    if let Some(kind) = event_property(event, "X-APPLE-SPECIAL-DAY") {
-        return match kind {
-            "WORK-HOLIDAY" => Some(HolidayEventKind::Rest),
-            "ALTERNATE-WORKDAY" => Some(HolidayEventKind::Makeup),
-            _ => None,
-        };
+/// 节假日ICS事件分类规则
+///
+/// 优先使用 `X-APPLE-SPECIAL-DAY` 属性，没有时按标题关键词判断。
+/// 标题同时命中两类关键词时（如 "上班(调休)"）视为调休上班。
+#[derive(Debug, Clone)]
+pub struct EventClassifier {
+    rest_keywords: Vec<String>,
+    makeup_keywords: Vec<String>,
+    special_days: HashMap<String, HolidayEventKind>,
+}
+
+impl Default for EventClassifier {
+    fn default() -> Self {
+        Self {
+            rest_keywords: vec!["休".to_string(), "放假".to_string()],
+            makeup_keywords: vec!["班".to_string()],
+            special_days: HashMap::from([
+                ("WORK-HOLIDAY".to_string(), HolidayEventKind::Rest),
+                ("ALTERNATE-WORKDAY".to_string(), HolidayEventKind::Makeup),
+            ]),
+        }
     }
+}
 
-    let summary = event_property(event, "SUMMARY")?;
-    let normalized = summary.replace([' ', '\t'], "");
-    if normalized.contains('休') || normalized.contains("放假") {
-        return Some(HolidayEventKind::Rest);
+impl EventClassifier {
+    /// 替换表示放假的标题关键词
+    pub fn with_rest_keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rest_keywords = keywords.into_iter().map(Into::into).collect();
+        self
     }
-    if normalized.contains('班') || normalized.contains("调休") || normalized.contains("上班")
+
+    /// 替换表示调休上班的标题关键词
+    pub fn with_makeup_keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
     {
-        return Some(HolidayEventKind::Makeup);
+        self.makeup_keywords = keywords.into_iter().map(Into::into).collect();
+        self
     }
 
-    None
+    /// 添加或覆盖 `X-APPLE-SPECIAL-DAY` 取值对应的类别
+    pub fn with_special_day(mut self, value: impl Into<String>, kind: HolidayEventKind) -> Self {
+        self.special_days.insert(value.into(), kind);
+        self
+    }
+
+    /// 判断事件类别，无法识别时返回 `None`
+    pub fn classify(&self, event: &IcalEvent) -> Option<HolidayEventKind> {
+        if let Some(value) = event_property(event, "X-APPLE-SPECIAL-DAY") {
+            return self.special_days.get(value).copied();
+        }
+        self.classify_summary(event_property(event, "SUMMARY")?)
+    }
+
+    /// 按标题关键词判断事件类别
+    pub fn classify_summary(&self, summary: &str) -> Option<HolidayEventKind> {
+        let normalized = summary.replace([' ', '\t'], "");
+        let matches = |keywords: &[String]| {
+            keywords
+                .iter()
+                .any(|keyword| normalized.contains(keyword.as_str()))
+        };
+        if matches(&self.makeup_keywords) {
+            Some(HolidayEventKind::Makeup)
+        } else if matches(&self.rest_keywords) {
+            Some(HolidayEventKind::Rest)
+        } else {
+            None
+        }
+    }
 }
 
 fn extract_event_dates(event: &IcalEvent) -> Result<Vec<NaiveDate>> {
@@ -367,17 +546,6 @@ fn parse_date(value: &str) -> std::result::Result<NaiveDate, chrono::ParseError>
         .or_else(|_| DateTime::parse_from_rfc3339(value).map(|dt| dt.date_naive()))
 }
 
-fn occurrence_date_for(semester: &Semester, week: u32, weekday: u32) -> NaiveDate {
-    let week_start = semester
-        .start_date
-        .checked_add_signed(Duration::weeks((week.saturating_sub(1)) as i64))
-        .unwrap_or(semester.start_date);
-    let date = week_start
-        .checked_add_signed(Duration::days((weekday.saturating_sub(1)) as i64))
-        .unwrap_or(week_start);
-    date.date_naive()
-}
-
 fn shift_weeks(
     base: DateTime<FixedOffset>,
     target_week: u32,
@@ -413,6 +581,7 @@ fn create_makeup_course(
         _ => Some(note),
     };
     course.raw_week = Some(format!("调休补课（{} → {}）", rest_fmt, makeup_fmt));
+    course.is_makeup = true;
 
     course
 }
@@ -442,6 +611,7 @@ fn handle_single_occurrence_course(
             date.format("%Y-%m-%d"),
             makeup_date.format("%Y-%m-%d")
         ));
+        moved.is_makeup = true;
         courses.push(moved);
     }
 }
@@ -503,6 +673,7 @@ fn assign_makeup(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::cache::MemoryCache;
     use chrono::TimeZone;
 
     fn load_calendar() -> HolidayCalendar {
@@ -557,10 +728,11 @@ mod tests {
         assert_eq!(calendar.rest_for_makeup(makeup_jan26), Some(rest_feb03));
         assert_eq!(calendar.rest_for_makeup(makeup_feb08), Some(rest_feb04));
 
-        let tz = FixedOffset::east_opt(8 * 3600).unwrap();
+        let tz = crate::CQUPT_TZ;
         let semester_start = tz.with_ymd_and_hms(2025, 1, 6, 0, 0, 0).unwrap();
         let semester = Semester {
             start_date: semester_start,
+            weeks_total: None,
         };
 
         let mut response = CourseResponse {
@@ -584,6 +756,7 @@ mod tests {
             ],
             semester: semester.clone(),
             generated_at: tz.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            warnings: Vec::new(),
         };
 
         calendar.apply_to_response(&mut response);
@@ -598,13 +771,7 @@ mod tests {
         let makeup_monday = response
             .courses
             .iter()
-            .find(|course| {
-                course.name == "软件工程导论"
-                    && course
-                        .raw_week
-                        .as_deref()
-                        .is_some_and(|raw| raw.contains("调休补课"))
-            })
+            .find(|course| course.name == "软件工程导论" && course.is_makeup)
             .expect("missing monday makeup course");
         assert_eq!(
             makeup_monday.start_time.date_naive(),
@@ -621,17 +788,251 @@ mod tests {
         let makeup_tuesday = response
             .courses
             .iter()
-            .find(|course| {
-                course.name == "操作系统"
-                    && course
-                        .raw_week
-                        .as_deref()
-                        .is_some_and(|raw| raw.contains("调休补课"))
-            })
+            .find(|course| course.name == "操作系统" && course.is_makeup)
             .expect("missing tuesday makeup course");
         assert_eq!(
             makeup_tuesday.start_time.date_naive(),
             NaiveDate::from_ymd_opt(2025, 2, 8).unwrap()
         );
     }
+
+    const NATIONAL_DAY_ICS: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VEVENT\r\n\
+UID:national-day-2025\r\n\
+DTSTART;VALUE=DATE:20251001\r\n\
+DTEND;VALUE=DATE:20251009\r\n\
+SUMMARY:国庆节、中秋节（休）\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:national-day-2025-work\r\n\
+DTSTART;VALUE=DATE:20250928\r\n\
+DTEND;VALUE=DATE:20250929\r\n\
+SUMMARY:国庆节（班）\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:new-year-2026\r\n\
+DTSTART;VALUE=DATE:20260101\r\n\
+DTEND;VALUE=DATE:20260102\r\n\
+SUMMARY:元旦 休\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn classify_holiday_summaries() {
+        let classifier = EventClassifier::default();
+        let kind = |summary| classifier.classify_summary(summary);
+        assert_eq!(kind("国庆节放假"), Some(HolidayEventKind::Rest));
+        assert_eq!(kind("国庆节（休）"), Some(HolidayEventKind::Rest));
+        assert_eq!(kind("元旦 休"), Some(HolidayEventKind::Rest));
+        assert_eq!(kind("国庆节（班）"), Some(HolidayEventKind::Makeup));
+        // 同时含 "休" 与 "班" 时按调休上班处理
+        assert_eq!(kind("上班(调休)"), Some(HolidayEventKind::Makeup));
+        assert_eq!(kind("国庆节放假调休"), Some(HolidayEventKind::Rest));
+        assert_eq!(kind("清明节"), None);
+        assert_eq!(kind(""), None);
+
+        let custom = EventClassifier::default().with_rest_keywords(["假期"]);
+        assert_eq!(
+            custom.classify_summary("劳动节假期"),
+            Some(HolidayEventKind::Rest)
+        );
+        assert_eq!(custom.classify_summary("劳动节放假"), None);
+    }
+
+    #[test]
+    fn special_day_property_takes_precedence() {
+        use ical::property::Property;
+
+        let event = |special_day: &str| {
+            let mut event = IcalEvent::new();
+            for (name, value) in [
+                ("SUMMARY", "国庆节 休"),
+                ("X-APPLE-SPECIAL-DAY", special_day),
+            ] {
+                event.properties.push(Property {
+                    name: name.to_string(),
+                    params: None,
+                    value: Some(value.to_string()),
+                });
+            }
+            event
+        };
+
+        let classifier = EventClassifier::default();
+        assert_eq!(
+            classifier.classify(&event("ALTERNATE-WORKDAY")),
+            Some(HolidayEventKind::Makeup)
+        );
+        assert_eq!(classifier.classify(&event("OTHER")), None);
+        assert_eq!(
+            classifier
+                .with_special_day("OTHER", HolidayEventKind::Rest)
+                .classify(&event("OTHER")),
+            Some(HolidayEventKind::Rest)
+        );
+    }
+
+    #[test]
+    fn holiday_events_are_merged_blocks() {
+        let calendar = HolidayCalendar::from_bytes(NATIONAL_DAY_ICS).unwrap();
+
+        let events = calendar.to_events(2025..=2026);
+        assert_eq!(
+            events,
+            vec![
+                HolidayEvent {
+                    name: "国庆节、中秋节".to_string(),
+                    start: NaiveDate::from_ymd_opt(2025, 10, 1).unwrap(),
+                    end: NaiveDate::from_ymd_opt(2025, 10, 8).unwrap(),
+                },
+                HolidayEvent {
+                    name: "元旦".to_string(),
+                    start: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                    end: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                },
+            ]
+        );
+        assert_eq!(calendar.to_events(2026..=2026).len(), 1);
+    }
+
+    #[test]
+    fn applying_twice_is_a_no_op() {
+        let calendar = HolidayCalendar::from_bytes(
+            "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+X-APPLE-UNIVERSAL-ID:national-day\r\n\
+DTSTART;VALUE=DATE:20251001\r\n\
+DTEND;VALUE=DATE:20251009\r\n\
+SUMMARY:国庆节 休\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+X-APPLE-UNIVERSAL-ID:national-day\r\n\
+DTSTART;VALUE=DATE:20250928\r\n\
+DTEND;VALUE=DATE:20250929\r\n\
+SUMMARY:国庆节 班\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n",
+        )
+        .unwrap();
+        let tz = crate::CQUPT_TZ;
+        let courses = (1..=5)
+            .map(|weekday| {
+                let start_time = tz.with_ymd_and_hms(2025, 9, 7 + weekday, 8, 0, 0).unwrap();
+                Course {
+                    name: format!("课程{weekday}"),
+                    start_time,
+                    end_time: start_time + Duration::minutes(90),
+                    weeks: Some((1..=8).collect()),
+                    weekday: Some(weekday),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let mut response = CourseResponse {
+            courses,
+            semester: Semester {
+                start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+                weeks_total: None,
+            },
+            generated_at: tz.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(),
+            warnings: Vec::new(),
+        };
+
+        calendar.apply_to_response(&mut response);
+        let once = response.clone();
+        assert!(once.courses.iter().any(|course| course.is_makeup));
+
+        calendar.apply_to_response(&mut response);
+        assert_eq!(response.courses.len(), once.courses.len());
+        for (twice, once) in response.courses.iter().zip(&once.courses) {
+            assert_eq!(twice.off_weeks, once.off_weeks);
+            assert_eq!(twice.start_time, once.start_time);
+        }
+    }
+
+    #[tokio::test]
+    async fn from_url_caches_downloaded_calendar() {
+        use wiremock::{Mock, MockServer, ResponseTemplate, matchers::method};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(NATIONAL_DAY_ICS))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let client = reqwest::Client::new();
+        let cache = MemoryCache::default();
+        let url = format!("{}/holidays.ics", server.uri());
+        let ttl = std::time::Duration::from_secs(60);
+
+        for _ in 0..2 {
+            let calendar = HolidayCalendar::from_url(&client, &url, &cache, ttl)
+                .await
+                .unwrap();
+            assert!(calendar.is_rest_day(NaiveDate::from_ymd_opt(2025, 10, 1).unwrap()));
+        }
+        assert!(cache.exists(&format!("holiday:{}", url)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn from_url_reports_http_errors() {
+        use wiremock::{Mock, MockServer, ResponseTemplate, matchers::method};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let cache = MemoryCache::default();
+
+        let result = HolidayCalendar::from_url(
+            &reqwest::Client::new(),
+            &server.uri(),
+            &cache,
+            std::time::Duration::from_secs(60),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Provider { .. })));
+        assert!(
+            !cache
+                .exists(&format!("holiday:{}", server.uri()))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn national_day_all_day_event_is_emitted() {
+        use crate::{IcsOptions, ics::IcsGenerator};
+
+        let calendar = HolidayCalendar::from_bytes(NATIONAL_DAY_ICS).unwrap();
+        let tz = crate::CQUPT_TZ;
+        let response = CourseResponse {
+            courses: Vec::new(),
+            semester: Semester {
+                start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+                weeks_total: None,
+            },
+            generated_at: tz.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(),
+            warnings: Vec::new(),
+        };
+        let generator = |include_holiday_events| {
+            IcsGenerator::new(IcsOptions {
+                include_holiday_events,
+                ..Default::default()
+            })
+            .with_holiday_events(calendar.to_events(2025..=2025))
+        };
+
+        let ics = generator(true).generate(&response).unwrap();
+        assert!(ics.contains(
+            "DTSTART;VALUE=DATE:20251001\r\n\
+             DTEND;VALUE=DATE:20251009\r\n\
+             SUMMARY:国庆节、中秋节\r\n"
+        ));
+
+        let ics = generator(false).generate(&response).unwrap();
+        assert!(!ics.contains("VALUE=DATE"));
+    }
 }
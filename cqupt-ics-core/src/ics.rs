@@ -1,14 +1,100 @@
 use crate::{
-    Course, CourseResponse, Error, IcsOptions, RecurrenceRule, Result, location::LocationManager,
+    ColorBy, ContentFilter, Course, CourseResponse, DescriptionStyle, Error, IcsOptions,
+    RecurrenceRule, Result, Semester, SplitBy,
+    holiday::HolidayEvent,
+    join_teachers,
+    location::{LocationCache, LocationManager},
 };
-use chrono::{DateTime, FixedOffset, Utc};
-use std::borrow::Cow;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use ical::parser::ical::{IcalParser, component::IcalEvent};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::HashMap, io::Write};
 use uuid::Uuid;
 
+/// 默认的 PRODID
+pub const DEFAULT_PRODID: &str = "-//CQUPT ICS//CQUPT Course Calendar//CN";
+
+/// 课程没有任何详情时的描述占位
+const EMPTY_CLASS_DESCRIPTION: &str = "暂无课程详情";
+
+/// 调休补课事件使用的 CATEGORIES 值
+const MAKEUP_CATEGORY: &str = "调休补课";
+
+/// 事件配色：CSS3 颜色名（COLOR 只接受颜色名）
+const EVENT_PALETTE: [&str; 16] = [
+    "steelblue",
+    "seagreen",
+    "coral",
+    "goldenrod",
+    "mediumpurple",
+    "cadetblue",
+    "indianred",
+    "olivedrab",
+    "darkcyan",
+    "orchid",
+    "peru",
+    "slateblue",
+    "lightseagreen",
+    "palevioletred",
+    "darkkhaki",
+    "royalblue",
+];
+
+/// BYDAY 使用的星期缩写，下标 0 对应星期一
+const ICS_WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+/// 将 1-7（1=星期一）转换为 BYDAY 缩写，超出范围时返回 [`Error::IcsGeneration`]
+fn ics_weekday(day: u32) -> Result<&'static str> {
+    day.checked_sub(1)
+        .and_then(|i| ICS_WEEKDAYS.get(i as usize))
+        .copied()
+        .ok_or_else(|| Error::IcsGeneration(format!("Invalid weekday: {} (expected 1-7)", day)))
+}
+
+/// 事件的修订序号记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct SequenceEntry {
+    sequence: u32,
+    digest: u64,
+}
+
+/// 按稳定 UID 记录的事件 SEQUENCE
+///
+/// 事件内容与上次生成不同时序号加一，订阅端据此更新已导入的事件。
+/// 可序列化后持久化到缓存，在多次生成之间保持。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventSequences {
+    entries: HashMap<String, SequenceEntry>,
+}
+
+impl EventSequences {
+    /// 记录事件内容摘要并返回当前序号
+    fn bump(&mut self, uid: &str, digest: u64) -> u32 {
+        let entry = self
+            .entries
+            .entry(uid.to_string())
+            .or_insert(SequenceEntry {
+                sequence: 0,
+                digest,
+            });
+        if entry.digest != digest {
+            entry.sequence += 1;
+            entry.digest = digest;
+        }
+        entry.sequence
+    }
+
+    /// 查询事件当前序号
+    pub fn get(&self, uid: &str) -> Option<u32> {
+        self.entries.get(uid).map(|entry| entry.sequence)
+    }
+}
+
 /// ICS日历生成器
 pub struct IcsGenerator {
     options: IcsOptions,
     location_manager: LocationManager,
+    holiday_events: Vec<HolidayEvent>,
 }
 
 impl IcsGenerator {
@@ -16,85 +102,394 @@ impl IcsGenerator {
         Self {
             options,
             location_manager: LocationManager::default(),
+            holiday_events: Vec::new(),
         }
     }
 
+    /// 设置放假时段，`include_holiday_events` 开启时输出为全天事件
+    pub fn with_holiday_events(mut self, events: Vec<HolidayEvent>) -> Self {
+        self.holiday_events = events;
+        self
+    }
+
     /// 生成ICS日历内容
     pub fn generate(&self, response: &CourseResponse) -> Result<String> {
+        self.generate_inner(response, None)
+    }
+
+    /// 按分组方式拆分为多个完整的ICS日历，键为分组名
+    ///
+    /// 每个日历的 X-WR-CALNAME 追加分组名称；没有事件的分组不输出。
+    /// 开启 `include_holiday_events` 时放假日单独放在 `holidays` 日历中。
+    pub fn generate_split(
+        &self,
+        response: &CourseResponse,
+        split_by: SplitBy,
+    ) -> Result<HashMap<String, String>> {
+        let mut groups: HashMap<String, Vec<Course>> = HashMap::new();
+        for course in &response.courses {
+            groups
+                .entry(split_by.key(course))
+                .or_default()
+                .push(course.clone());
+        }
+
+        let weeks_total = response.semester.weeks_total;
+        let dtstamp = self.dtstamp(response);
+        let mut calendars = HashMap::new();
+        for (key, courses) in groups {
+            let processed = self.process_courses(&courses, weeks_total)?;
+            if processed.is_empty() {
+                continue;
+            }
+            let mut buffer = Vec::new();
+            self.write_events(
+                &processed,
+                None,
+                &mut buffer,
+                Some(split_label(&key)),
+                false,
+                &dtstamp,
+            )?;
+            calendars.insert(key, into_ics_string(buffer)?);
+        }
+        if self.options.include_holiday_events && !self.holiday_events.is_empty() {
+            let mut buffer = Vec::new();
+            self.write_events(
+                &[],
+                None,
+                &mut buffer,
+                Some(split_label("holidays")),
+                true,
+                &dtstamp,
+            )?;
+            calendars.insert("holidays".to_string(), into_ics_string(buffer)?);
+        }
+        Ok(calendars)
+    }
+
+    /// 逐个事件写出ICS日历内容，适合边生成边发送的场景
+    pub fn generate_to_writer<W: Write>(&self, response: &CourseResponse, writer: W) -> Result<()> {
+        self.write_calendar(response, None, writer)
+    }
+
+    /// 生成ICS日历内容，并根据上次生成的记录递增变化事件的 SEQUENCE
+    ///
+    /// 序号按稳定 UID 记录，因此总是使用稳定 UID。
+    pub fn generate_with_sequences(
+        &self,
+        response: &CourseResponse,
+        sequences: &mut EventSequences,
+    ) -> Result<String> {
+        self.generate_inner(response, Some(sequences))
+    }
+
+    /// 用ICS解析器读回生成的内容，检查每个 VEVENT 与 VTODO 的必需属性
+    ///
+    /// VEVENT 要求包含 UID、DTSTART、SUMMARY，以及 DTEND 或 DURATION 之一；
+    /// VTODO 要求包含 UID、SUMMARY、DUE，同时有 DTSTART 时须早于 DUE。
+    /// 所有问题汇总到一个 `Error::IcsGeneration` 中返回。
+    pub fn validate(ics: &str) -> Result<()> {
+        let mut problems = Vec::new();
+        let mut calendars = 0;
+        for calendar in IcalParser::new(ics.as_bytes()) {
+            let calendar =
+                calendar.map_err(|e| Error::IcsGeneration(format!("ICS校验失败: {}", e)))?;
+            calendars += 1;
+            for (index, event) in calendar.events.iter().enumerate() {
+                let has = |name: &str| {
+                    event.properties.iter().any(|p| {
+                        p.name == name && p.value.as_deref().is_some_and(|v| !v.is_empty())
+                    })
+                };
+                let mut missing: Vec<&str> = ["UID", "DTSTART", "SUMMARY"]
+                    .into_iter()
+                    .filter(|name| !has(name))
+                    .collect();
+                if !has("DTEND") && !has("DURATION") {
+                    missing.push("DTEND/DURATION");
+                }
+                if !missing.is_empty() {
+                    problems.push(format!("第{}个事件缺少 {}", index + 1, missing.join("、")));
+                }
+            }
+            for (index, todo) in calendar.todos.iter().enumerate() {
+                let value = |name: &str| {
+                    todo.properties
+                        .iter()
+                        .find(|p| p.name == name)
+                        .and_then(|p| p.value.as_deref())
+                        .filter(|v| !v.is_empty())
+                };
+                let missing: Vec<&str> = ["UID", "SUMMARY", "DUE"]
+                    .into_iter()
+                    .filter(|name| value(name).is_none())
+                    .collect();
+                if !missing.is_empty() {
+                    problems.push(format!("第{}个待办缺少 {}", index + 1, missing.join("、")));
+                }
+                // 同为 UTC 时间或日期格式，可直接按字符串比较
+                if let (Some(start), Some(due)) = (value("DTSTART"), value("DUE"))
+                    && start >= due
+                {
+                    problems.push(format!("第{}个待办的 DTSTART 不早于 DUE", index + 1));
+                }
+            }
+        }
+        if calendars == 0 {
+            problems.push("没有 VCALENDAR".to_string());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::IcsGeneration(format!(
+                "ICS校验失败: {}",
+                problems.join("; ")
+            )))
+        }
+    }
+
+    fn generate_inner(
+        &self,
+        response: &CourseResponse,
+        sequences: Option<&mut EventSequences>,
+    ) -> Result<String> {
+        let mut buffer = Vec::new();
+        self.write_calendar(response, sequences, &mut buffer)?;
+        into_ics_string(buffer)
+    }
+
+    fn write_calendar<W: Write>(
+        &self,
+        response: &CourseResponse,
+        sequences: Option<&mut EventSequences>,
+        writer: W,
+    ) -> Result<()> {
         // 首先处理课程，智能创建重复规则
-        let processed_courses = self.process_courses(&response.courses)?;
+        let processed_courses =
+            self.process_courses(&response.courses, response.semester.weeks_total)?;
+        self.write_events(
+            &processed_courses,
+            sequences,
+            writer,
+            None,
+            self.options.include_holiday_events,
+            &self.dtstamp(response),
+        )
+    }
+
+    /// 写出完整的 VCALENDAR，`name_suffix` 追加在日历名称之后
+    fn write_events<W: Write>(
+        &self,
+        processed_courses: &[CourseWithRecurrence],
+        mut sequences: Option<&mut EventSequences>,
+        mut writer: W,
+        name_suffix: Option<&str>,
+        include_holidays: bool,
+        dtstamp: &str,
+    ) -> Result<()> {
+        let mut write = |content: &mut String| {
+            writer
+                .write_all(content.as_bytes())
+                .map_err(|e| Error::IcsGeneration(format!("Failed to write ICS: {}", e)))?;
+            content.clear();
+            Ok::<_, Error>(())
+        };
 
         let mut ics_content = String::new();
 
         // ICS文件头部
         ics_content.push_str("BEGIN:VCALENDAR\r\n");
         ics_content.push_str("VERSION:2.0\r\n");
-        ics_content.push_str("PRODID:-//CQUPT ICS//CQUPT Course Calendar//CN\r\n");
+        ics_content.push_str(&format!(
+            "PRODID:{}\r\n",
+            self.options.prodid.as_deref().unwrap_or(DEFAULT_PRODID)
+        ));
         ics_content.push_str("CALSCALE:GREGORIAN\r\n");
         ics_content.push_str("METHOD:PUBLISH\r\n");
 
-        if let Some(ref name) = self.options.calendar_name {
-            ics_content.push_str(&format!("X-WR-CALNAME:{}\r\n", name));
+        match (self.options.calendar_name.as_deref(), name_suffix) {
+            (Some(name), Some(suffix)) => {
+                ics_content.push_str(&format!("X-WR-CALNAME:{} - {}\r\n", name, suffix))
+            }
+            (Some(name), None) | (None, Some(name)) => {
+                ics_content.push_str(&format!("X-WR-CALNAME:{}\r\n", name))
+            }
+            (None, None) => {}
+        }
+        match (self.options.calendar_id.as_deref(), name_suffix) {
+            (Some(id), Some(suffix)) => {
+                ics_content.push_str(&format!("X-WR-RELCALID:{}-{}\r\n", id, suffix))
+            }
+            (Some(id), None) => ics_content.push_str(&format!("X-WR-RELCALID:{}\r\n", id)),
+            (None, _) => {}
+        }
+        if let Some(ref description) = self.options.calendar_description {
+            ics_content.push_str(&format!(
+                "X-WR-CALDESC:{}\r\n",
+                self.escape_text(description)
+            ));
+        }
+        ics_content.push_str("X-WR-TIMEZONE:Asia/Shanghai\r\n");
+        write(&mut ics_content)?;
+
+        // 添加课程事件，每个事件生成后立即写出；相同地点只解析一次
+        let mut locations = LocationCache::default();
+        for course_with_recurrence in processed_courses {
+            self.add_course_event(
+                &mut ics_content,
+                course_with_recurrence,
+                sequences.as_deref_mut(),
+                &mut locations,
+                dtstamp,
+            )?;
+            write(&mut ics_content)?;
         }
 
-        // 添加课程事件
-        for course_with_recurrence in &processed_courses {
-            self.add_course_event(&mut ics_content, course_with_recurrence)?;
+        // 添加放假日全天事件
+        if include_holidays {
+            for event in &self.holiday_events {
+                self.add_holiday_event(&mut ics_content, event, dtstamp);
+                write(&mut ics_content)?;
+            }
         }
 
         // ICS文件尾部
         ics_content.push_str("END:VCALENDAR\r\n");
+        write(&mut ics_content)?;
 
-        Ok(ics_content)
+        writer
+            .flush()
+            .map_err(|e| Error::IcsGeneration(format!("Failed to write ICS: {}", e)))
     }
 
     /// 处理课程列表，智能创建重复规则
-    fn process_courses(&self, courses: &[Course]) -> Result<Vec<CourseWithRecurrence>> {
+    ///
+    /// `weeks_total` 为 provider 给出的学期总周数，为空时不裁剪周次。
+    fn process_courses(
+        &self,
+        courses: &[Course],
+        weeks_total: Option<u32>,
+    ) -> Result<Vec<CourseWithRecurrence>> {
         let mut processed = Vec::new();
 
-        for course in courses {
-            let processed_course = if self.is_exam_course(course) {
+        let clamped = courses
+            .iter()
+            .filter(|c| self.matches_filter(c))
+            .filter_map(|c| match self.options.date_window {
+                Some((since, until)) => clamp_to_window(c, since, until),
+                None => Some(Cow::Borrowed(c)),
+            })
+            .map(|c| self.clamp_duration(c))
+            .collect::<Vec<_>>();
+        // 同一日历内已使用的稳定 UID 及出现次数
+        let mut seen_uids = HashMap::new();
+        for course in clamped.iter().map(Cow::as_ref) {
+            // 所有周次都因放假停课时不再输出，补课事件单独存在
+            if let (Some(weeks), Some(off_weeks)) = (&course.weeks, &course.off_weeks)
+                && !weeks.is_empty()
+                && weeks.iter().all(|week| off_weeks.contains(week))
+            {
+                continue;
+            }
+
+            let recurrence = if course.is_exam() {
                 // 考试不需要重复规则
-                CourseWithRecurrence {
-                    course: course.clone(),
-                    recurrence: None,
-                }
+                None
             } else if let (Some(weeks), Some(weekday)) = (&course.weeks, course.weekday)
                 && weeks.len() > 1
             {
-                // 创建重复规则
-                let recurrence = self.create_recurrence_rule(
+                // 创建重复规则；周次全部超出学期时跳过该课程，不影响其他课程
+                let rule = self.create_recurrence_rule(
                     weeks,
                     course.off_weeks.as_deref(),
                     weekday,
                     &course.start_time,
+                    weeks_total,
                 )?;
-
-                CourseWithRecurrence {
-                    course: course.clone(),
-                    recurrence: Some(recurrence),
+                if rule.is_none() {
+                    tracing::warn!(
+                        "Course {} has no weeks within the semester, skipping it",
+                        course.name
+                    );
+                    continue;
                 }
+                rule
             } else {
                 // 没有足够信息创建重复规则，作为单次事件
-                CourseWithRecurrence {
-                    course: course.clone(),
-                    recurrence: None,
-                }
+                None
+            };
+
+            let events = match recurrence {
+                Some(rule) => split_by_weekday(course.clone(), rule)
+                    .into_iter()
+                    .map(|(course, rule)| (course, Some(rule)))
+                    .collect(),
+                None => vec![(course.clone(), None)],
             };
+            for (course, recurrence) in events {
+                // 同一时段的不同课程（如教师、地点不同）可能得到相同的 UID，重复时追加序号区分
+                let hash = self.stable_uid_hash(&course, recurrence.is_some());
+                let count = seen_uids.entry(hash).or_insert(0u32);
+                *count += 1;
+                let stable_uid = match *count {
+                    1 => format!("{:016x}@cqupt-ics", hash),
+                    n => format!("{:016x}-{}@cqupt-ics", hash, n),
+                };
 
-            processed.push(processed_course);
+                processed.push(CourseWithRecurrence {
+                    course,
+                    recurrence,
+                    stable_uid,
+                });
+            }
         }
 
         Ok(processed)
     }
 
-    /// 判断是否是考试课程
-    fn is_exam_course(&self, course: &Course) -> bool {
-        course.exam_type.is_some()
-            || course
-                .course_type
-                .as_ref()
-                .is_some_and(|t| t.contains("考试"))
+    /// 修正异常时长：结束不晚于开始时改用 `min_duration`，超过 `max_duration` 时截断
+    fn clamp_duration<'a>(&self, course: Cow<'a, Course>) -> Cow<'a, Course> {
+        if course.all_day {
+            return course;
+        }
+        let duration = course.end_time - course.start_time;
+        let replacement = if duration <= chrono::Duration::zero() {
+            tracing::warn!(
+                "Course {} ends at or before its start ({} - {}), using {} minutes",
+                course.name,
+                course.start_time,
+                course.end_time,
+                self.options.min_duration
+            );
+            self.options.min_duration
+        } else {
+            match self.options.max_duration {
+                Some(max) if duration > chrono::Duration::minutes(max as i64) => {
+                    tracing::warn!(
+                        "Course {} lasts {} minutes, clamping to {} minutes",
+                        course.name,
+                        duration.num_minutes(),
+                        max
+                    );
+                    max
+                }
+                _ => return course,
+            }
+        };
+        let mut course = course.into_owned();
+        course.end_time = course.start_time + chrono::Duration::minutes(replacement as i64);
+        Cow::Owned(course)
+    }
+
+    /// 判断课程是否符合 `content_filter`
+    fn matches_filter(&self, course: &Course) -> bool {
+        match self.options.content_filter {
+            ContentFilter::All => true,
+            ContentFilter::ExamsOnly => course.is_exam(),
+            ContentFilter::CustomOnly => course.is_custom(),
+            ContentFilter::ClassesOnly => !course.is_exam() && !course.is_custom(),
+        }
     }
 
     /// 创建重复规则
@@ -104,10 +499,23 @@ impl IcsGenerator {
         off_weeks: Option<&[u32]>,
         weekday: u32,
         start_time: &DateTime<FixedOffset>,
-    ) -> Result<RecurrenceRule> {
+        weeks_total: Option<u32>,
+    ) -> Result<Option<RecurrenceRule>> {
         let mut weeks = weeks.into();
-        let wp = weeks_pattern(&mut weeks)
-            .ok_or_else(|| Error::Config("Course has no week information".to_string()))?;
+        // 超出学期总周数的周次多为数据异常，忽略以免 UNTIL 延后到学期之外
+        if let Some(weeks_total) = weeks_total
+            && weeks.iter().any(|&week| week > weeks_total)
+        {
+            tracing::warn!(
+                "Course weeks {:?} exceed the {}-week semester, ignoring the extra weeks",
+                weeks,
+                weeks_total
+            );
+            weeks.to_mut().retain(|&week| week <= weeks_total);
+        }
+        let Some(wp) = weeks_pattern(&mut weeks) else {
+            return Ok(None);
+        };
         let weeks = wp.weeks;
         let first_week = *weeks.first().unwrap();
         let last_week = *weeks.last().unwrap();
@@ -156,25 +564,77 @@ impl IcsGenerator {
             )
         };
 
-        Ok(RecurrenceRule {
+        ics_weekday(weekday)?;
+        Ok(Some(RecurrenceRule {
             frequency,
             interval,
             until,
             count,
             by_day: Some(vec![weekday]),
             exception_dates,
-        })
+        }))
+    }
+
+    /// 由课程名称、代码与上课时间派生稳定 UID 的哈希
+    ///
+    /// 不包含地点、教师等可能变化的信息，重复课程只取星期与时刻，
+    /// 单次事件（如考试）使用完整的开始时间。
+    fn stable_uid_hash(&self, course: &Course, recurring: bool) -> u64 {
+        let when = if recurring {
+            course.start_time.format("%u %H:%M").to_string()
+        } else {
+            course.start_time.to_rfc3339()
+        };
+        let key = format!(
+            "{}\n{}\n{}",
+            course.name,
+            course.code.as_deref().unwrap_or_default(),
+            when
+        );
+        fnv1a(key.as_bytes())
+    }
+
+    /// 设置了 `fixed_dtstamp` 或 `dtstamp_from_response` 时 DTSTAMP 可复现
+    fn reproducible_dtstamp(&self) -> bool {
+        self.options.fixed_dtstamp.is_some() || self.options.dtstamp_from_response
+    }
+
+    /// 事件的 DTSTAMP，见 [`Self::reproducible_dtstamp`]
+    fn dtstamp(&self, response: &CourseResponse) -> String {
+        self.options
+            .fixed_dtstamp
+            .or_else(|| {
+                self.options
+                    .dtstamp_from_response
+                    .then(|| response.generated_at.to_utc())
+            })
+            .unwrap_or_else(Utc::now)
+            .format("%Y%m%dT%H%M%SZ")
+            .to_string()
     }
 
     /// 添加单个课程事件
     fn add_course_event(
         &self,
-        ics_content: &mut String,
+        output: &mut String,
         course_with_recurrence: &CourseWithRecurrence,
+        sequences: Option<&mut EventSequences>,
+        locations: &mut LocationCache,
+        dtstamp: &str,
     ) -> Result<()> {
         let course = &course_with_recurrence.course;
-        let uid = Uuid::new_v4().to_string();
-        let dtstamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        if self.options.custom_as_todo && course.is_custom() {
+            return self.add_todo(
+                output,
+                course_with_recurrence,
+                sequences,
+                locations,
+                dtstamp,
+            );
+        }
+        let uid = self.event_uid(course_with_recurrence, sequences.is_some());
+        // 事件主体单独构建，用于判断内容是否变化
+        let ics_content = &mut String::new();
 
         // 根据 ICS 标准，DateTime<FixedOffset> 应该转换为 UTC 格式
         // 这样既符合标准，又充分利用了 FixedOffset 的时区信息
@@ -183,86 +643,357 @@ impl IcsGenerator {
         let dtstart = dtstart_utc.format("%Y%m%dT%H%M%SZ").to_string();
         let dtend = dtend_utc.format("%Y%m%dT%H%M%SZ").to_string();
 
-        ics_content.push_str("BEGIN:VEVENT\r\n");
-        ics_content.push_str(&format!("UID:{}\r\n", uid));
-        ics_content.push_str(&format!("DTSTAMP:{}\r\n", dtstamp));
-        ics_content.push_str(&format!("DTSTART:{}\r\n", dtstart));
-        ics_content.push_str(&format!("DTEND:{}\r\n", dtend));
+        if course.all_day {
+            // 全天事件使用 VALUE=DATE，DTEND 不含，至少为开始的次日
+            let start = course.start_time.date_naive();
+            let end = course
+                .end_time
+                .date_naive()
+                .max(start + chrono::Duration::days(1));
+            ics_content.push_str(&format!(
+                "DTSTART;VALUE=DATE:{}\r\n",
+                start.format("%Y%m%d")
+            ));
+            ics_content.push_str(&format!("DTEND;VALUE=DATE:{}\r\n", end.format("%Y%m%d")));
+        } else {
+            ics_content.push_str(&format!("DTSTART:{}\r\n", dtstart));
+            if self.options.use_duration {
+                let minutes = (course.end_time - course.start_time).num_minutes().max(0);
+                ics_content.push_str(&format!("DURATION:PT{}M\r\n", minutes));
+            } else {
+                ics_content.push_str(&format!("DTEND:{}\r\n", dtend));
+            }
+        }
         ics_content.push_str(&format!(
             "SUMMARY:{}\r\n",
             self.escape_text(&self.build_course_title(course))
         ));
+        if course.is_makeup {
+            ics_content.push_str(&format!("CATEGORIES:{}\r\n", MAKEUP_CATEGORY));
+        }
+        if let Some(credits) = course_credits(course) {
+            ics_content.push_str(&format!("X-CQUPT-CREDITS:{:.1}\r\n", credits));
+        }
+        if let Some(color) = self.event_color(course) {
+            ics_content.push_str(&format!("COLOR:{}\r\n", color));
+        }
+
+        self.add_course_details(ics_content, course, locations, "课程提醒", false);
+
+        // 添加重复规则
+        if let Some(ref recurrence) = course_with_recurrence.recurrence {
+            self.add_recurrence_rule(ics_content, recurrence, course.all_day)?;
+        }
+
+        let sequence = sequences.map_or(0, |s| s.bump(&uid, fnv1a(ics_content.as_bytes())));
+        output.push_str("BEGIN:VEVENT\r\n");
+        output.push_str(&format!("UID:{}\r\n", uid));
+        output.push_str(&format!("DTSTAMP:{}\r\n", dtstamp));
+        output.push_str(&format!("SEQUENCE:{}\r\n", sequence));
+        output.push_str(ics_content);
+        output.push_str("END:VEVENT\r\n");
+
+        Ok(())
+    }
 
-        // 添加位置信息（包含地理坐标）
+    /// 添加地点（包含地理坐标）、描述与提醒，课程事件与待办共用
+    ///
+    /// 考试优先使用专用提醒；`related_end` 为真时提醒相对结束（待办的 DUE）触发。
+    fn add_course_details(
+        &self,
+        ics_content: &mut String,
+        course: &Course,
+        locations: &mut LocationCache,
+        alarm_label: &str,
+        related_end: bool,
+    ) {
         if let Some(ref location) = course.location {
-            let location_with_geo = self.location_manager.get_location_with_geo(location);
+            let location_with_geo = self.location_manager.get_location_with_detail_cached(
+                location,
+                self.options.location_detail,
+                locations,
+            );
             ics_content.push_str(&location_with_geo);
         }
 
-        // 构建描述信息
-        if self.options.include_description {
+        if self.options.include_description
+            && self.options.description_style != DescriptionStyle::None
+        {
             let description = self.build_course_description(course);
-            ics_content.push_str(&format!(
-                "DESCRIPTION:{}\r\n",
-                self.escape_text(&description)
-            ));
+            if !description.is_empty() {
+                ics_content.push_str(&format!(
+                    "DESCRIPTION:{}\r\n",
+                    self.escape_text(&description)
+                ));
+                if self.options.html_description {
+                    let html = self.build_html_description(&description);
+                    ics_content.push_str(&fold_line(&format!(
+                        "X-ALT-DESC;FMTTYPE=text/html:{}",
+                        self.escape_text(&html)
+                    )));
+                }
+            }
         }
 
-        // 添加提醒
-        if let Some(reminder_minutes) = self.options.reminder_minutes {
-            ics_content.push_str("BEGIN:VALARM\r\n");
-            ics_content.push_str("ACTION:DISPLAY\r\n");
-            ics_content.push_str("DESCRIPTION:课程提醒\r\n");
-            ics_content.push_str(&format!("TRIGGER:-PT{}M\r\n", reminder_minutes));
-            ics_content.push_str("END:VALARM\r\n");
+        if course.is_exam() && !self.options.exam_reminders.is_empty() {
+            for reminder in &self.options.exam_reminders {
+                self.add_alarm(
+                    ics_content,
+                    "考试提醒",
+                    reminder.minutes_before,
+                    related_end,
+                );
+            }
+        } else {
+            for minutes_before in self.class_reminders() {
+                self.add_alarm(ics_content, alarm_label, minutes_before, related_end);
+            }
         }
+    }
 
-        // 添加重复规则
+    /// 添加自定义日程待办
+    ///
+    /// 单次待办以日程开始时间为 `DUE`，提醒相对截止时间触发；按周重复时
+    /// 输出 RFC 5545 要求的 `DTSTART`（开始时间），`DUE` 取结束时间以晚于
+    /// `DTSTART`，提醒相对开始时间触发。
+    fn add_todo(
+        &self,
+        output: &mut String,
+        course_with_recurrence: &CourseWithRecurrence,
+        sequences: Option<&mut EventSequences>,
+        locations: &mut LocationCache,
+        dtstamp: &str,
+    ) -> Result<()> {
+        let course = &course_with_recurrence.course;
+        let uid = self.event_uid(course_with_recurrence, sequences.is_some());
+        let ics_content = &mut String::new();
+
+        let recurring = course_with_recurrence.recurrence.is_some();
+        let date_value = |time: &DateTime<FixedOffset>| {
+            if course.all_day {
+                format!(";VALUE=DATE:{}", time.format("%Y%m%d"))
+            } else {
+                format!(":{}", time.to_utc().format("%Y%m%dT%H%M%SZ"))
+            }
+        };
+        if recurring {
+            ics_content.push_str(&format!("DTSTART{}\r\n", date_value(&course.start_time)));
+            let due = if course.all_day {
+                // 全天待办的 DUE 至少为开始的次日
+                course
+                    .end_time
+                    .max(course.start_time + chrono::Duration::days(1))
+            } else {
+                course
+                    .end_time
+                    .max(course.start_time + chrono::Duration::minutes(1))
+            };
+            ics_content.push_str(&format!("DUE{}\r\n", date_value(&due)));
+        } else {
+            ics_content.push_str(&format!("DUE{}\r\n", date_value(&course.start_time)));
+        }
+        ics_content.push_str(&format!(
+            "SUMMARY:{}\r\n",
+            self.escape_text(&self.build_course_title(course))
+        ));
+        ics_content.push_str("STATUS:NEEDS-ACTION\r\n");
+        self.add_course_details(ics_content, course, locations, "待办提醒", !recurring);
         if let Some(ref recurrence) = course_with_recurrence.recurrence {
-            self.add_recurrence_rule(ics_content, recurrence)?;
+            self.add_recurrence_rule(ics_content, recurrence, course.all_day)?;
         }
 
-        ics_content.push_str("END:VEVENT\r\n");
+        let sequence = sequences.map_or(0, |s| s.bump(&uid, fnv1a(ics_content.as_bytes())));
+        output.push_str("BEGIN:VTODO\r\n");
+        output.push_str(&format!("UID:{}\r\n", uid));
+        output.push_str(&format!("DTSTAMP:{}\r\n", dtstamp));
+        output.push_str(&format!("SEQUENCE:{}\r\n", sequence));
+        output.push_str(ics_content);
+        output.push_str("END:VTODO\r\n");
 
         Ok(())
     }
 
+    /// 开启稳定 UID 或记录序号时使用稳定 UID，否则随机生成
+    fn event_uid(&self, course_with_recurrence: &CourseWithRecurrence, tracked: bool) -> String {
+        if self.options.stable_uids || tracked {
+            course_with_recurrence.stable_uid.clone()
+        } else {
+            Uuid::new_v4().to_string()
+        }
+    }
+
+    /// 添加放假日全天事件
+    ///
+    /// 全天事件使用 `VALUE=DATE`，DTEND 为结束日期的次日（不含）。
+    /// 开启稳定 UID 或 DTSTAMP 可复现时 UID 由日期与名称派生，否则随机生成。
+    fn add_holiday_event(&self, ics_content: &mut String, event: &HolidayEvent, dtstamp: &str) {
+        let end = event.end.succ_opt().unwrap_or(event.end);
+        let uid = if self.options.stable_uids || self.reproducible_dtstamp() {
+            let key = format!("{}\n{}\n{}", event.start, event.end, event.name);
+            format!("{:016x}@cqupt-ics", fnv1a(key.as_bytes()))
+        } else {
+            Uuid::new_v4().to_string()
+        };
+
+        ics_content.push_str("BEGIN:VEVENT\r\n");
+        ics_content.push_str(&format!("UID:{}\r\n", uid));
+        ics_content.push_str(&format!("DTSTAMP:{}\r\n", dtstamp));
+        ics_content.push_str(&format!(
+            "DTSTART;VALUE=DATE:{}\r\n",
+            event.start.format("%Y%m%d")
+        ));
+        ics_content.push_str(&format!("DTEND;VALUE=DATE:{}\r\n", end.format("%Y%m%d")));
+        ics_content.push_str(&format!("SUMMARY:{}\r\n", self.escape_text(&event.name)));
+        ics_content.push_str("TRANSP:TRANSPARENT\r\n");
+        ics_content.push_str("END:VEVENT\r\n");
+    }
+
+    /// 课程提醒（提前分钟数），`reminders` 非空时取代 `reminder_minutes`
+    fn class_reminders(&self) -> Vec<u32> {
+        if self.options.reminders.is_empty() {
+            self.options.reminder_minutes.into_iter().collect()
+        } else {
+            self.options
+                .reminders
+                .iter()
+                .map(|reminder| reminder.minutes_before)
+                .collect()
+        }
+    }
+
+    /// 添加 VALARM 提醒
+    fn add_alarm(
+        &self,
+        ics_content: &mut String,
+        description: &str,
+        minutes_before: u32,
+        related_end: bool,
+    ) {
+        let related = if related_end { ";RELATED=END" } else { "" };
+        ics_content.push_str("BEGIN:VALARM\r\n");
+        ics_content.push_str("ACTION:DISPLAY\r\n");
+        ics_content.push_str(&format!("DESCRIPTION:{}\r\n", description));
+        ics_content.push_str(&format!("TRIGGER{}:-PT{}M\r\n", related, minutes_before));
+        ics_content.push_str("END:VALARM\r\n");
+    }
+
     /// 转义ICS文本内容
+    ///
+    /// 按 RFC 5545 的 TEXT 规则转义反斜杠、逗号、分号和换行；
+    /// CRLF 与单独的 CR 视为换行，其余控制字符（制表符除外）直接移除。
     fn escape_text(&self, text: &str) -> String {
-        text.replace("\\", "\\\\")
-            .replace("\n", "\\n")
-            .replace("\r", "\\r")
-            .replace(",", "\\,")
-            .replace(";", "\\;")
+        let mut escaped = String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                ',' => escaped.push_str("\\,"),
+                ';' => escaped.push_str("\\;"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => {
+                    if chars.peek() == Some(&'\n') {
+                        chars.next();
+                    }
+                    escaped.push_str("\\n");
+                }
+                '\t' => escaped.push(c),
+                c if c.is_control() => {}
+                c => escaped.push(c),
+            }
+        }
+        escaped
     }
 
     /// 构建课程描述信息
     pub fn build_course_description(&self, course: &Course) -> String {
-        // 检查是否是考试类型
-        if self.is_exam_course(course) {
-            self.build_exam_description(course)
-        } else {
-            self.build_class_description(course)
+        match self.options.description_style {
+            DescriptionStyle::Full => {
+                // 检查是否是考试类型
+                if course.is_exam() {
+                    self.build_exam_description(course)
+                } else {
+                    self.build_class_description(course)
+                }
+            }
+            DescriptionStyle::Compact => self.build_compact_description(course),
+            DescriptionStyle::None => String::new(),
         }
     }
 
+    /// 将纯文本描述转换为 HTML：按 `，` 分行，`标签: 内容` 中的标签加粗
+    pub fn build_html_description(&self, description: &str) -> String {
+        description
+            .split('，')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| match segment.split_once([':', '：']) {
+                Some((label, value)) => format!(
+                    "<b>{}:</b> {}",
+                    escape_html(label.trim()),
+                    escape_html(value.trim())
+                ),
+                None => escape_html(segment),
+            })
+            .collect::<Vec<_>>()
+            .join("<br>")
+    }
+
+    /// 构建精简描述：仅教师与地点
+    pub fn build_compact_description(&self, course: &Course) -> String {
+        let teacher =
+            join_teachers(&course.teacher_names()).filter(|_| self.options.include_teacher);
+        let location = course.location.clone().filter(|l| !l.is_empty());
+
+        teacher
+            .into_iter()
+            .chain(location)
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+
     /// 构建课程标题
     pub fn build_course_title(&self, course: &Course) -> String {
-        if self.is_exam_course(course) {
+        let name = self.display_name(&course.name);
+        // 课程名 - 地点，无地点时只有课程名
+        let title = match course.location.as_ref().filter(|l| !l.is_empty()) {
+            Some(location) => format!("{} - {}", name, location),
+            None => name.to_string(),
+        };
+        if course.is_exam() {
             // 考试类型：[考试类型考试] 课程名 - 地点
             let exam_type = course.exam_type.as_deref().unwrap_or("");
-            let location = course.location.as_deref().unwrap_or("");
-            format!("[{}考试] {} - {}", exam_type, course.name, location)
+            format!("[{}考试] {}", exam_type, title)
         } else {
-            // 普通课程：课程名 - 地点
-            if let Some(location) = course.location.as_ref().filter(|l| !l.is_empty()) {
-                format!("{} - {}", course.name, location)
-            } else {
-                course.name.to_string()
+            title
+        }
+    }
+
+    /// 事件颜色名，同名课程总是得到相同的颜色
+    pub fn event_color(&self, course: &Course) -> Option<&'static str> {
+        match self.options.color_by {
+            ColorBy::None => None,
+            ColorBy::Type if course.is_exam() => Some(EVENT_PALETTE[6]),
+            ColorBy::Type if course.is_custom() => Some(EVENT_PALETTE[4]),
+            ColorBy::Type => Some(EVENT_PALETTE[0]),
+            ColorBy::CourseName => {
+                let index = fnv1a(course.name.as_bytes()) % EVENT_PALETTE.len() as u64;
+                Some(EVENT_PALETTE[index as usize])
             }
         }
     }
 
+    /// 按别名表取课程显示名称：精确匹配优先，其次取最长的前缀匹配
+    fn display_name<'a>(&'a self, name: &'a str) -> &'a str {
+        let aliases = &self.options.name_aliases;
+        if let Some(alias) = aliases.get(name) {
+            return alias;
+        }
+        aliases
+            .iter()
+            .filter(|(raw, _)| !raw.is_empty() && name.starts_with(raw.as_str()))
+            .max_by_key(|(raw, _)| raw.len())
+            .map_or(name, |(_, alias)| alias.as_str())
+    }
+
     pub fn format_weeks<'a, W: Into<Cow<'a, [u32]>>>(&self, weeks: W) -> Option<String> {
         format_weeks(&mut weeks.into())
     }
@@ -274,7 +1005,9 @@ impl IcsGenerator {
             segments.push(code.to_string());
         }
 
-        if let Some(teacher) = course.teacher.as_ref().filter(|t| !t.is_empty()) {
+        if let Some(teacher) =
+            join_teachers(&course.teacher_names()).filter(|_| self.options.include_teacher)
+        {
             segments.push(format!("任课教师: {}", teacher));
         }
 
@@ -282,6 +1015,10 @@ impl IcsGenerator {
             segments.push(format!("该课程是{}课", course_type));
         }
 
+        if let Some(credits) = course_credits(course) {
+            segments.push(format!("学分: {:.1}", credits));
+        }
+
         let mut week_str = None;
         if let (Some(week), Some(begin), Some(duration)) = (
             course
@@ -300,6 +1037,15 @@ impl IcsGenerator {
                 begin,
                 begin + duration - 1
             ));
+        } else {
+            // 无节次信息时单独列出周次，避免与上面的行课信息重复
+            match course.weeks.as_deref() {
+                Some([week]) => segments.push(format!("本学期第{}周", week)),
+                Some(weeks) if !weeks.is_empty() => {
+                    segments.push(format!("行课周次：{}", compress_weeks(weeks)));
+                }
+                _ => {}
+            }
         }
 
         if let Some(note) = course.note.as_ref().filter(|n| !n.is_empty()) {
@@ -307,7 +1053,12 @@ impl IcsGenerator {
         }
 
         if segments.is_empty() {
-            "暂无课程详情".to_string()
+            // 空描述不会输出 DESCRIPTION
+            if self.options.omit_empty_description {
+                String::new()
+            } else {
+                EMPTY_CLASS_DESCRIPTION.to_string()
+            }
         } else {
             segments.join("，")
         }
@@ -332,9 +1083,21 @@ impl IcsGenerator {
             "未知".to_string()
         };
 
+        // 监考信息：主考: …，监考: …
+        let mut invigilators = String::new();
+        if let Some(chief) = course.chief_invigilator.as_ref().filter(|c| !c.is_empty()) {
+            invigilators.push_str(&format!("主考: {}，", chief));
+        }
+        if !course.deputy_invigilators.is_empty() {
+            invigilators.push_str(&format!(
+                "监考: {}，",
+                course.deputy_invigilators.join("、")
+            ));
+        }
+
         format!(
-            "考试在第{}周进行，时间为{}至{}，考试座位号是{}，考试状态: {}，祝考试顺利！（最终考试信息请以教务在线公布为准）",
-            current_week, start_time, end_time, seat, test_status
+            "考试在第{}周进行，时间为{}至{}，考试座位号是{}，考试状态: {}，{}祝考试顺利！（最终考试信息请以教务在线公布为准）",
+            current_week, start_time, end_time, seat, test_status, invigilators
         )
     }
 
@@ -343,68 +1106,166 @@ impl IcsGenerator {
         &self,
         ics_content: &mut String,
         recurrence: &RecurrenceRule,
+        all_day: bool,
     ) -> Result<()> {
-        let mut rrule = format!("RRULE:FREQ={}", recurrence.frequency);
+        let rrule = recurrence.rrule_value(all_day)?;
+        ics_content.push_str(&format!("RRULE:{}\r\n", rrule));
+
+        // 添加例外日期
+        for exception_date in &recurrence.exception_dates {
+            if all_day {
+                ics_content.push_str(&format!(
+                    "EXDATE;VALUE=DATE:{}\r\n",
+                    exception_date.format("%Y%m%d")
+                ));
+                continue;
+            }
+            // 转换为 UTC 格式以保持一致性
+            let exception_utc = exception_date.to_utc();
+            ics_content.push_str(&format!(
+                "EXDATE:{}\r\n",
+                exception_utc.format("%Y%m%dT%H%M%SZ")
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl RecurrenceRule {
+    /// 序列化为 RRULE 属性值，例如 `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO`
+    ///
+    /// UNTIL 输出为 UTC 时间，不包含 `RRULE:` 前缀与例外日期。
+    pub fn to_rrule_string(&self) -> Result<String> {
+        self.rrule_value(false)
+    }
+
+    /// 解析 RRULE 属性值，可带 `RRULE:` 前缀，浮动时间按学校时区解释
+    pub fn parse(rrule: &str) -> Result<Self> {
+        let value = rrule.trim();
+        let value = value.strip_prefix("RRULE:").unwrap_or(value);
+        let rule = parse_rrule(value, Vec::new(), &crate::CQUPT_TZ)?;
+        if rule.frequency.is_empty() {
+            return Err(Error::Config(format!("RRULE缺少FREQ: {}", rrule)));
+        }
+        Ok(rule)
+    }
 
-        if recurrence.interval > 1 {
-            rrule.push_str(&format!(";INTERVAL={}", recurrence.interval));
+    /// 生成 RRULE 属性值，全天事件的 UNTIL 使用日期格式
+    fn rrule_value(&self, all_day: bool) -> Result<String> {
+        let mut rrule = format!("FREQ={}", self.frequency);
+
+        if self.interval > 1 {
+            rrule.push_str(&format!(";INTERVAL={}", self.interval));
         }
 
-        if let Some(until) = recurrence.until {
+        if let Some(until) = self.until {
             // 根据 ICS 标准，UNTIL 必须与 DTSTART 使用相同格式
-            let until_utc = until.to_utc();
-            rrule.push_str(&format!(";UNTIL={}", until_utc.format("%Y%m%dT%H%M%SZ")));
+            if all_day {
+                rrule.push_str(&format!(";UNTIL={}", until.format("%Y%m%d")));
+            } else {
+                let until_utc = until.to_utc();
+                rrule.push_str(&format!(";UNTIL={}", until_utc.format("%Y%m%dT%H%M%SZ")));
+            }
         }
 
-        if let Some(count) = recurrence.count {
+        if let Some(count) = self.count {
             rrule.push_str(&format!(";COUNT={}", count));
         }
 
-        if let Some(ref by_day) = recurrence.by_day {
-            let days: Vec<String> = by_day
+        if let Some(ref by_day) = self.by_day {
+            let days = by_day
                 .iter()
-                .map(|d| match d {
-                    1 => "MO".to_string(),
-                    2 => "TU".to_string(),
-                    3 => "WE".to_string(),
-                    4 => "TH".to_string(),
-                    5 => "FR".to_string(),
-                    6 => "SA".to_string(),
-                    7 => "SU".to_string(),
-                    _ => format!("{}", d),
-                })
-                .collect();
+                .map(|&d| ics_weekday(d))
+                .collect::<Result<Vec<_>>>()?;
             if !days.is_empty() {
                 rrule.push_str(&format!(";BYDAY={}", days.join(",")));
             }
         }
 
-        ics_content.push_str(&format!("{}\r\n", rrule));
+        Ok(rrule)
+    }
+}
 
-        // 添加例外日期
-        for exception_date in &recurrence.exception_dates {
-            // 转换为 UTC 格式以保持一致性
-            let exception_utc = exception_date.to_utc();
-            ics_content.push_str(&format!(
-                "EXDATE:{}\r\n",
-                exception_utc.format("%Y%m%dT%H%M%SZ")
-            ));
-        }
+/// 将课程限制在 [since, until] 日期内
+///
+/// 有周次的课程只保留窗口内的周次，并把开始时间移到第一次保留的上课；
+/// 单次事件按开始日期判断。没有任何上课落在窗口内时返回 None。
+fn clamp_to_window(course: &Course, since: NaiveDate, until: NaiveDate) -> Option<Cow<'_, Course>> {
+    let in_window = |time: &DateTime<FixedOffset>| (since..=until).contains(&time.date_naive());
+    let Some(first_week) = course
+        .weeks
+        .as_ref()
+        .and_then(|weeks| weeks.iter().min().copied())
+    else {
+        return in_window(&course.start_time).then_some(Cow::Borrowed(course));
+    };
 
-        Ok(())
+    let offset = |week: u32| chrono::Duration::weeks(week as i64 - first_week as i64);
+    let weeks = course
+        .weeks
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|&week| in_window(&(course.start_time + offset(week))))
+        .collect::<Vec<_>>();
+    let new_first = weeks.iter().min().copied()?;
+    if new_first == first_week && weeks.len() == course.weeks.as_ref().map_or(0, Vec::len) {
+        return Some(Cow::Borrowed(course));
     }
+
+    let mut clamped = course.clone();
+    clamped.start_time = course.start_time + offset(new_first);
+    clamped.end_time = course.end_time + offset(new_first);
+    clamped.weeks = Some(weeks);
+    Some(Cow::Owned(clamped))
 }
 
-struct WeeksPattern<'a> {
-    gap: Option<u32>,
-    weeks: &'a mut Cow<'a, [u32]>,
+/// 课程学分，缺失或为零时返回 `None`
+fn course_credits(course: &Course) -> Option<f32> {
+    course.credits.filter(|credits| *credits > 0.0)
 }
 
-fn weeks_pattern<'a>(weeks: &'a mut Cow<'a, [u32]>) -> Option<WeeksPattern<'a>> {
-    if weeks.is_empty() {
-        return None;
+/// 拆分日历的分组在日历名称中的显示名称
+fn split_label(key: &str) -> &str {
+    match key {
+        "classes" => "课程",
+        "exams" => "考试",
+        "custom" => "日程",
+        "holidays" => "放假",
+        other => other,
     }
-    if !weeks.is_sorted() {
+}
+
+fn into_ics_string(buffer: Vec<u8>) -> Result<String> {
+    String::from_utf8(buffer).map_err(|e| Error::IcsGeneration(e.to_string()))
+}
+
+/// 由 `{provider}:{username}:{学期}` 派生的稳定日历标识
+///
+/// 同一账号同一学期重新生成时保持不变，订阅端据此识别为同一个日历。
+pub fn calendar_id(provider: &str, username: &str, semester: &Semester) -> String {
+    let key = format!("{}:{}:{}", provider, username, semester.term_label());
+    format!("cqupt-ics-{:016x}", fnv1a(key.as_bytes()))
+}
+
+/// FNV-1a 64 位哈希，结果跨版本稳定，用于 UID 与内容摘要
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+struct WeeksPattern<'a> {
+    gap: Option<u32>,
+    weeks: &'a mut Cow<'a, [u32]>,
+}
+
+fn weeks_pattern<'a>(weeks: &'a mut Cow<'a, [u32]>) -> Option<WeeksPattern<'a>> {
+    if weeks.is_empty() {
+        return None;
+    }
+    if !weeks.is_sorted() {
         weeks.to_mut().sort();
     }
 
@@ -453,11 +1314,406 @@ fn format_weeks<'a>(weeks: &'a mut Cow<'a, [u32]>) -> Option<String> {
     .into()
 }
 
+/// 将周次列表压缩为区间表示，如 [1,2,3,5,6,8] -> "1-3,5-6,8"
+fn compress_weeks(weeks: &[u32]) -> String {
+    let mut sorted = weeks.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut iter = sorted.into_iter();
+    let Some(mut start) = iter.next() else {
+        return String::new();
+    };
+    let mut end = start;
+    for week in iter {
+        if week == end + 1 {
+            end = week;
+            continue;
+        }
+        ranges.push(format_range(start, end));
+        start = week;
+        end = week;
+    }
+    ranges.push(format_range(start, end));
+    ranges.join(",")
+}
+
+fn format_range(start: u32, end: u32) -> String {
+    if start == end {
+        start.to_string()
+    } else {
+        format!("{}-{}", start, end)
+    }
+}
+
+/// 解析ICS日历内容为课程响应
+///
+/// 用于读回本模块生成的日历（例如重新应用节假日调休）。解析是有损的：
+/// - `SUMMARY` 按 `课程名 - 地点` 拆分，考试标题中的 `[类型考试]` 前缀还原为 `exam_type`；
+/// - 仅 `FREQ=WEEKLY` 的 `RRULE` 会结合 `EXDATE` 还原 `weeks`/`weekday`，单次事件不设置周次；
+/// - 学期开始时间取最早事件所在周的星期一；
+/// - 全天事件（`VALUE=DATE`，如放假日）会被跳过；
+/// - 课程代码、教师、备注、学分、节次等只存在于 `DESCRIPTION` 中的信息不会还原。
+pub fn parse(ics: &str) -> Result<CourseResponse> {
+    let tz = crate::CQUPT_TZ;
+    let mut events = Vec::new();
+    for calendar in IcalParser::new(ics.as_bytes()) {
+        let calendar = calendar.map_err(|err| Error::Config(format!("ICS解析失败: {}", err)))?;
+        for event in calendar.events {
+            if is_all_day_event(&event) {
+                continue;
+            }
+            events.push(parse_event(&event, &tz)?);
+        }
+    }
+
+    let semester_start = events
+        .iter()
+        .map(|event| event.course.start_time)
+        .min()
+        .map(|first| {
+            let monday = first.date_naive()
+                - chrono::Duration::days(first.weekday().num_days_from_monday() as i64);
+            tz.from_local_datetime(&monday.and_hms_opt(0, 0, 0).unwrap())
+                .single()
+                .unwrap()
+        })
+        .ok_or_else(|| Error::Config("ICS中没有任何事件".to_string()))?;
+
+    let courses = events
+        .into_iter()
+        .map(|event| event.into_course(&semester_start))
+        .collect();
+
+    Ok(CourseResponse {
+        courses,
+        semester: Semester {
+            start_date: semester_start,
+            weeks_total: None,
+        },
+        generated_at: Utc::now().with_timezone(&tz),
+        warnings: Vec::new(),
+    })
+}
+
+/// 解析出的单个事件
+struct ParsedEvent {
+    course: Course,
+    recurrence: Option<RecurrenceRule>,
+}
+
+impl ParsedEvent {
+    /// 根据重复规则与例外日期还原上课周次
+    fn into_course(self, semester_start: &DateTime<FixedOffset>) -> Course {
+        let mut course = self.course;
+        let Some(rule) = self
+            .recurrence
+            .filter(|r| r.frequency == "WEEKLY" && (r.until.is_some() || r.count.is_some()))
+        else {
+            return course;
+        };
+
+        let week_of = |time: &DateTime<FixedOffset>| {
+            (time.signed_duration_since(*semester_start).num_days() / 7 + 1) as u32
+        };
+        let step = chrono::Duration::weeks(rule.interval.max(1) as i64);
+        let mut weeks = Vec::new();
+        let mut occurrence = course.start_time;
+        let mut count = 0;
+        loop {
+            if rule.until.is_some_and(|until| occurrence > until)
+                || rule.count.is_some_and(|limit| count >= limit)
+            {
+                break;
+            }
+            if !rule.exception_dates.contains(&occurrence) {
+                weeks.push(week_of(&occurrence));
+            }
+            count += 1;
+            occurrence += step;
+        }
+
+        if !weeks.is_empty() {
+            course.weekday = Some(course.start_time.weekday().number_from_monday());
+            course.weeks = Some(weeks);
+        }
+        course
+    }
+}
+
+/// 判断事件是否为全天事件（`DTSTART;VALUE=DATE`）
+fn is_all_day_event(event: &IcalEvent) -> bool {
+    event
+        .properties
+        .iter()
+        .find(|prop| prop.name.eq_ignore_ascii_case("DTSTART"))
+        .and_then(|prop| prop.params.as_ref())
+        .is_some_and(|params| {
+            params.iter().any(|(name, values)| {
+                name.eq_ignore_ascii_case("VALUE") && values.iter().any(|v| v == "DATE")
+            })
+        })
+}
+
+fn parse_event(event: &IcalEvent, tz: &FixedOffset) -> Result<ParsedEvent> {
+    let property = |name: &str| {
+        event
+            .properties
+            .iter()
+            .find(|prop| prop.name.eq_ignore_ascii_case(name))
+            .and_then(|prop| prop.value.as_deref())
+    };
+
+    let start_raw =
+        property("DTSTART").ok_or_else(|| Error::Config("ICS事件缺少DTSTART字段".to_string()))?;
+    let start_time = parse_ics_datetime(start_raw, tz)?;
+    let end_time = match (property("DTEND"), property("DURATION")) {
+        (Some(value), _) => parse_ics_datetime(value, tz)?,
+        (None, Some(value)) => start_time + parse_ics_duration(value)?,
+        (None, None) => start_time,
+    };
+
+    let summary = unescape_text(property("SUMMARY").unwrap_or_default());
+    let (title, exam_type) = match summary
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("考试] "))
+    {
+        Some((exam_type, title)) => (title.to_string(), Some(exam_type.to_string())),
+        None => (summary, None),
+    };
+    // 生成时标题为 "课程名 - 地点"，仅在有 LOCATION 时拆分
+    let (name, location) = match property("LOCATION").and_then(|_| title.rsplit_once(" - ")) {
+        Some((name, location)) => (name.to_string(), Some(location.to_string())),
+        None => (title, None),
+    };
+
+    let course_type = exam_type.as_ref().map(|_| "考试".to_string());
+    let is_makeup = property("CATEGORIES")
+        .is_some_and(|categories| categories.split(',').any(|c| c == MAKEUP_CATEGORY));
+    let credits = property("X-CQUPT-CREDITS").and_then(|value| value.parse().ok());
+    let mut exception_dates = Vec::new();
+    for prop in event
+        .properties
+        .iter()
+        .filter(|prop| prop.name.eq_ignore_ascii_case("EXDATE"))
+    {
+        for value in prop.value.as_deref().unwrap_or_default().split(',') {
+            exception_dates.push(parse_ics_datetime(value, tz)?);
+        }
+    }
+    let recurrence = property("RRULE")
+        .map(|rrule| parse_rrule(rrule, exception_dates, tz))
+        .transpose()?;
+
+    Ok(ParsedEvent {
+        course: Course {
+            name,
+            location,
+            start_time,
+            end_time,
+            course_type,
+            exam_type,
+            is_makeup,
+            credits,
+            ..Default::default()
+        },
+        recurrence,
+    })
+}
+
+fn parse_rrule(
+    rrule: &str,
+    exception_dates: Vec<DateTime<FixedOffset>>,
+    tz: &FixedOffset,
+) -> Result<RecurrenceRule> {
+    let mut rule = RecurrenceRule {
+        frequency: String::new(),
+        interval: 1,
+        until: None,
+        count: None,
+        by_day: None,
+        exception_dates,
+    };
+    for part in rrule.split(';') {
+        let Some((key, value)) = part.split_once('=') else {
+            continue;
+        };
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => rule.frequency = value.to_ascii_uppercase(),
+            "INTERVAL" => {
+                rule.interval = value
+                    .parse()
+                    .map_err(|_| Error::Config(format!("无效的RRULE INTERVAL: {}", value)))?;
+            }
+            "UNTIL" => rule.until = Some(parse_ics_datetime(value, tz)?),
+            "COUNT" => {
+                rule.count = Some(
+                    value
+                        .parse()
+                        .map_err(|_| Error::Config(format!("无效的RRULE COUNT: {}", value)))?,
+                );
+            }
+            "BYDAY" => {
+                rule.by_day = Some(
+                    value
+                        .split(',')
+                        .filter_map(|day| {
+                            ICS_WEEKDAYS
+                                .iter()
+                                .position(|d| *d == day)
+                                .map(|i| i as u32 + 1)
+                        })
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(rule)
+}
+
+/// 解析ICS日期时间，UTC 与浮动时间统一转换到给定时区
+fn parse_ics_datetime(value: &str, tz: &FixedOffset) -> Result<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")?;
+        return Ok(naive.and_utc().with_timezone(tz));
+    }
+    let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").or_else(|_| {
+        chrono::NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+    })?;
+    tz.from_local_datetime(&naive)
+        .single()
+        .ok_or_else(|| Error::Config(format!("无效的ICS时间: {}", value)))
+}
+
+/// 解析 DURATION 值，支持 `P1D`、`PT1H30M`、`PT90M` 等形式
+fn parse_ics_duration(value: &str) -> Result<chrono::Duration> {
+    let invalid = || Error::Config(format!("无效的ICS DURATION: {}", value));
+    let rest = value.trim().trim_start_matches('+');
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+    let mut duration = chrono::Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        if c == 'T' {
+            continue;
+        }
+        let n: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        duration += match c {
+            'W' => chrono::Duration::weeks(n),
+            'D' => chrono::Duration::days(n),
+            'H' => chrono::Duration::hours(n),
+            'M' => chrono::Duration::minutes(n),
+            'S' => chrono::Duration::seconds(n),
+            _ => return Err(invalid()),
+        };
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(duration)
+}
+
+/// 按 RFC 5545 反转义 TEXT 值
+fn unescape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// 将 BYDAY 含多个星期的重复规则拆分为每个星期一条
+///
+/// DTSTART 只能锚定一天，多天规则的首周容易被客户端漏算。拆分后各事件的
+/// DTSTART 移到首周对应的星期，UNTIL 同步平移，例外日期按星期归属。
+fn split_by_weekday(course: Course, rule: RecurrenceRule) -> Vec<(Course, RecurrenceRule)> {
+    let mut days = match rule.by_day.as_deref() {
+        Some(days) if days.len() > 1 => days.to_vec(),
+        _ => return vec![(course, rule)],
+    };
+    days.sort_unstable();
+    days.dedup();
+
+    let anchor = course.start_time.weekday().number_from_monday() as i64;
+    days.into_iter()
+        .map(|day| {
+            let offset = chrono::Duration::days(day as i64 - anchor);
+            let mut course = course.clone();
+            course.start_time += offset;
+            course.end_time += offset;
+            course.weekday = Some(day);
+            let rule = RecurrenceRule {
+                by_day: Some(vec![day]),
+                until: rule.until.map(|until| until + offset),
+                exception_dates: rule
+                    .exception_dates
+                    .iter()
+                    .filter(|date| date.weekday().number_from_monday() == day)
+                    .copied()
+                    .collect(),
+                ..rule.clone()
+            };
+            (course, rule)
+        })
+        .collect()
+}
+
+/// 转义 HTML 特殊字符
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 按 RFC 5545 将内容行折叠为不超过 75 字节的行，续行以空格开头
+fn fold_line(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_OCTETS * 3 + 2);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
 /// 带重复规则的课程
 #[derive(Debug, Clone)]
 struct CourseWithRecurrence {
     course: Course,
     recurrence: Option<RecurrenceRule>,
+    /// 稳定 UID，已在同一日历内去重
+    stable_uid: String,
 }
 
 impl Default for IcsGenerator {
@@ -467,12 +1723,391 @@ impl Default for IcsGenerator {
 }
 
 #[test]
-fn test_rrule_generation() {
-    use chrono::{FixedOffset, TimeZone};
+fn test_html_description_is_escaped_and_folded() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![Course {
+        name: "高等数学".to_string(),
+        code: Some("A1100010".to_string()),
+        teacher: Some("张老师".to_string()),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        note: Some("带<计算器> & 草稿纸".to_string()),
+        ..Default::default()
+    }]);
+    let generator = IcsGenerator::new(IcsOptions {
+        html_description: true,
+        ..Default::default()
+    });
+
+    let ics = generator.generate(&response).unwrap();
+    assert!(ics.contains("DESCRIPTION:A1100010，任课教师: 张老师，备注: 带<计算器> & 草稿纸\r\n"));
+
+    // 展开折叠行后检查 HTML 内容
+    let unfolded = ics.replace("\r\n ", "");
+    let html = unfolded
+        .lines()
+        .find_map(|line| line.strip_prefix("X-ALT-DESC;FMTTYPE=text/html:"))
+        .unwrap();
+    assert_eq!(
+        html,
+        "A1100010<br><b>任课教师:</b> 张老师<br><b>备注:</b> 带&lt\\;计算器&gt\\; &amp\\; 草稿纸"
+    );
+    let start = ics.find("X-ALT-DESC").unwrap();
+    let end = start + ics[start..].find("\r\nBEGIN:VALARM").unwrap();
+    assert!(ics[start..end].split("\r\n").all(|line| line.len() <= 75));
+
+    // 默认不输出
+    let plain = IcsGenerator::default().generate(&response).unwrap();
+    assert!(!plain.contains("X-ALT-DESC"));
+}
+
+#[test]
+fn test_event_colors() {
+    use chrono::TimeZone;
+    let course = |name: &str, course_type: Option<&str>| Course {
+        name: name.to_string(),
+        course_type: course_type.map(str::to_string),
+        ..Default::default()
+    };
+    let by_name = IcsGenerator::new(IcsOptions {
+        color_by: ColorBy::CourseName,
+        ..Default::default()
+    });
+    let math = by_name.event_color(&course("高等数学", None)).unwrap();
+    assert_eq!(
+        by_name.event_color(&course("高等数学", Some("考试"))),
+        Some(math)
+    );
+    assert_eq!(
+        IcsGenerator::new(IcsOptions {
+            color_by: ColorBy::CourseName,
+            ..Default::default()
+        })
+        .event_color(&course("高等数学", None)),
+        Some(math)
+    );
+    assert_ne!(by_name.event_color(&course("大学英语", None)), Some(math));
+
+    let by_type = IcsGenerator::new(IcsOptions {
+        color_by: ColorBy::Type,
+        ..Default::default()
+    });
+    assert_eq!(
+        by_type.event_color(&course("高等数学", None)),
+        by_type.event_color(&course("大学英语", None))
+    );
+    assert_ne!(
+        by_type.event_color(&course("高等数学", None)),
+        by_type.event_color(&course("高等数学", Some("考试")))
+    );
+    assert_eq!(
+        IcsGenerator::default().event_color(&course("高等数学", None)),
+        None
+    );
+
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![Course {
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        ..course("高等数学", None)
+    }]);
+    let ics = by_name.generate(&response).unwrap();
+    assert!(ics.contains(&format!("COLOR:{}\r\n", math)));
+    // X-APPLE-CALENDAR-COLOR 是日历级属性，不写入事件
+    assert!(!ics.contains("X-APPLE-CALENDAR-COLOR"));
+    assert!(
+        !IcsGenerator::default()
+            .generate(&response)
+            .unwrap()
+            .contains("COLOR:")
+    );
+}
+
+#[test]
+fn test_event_durations_are_clamped() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let course = |name: &str, minutes: i64| tests::course(name, start_time, minutes);
+    let response = tests::response(vec![
+        course("高等数学", 0),
+        course("大学英语", -30),
+        course("大学物理", 100),
+        course("实验课", 20 * 60),
+    ]);
+    let end_times = |options: IcsOptions| {
+        let ics = IcsGenerator::new(options).generate(&response).unwrap();
+        ics.lines()
+            .filter_map(|line| line.strip_prefix("DTEND:"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    // 零或负时长使用默认的 45 分钟，超过 12 小时截断
+    assert_eq!(
+        end_times(IcsOptions::default()),
+        [
+            "20250908T004500Z",
+            "20250908T004500Z",
+            "20250908T014000Z",
+            "20250908T120000Z"
+        ]
+    );
+    assert_eq!(
+        end_times(IcsOptions {
+            min_duration: 90,
+            max_duration: None,
+            ..Default::default()
+        }),
+        [
+            "20250908T013000Z",
+            "20250908T013000Z",
+            "20250908T014000Z",
+            "20250908T200000Z"
+        ]
+    );
+}
+
+#[test]
+fn test_credits_are_included_when_set() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let course = |name: &str, credits: Option<f32>| Course {
+        name: name.to_string(),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        credits,
+        ..Default::default()
+    };
+    let generator = IcsGenerator::default();
+    assert_eq!(
+        generator.build_class_description(&course("高等数学", Some(2.0))),
+        "学分: 2.0"
+    );
+    assert_eq!(
+        generator.build_class_description(&course("形势与政策", Some(0.0))),
+        EMPTY_CLASS_DESCRIPTION
+    );
+
+    let response = tests::response(vec![
+        course("高等数学", Some(2.0)),
+        course("大学英语", None),
+        course("形势与政策", Some(0.0)),
+    ]);
+    let ics = generator.generate(&response).unwrap();
+    assert_eq!(ics.matches("X-CQUPT-CREDITS:").count(), 1);
+    assert!(ics.contains("X-CQUPT-CREDITS:2.0\r\n"));
+    assert_eq!(ics.matches("学分").count(), 1);
+
+    let parsed = parse(&ics).unwrap();
+    let credits: Vec<_> = parsed.courses.iter().map(|c| c.credits).collect();
+    assert_eq!(credits, [Some(2.0), None, None]);
+}
+
+#[test]
+fn test_makeup_events_are_categorized() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 28, 8, 0, 0).unwrap();
+    let course = |name: &str, is_makeup: bool| Course {
+        name: name.to_string(),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        is_makeup,
+        ..Default::default()
+    };
+    let response = tests::response(vec![course("高等数学", false), course("大学英语", true)]);
+
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    assert_eq!(ics.matches("CATEGORIES:").count(), 1);
+    assert!(ics.contains("SUMMARY:大学英语\r\nCATEGORIES:调休补课\r\n"));
+
+    let parsed = parse(&ics).unwrap();
+    let makeup: Vec<_> = parsed
+        .courses
+        .iter()
+        .map(|course| (course.name.as_str(), course.is_makeup))
+        .collect();
+    assert_eq!(makeup, [("高等数学", false), ("大学英语", true)]);
+}
+
+#[test]
+fn test_off_weeks_become_exdates() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let generator = IcsGenerator::default();
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let course = |weeks: Vec<u32>| Course {
+        name: "高等数学".to_string(),
+        start_time: start_time + chrono::Duration::weeks(weeks[0] as i64 - 1),
+        end_time: start_time
+            + chrono::Duration::weeks(weeks[0] as i64 - 1)
+            + chrono::Duration::minutes(100),
+        weeks: Some(weeks),
+        off_weeks: Some(vec![5]),
+        weekday: Some(1),
+        ..Default::default()
+    };
+    let response = tests::response(vec![course((1..=8).collect()), course(vec![5])]);
+
+    let ics = generator.generate(&response).unwrap();
+    // 第5周周一 08:00（UTC 00:00）被排除
+    assert!(ics.contains("EXDATE:20251006T000000Z\r\n"));
+    assert_eq!(ics.matches("EXDATE").count(), 1);
+    // 仅在放假周上课的课程不输出
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+}
+
+#[test]
+fn test_name_aliases_apply_to_summary() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let course = |name: &str, code: &str| Course {
+        name: name.to_string(),
+        code: Some(code.to_string()),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        weeks: Some(vec![1, 2]),
+        weekday: Some(1),
+        ..Default::default()
+    };
+    let generator = IcsGenerator::new(IcsOptions {
+        name_aliases: [
+            ("高等数学A(1)", "高数"),
+            ("大学物理", "物理"),
+            ("大学物理实验", "物理实验"),
+        ]
+        .into_iter()
+        .map(|(raw, alias)| (raw.to_string(), alias.to_string()))
+        .collect(),
+        ..Default::default()
+    });
+
+    // 精确匹配
+    let exact = course("高等数学A(1)", "A1110010");
+    assert_eq!(generator.build_course_title(&exact), "高数");
+    // 取最长的前缀匹配
+    let prefixed = course("大学物理实验B", "A1120040");
+    assert_eq!(generator.build_course_title(&prefixed), "物理实验");
+    // 未命中的课程名保持不变
+    let other = course("线性代数", "A1110030");
+    assert_eq!(generator.build_course_title(&other), "线性代数");
+
+    let response = tests::response(vec![exact]);
+    let ics = generator.generate(&response).unwrap();
+    assert!(ics.contains("SUMMARY:高数\r\n"));
+    assert!(ics.contains("DESCRIPTION:A1110010"));
+}
+
+#[test]
+fn test_until_counts_from_first_attended_week() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
     let generator = IcsGenerator::default();
+    // 第3周周一，提供方将 start_time 设为首个上课周
+    let start_time = tz.with_ymd_and_hms(2025, 9, 22, 8, 0, 0).unwrap();
 
-    let start_time = FixedOffset::east_opt(8 * 3600)
+    let recurrence = generator
+        .create_recurrence_rule(Cow::Owned(vec![3, 4, 5, 6]), None, 1, &start_time, Some(20))
         .unwrap()
+        .unwrap();
+    // UNTIL 落在第6周的上课时间，而不是第8周
+    assert_eq!(
+        recurrence.until,
+        Some(tz.with_ymd_and_hms(2025, 10, 13, 8, 0, 0).unwrap())
+    );
+    assert!(recurrence.exception_dates.is_empty());
+
+    let response = tests::response(vec![Course {
+        name: "形势与政策".to_string(),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        weeks: Some(vec![3, 4, 5, 6]),
+        weekday: Some(1),
+        ..Default::default()
+    }]);
+    let ics = generator.generate(&response).unwrap();
+    assert!(ics.contains("DTSTART:20250922T000000Z\r\n"));
+    assert!(ics.contains("RRULE:FREQ=WEEKLY;UNTIL=20251013T000000Z;BYDAY=MO\r\n"));
+}
+
+#[test]
+fn test_stray_weeks_are_clamped() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let generator = IcsGenerator::default();
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+
+    let recurrence = generator
+        .create_recurrence_rule(Cow::Owned(vec![1, 2, 30]), None, 1, &start_time, Some(18))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        recurrence.until,
+        Some(tz.with_ymd_and_hms(2025, 9, 15, 8, 0, 0).unwrap())
+    );
+    assert!(recurrence.exception_dates.is_empty());
+
+    // 通过学期总周数生效
+    let response = CourseResponse {
+        courses: vec![Course {
+            name: "高等数学".to_string(),
+            start_time,
+            end_time: start_time + chrono::Duration::minutes(100),
+            weeks: Some(vec![1, 2, 30]),
+            weekday: Some(1),
+            ..Default::default()
+        }],
+        semester: Semester {
+            start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+            weeks_total: None,
+        }
+        .with_weeks_total(18)
+        .unwrap(),
+        generated_at: tz.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(),
+        warnings: Vec::new(),
+    };
+    let ics = generator.generate(&response).unwrap();
+    assert!(ics.contains("RRULE:FREQ=WEEKLY;UNTIL=20250915T000000Z;BYDAY=MO\r\n"));
+
+    // 周次全部超出学期的课程被跳过，不影响整个日历
+    let mut stray = response.clone();
+    stray.courses.push(Course {
+        name: "线性代数".to_string(),
+        weeks: Some(vec![25, 30]),
+        ..response.courses[0].clone()
+    });
+    let ics = generator.generate(&stray).unwrap();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(!ics.contains("线性代数"));
+
+    // provider 未给出总周数时不按默认值裁剪
+    let mut unknown = response.clone();
+    unknown.semester.weeks_total = None;
+    let ics = generator.generate(&unknown).unwrap();
+    assert!(ics.contains("RRULE:FREQ=WEEKLY;UNTIL=20260330T000000Z;BYDAY=MO\r\n"));
+
+    assert!(response.semester.clone().with_weeks_total(0).is_err());
+    assert!(
+        response
+            .semester
+            .clone()
+            .with_weeks_total(crate::MAX_WEEKS_TOTAL + 1)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_rrule_generation() {
+    use chrono::TimeZone;
+    let generator = IcsGenerator::default();
+
+    let start_time = crate::CQUPT_TZ
         .with_ymd_and_hms(2024, 9, 2, 10, 0, 0)
         .unwrap();
 
@@ -480,7 +2115,8 @@ fn test_rrule_generation() {
     let weekss = vec![vec![1, 3, 5, 7, 9], vec![2, 4]];
     for weeks in weekss {
         let recurrence = generator
-            .create_recurrence_rule(Cow::Owned(weeks), None, 1, &start_time)
+            .create_recurrence_rule(Cow::Owned(weeks), None, 1, &start_time, None)
+            .unwrap()
             .unwrap();
         assert_eq!(recurrence.frequency, "WEEKLY");
         assert_eq!(recurrence.interval, 2);
@@ -493,7 +2129,8 @@ fn test_rrule_generation() {
     // 测试非连续周次
     let weeks = vec![1, 2, 4, 5, 7];
     let recurrence = generator
-        .create_recurrence_rule(Cow::Owned(weeks), None, 1, &start_time)
+        .create_recurrence_rule(Cow::Owned(weeks), None, 1, &start_time, None)
+        .unwrap()
         .unwrap();
     assert_eq!(recurrence.frequency, "WEEKLY");
     assert_eq!(recurrence.interval, 1);
@@ -502,3 +2139,1154 @@ fn test_rrule_generation() {
     assert_eq!(recurrence.by_day, Some(vec![1]));
     assert_eq!(recurrence.exception_dates.len(), 2); // 第3和第6周缺失
 }
+
+#[test]
+fn test_parse_round_trip() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let semester = Semester {
+        start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+        weeks_total: None,
+    };
+    let courses = vec![
+        Course {
+            name: "高等数学 - A".to_string(),
+            location: Some("2101".to_string()),
+            start_time: tz.with_ymd_and_hms(2025, 9, 9, 8, 0, 0).unwrap(),
+            end_time: tz.with_ymd_and_hms(2025, 9, 9, 9, 40, 0).unwrap(),
+            weeks: Some(vec![1, 2, 3, 5, 6, 8]),
+            weekday: Some(2),
+            ..Default::default()
+        },
+        Course {
+            name: "大学英语".to_string(),
+            location: Some("3208".to_string()),
+            start_time: tz.with_ymd_and_hms(2025, 9, 10, 10, 15, 0).unwrap(),
+            end_time: tz.with_ymd_and_hms(2025, 9, 10, 11, 55, 0).unwrap(),
+            weeks: Some(vec![1, 3, 5, 7]),
+            weekday: Some(3),
+            ..Default::default()
+        },
+        Course {
+            name: "线性代数".to_string(),
+            location: Some("4101".to_string()),
+            start_time: tz.with_ymd_and_hms(2026, 1, 8, 14, 0, 0).unwrap(),
+            end_time: tz.with_ymd_and_hms(2026, 1, 8, 16, 0, 0).unwrap(),
+            course_type: Some("考试".to_string()),
+            exam_type: Some("期末".to_string()),
+            ..Default::default()
+        },
+        // 无地点时标题不带 " - " 后缀，课程名中的 " - " 也不被拆分
+        Course {
+            name: "大学物理 - B".to_string(),
+            start_time: tz.with_ymd_and_hms(2026, 1, 9, 14, 0, 0).unwrap(),
+            end_time: tz.with_ymd_and_hms(2026, 1, 9, 16, 0, 0).unwrap(),
+            course_type: Some("考试".to_string()),
+            exam_type: Some("期末".to_string()),
+            ..Default::default()
+        },
+    ];
+    let response = CourseResponse {
+        courses: courses.clone(),
+        semester: semester.clone(),
+        generated_at: tz.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(),
+        warnings: Vec::new(),
+    };
+
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    assert!(ics.contains("SUMMARY:[期末考试] 大学物理 - B\r\n"));
+    let parsed = parse(&ics).unwrap();
+
+    assert_eq!(parsed.semester.start_date, semester.start_date);
+    assert_eq!(parsed.courses.len(), courses.len());
+    for (original, parsed) in courses.iter().zip(&parsed.courses) {
+        assert_eq!(parsed.name, original.name);
+        assert_eq!(parsed.location, original.location);
+        assert_eq!(parsed.start_time, original.start_time);
+        assert_eq!(parsed.end_time, original.end_time);
+        assert_eq!(parsed.weeks, original.weeks);
+        assert_eq!(parsed.weekday, original.weekday);
+        assert_eq!(parsed.exam_type, original.exam_type);
+    }
+}
+
+#[test]
+fn test_description_styles() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let course = Course {
+        name: "高等数学".to_string(),
+        code: Some("A1100010".to_string()),
+        teacher: Some("张老师".to_string()),
+        location: Some("2101".to_string()),
+        start_time: tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2025, 9, 8, 9, 40, 0).unwrap(),
+        course_type: Some("必修".to_string()),
+        ..Default::default()
+    };
+    let response = tests::response(vec![course.clone()]);
+    let generator = |description_style, include_teacher| {
+        IcsGenerator::new(IcsOptions {
+            description_style,
+            include_teacher,
+            ..Default::default()
+        })
+    };
+
+    let full = generator(DescriptionStyle::Full, true);
+    let description = full.build_course_description(&course);
+    assert!(description.contains("A1100010"));
+    assert!(description.contains("任课教师: 张老师"));
+    assert!(description.contains("该课程是必修课"));
+
+    let compact = generator(DescriptionStyle::Compact, true);
+    assert_eq!(compact.build_course_description(&course), "张老师 · 2101");
+    let compact = generator(DescriptionStyle::Compact, false);
+    assert_eq!(compact.build_course_description(&course), "2101");
+
+    let none = generator(DescriptionStyle::None, true);
+    let ics = none.generate(&response).unwrap();
+    assert_eq!(ics.matches("DESCRIPTION:").count(), 1); // 仅保留提醒中的描述
+
+    assert_eq!(
+        "Compact".parse::<DescriptionStyle>(),
+        Ok(DescriptionStyle::Compact)
+    );
+    assert!("verbose".parse::<DescriptionStyle>().is_err());
+}
+
+#[test]
+fn test_multiple_teachers_description() {
+    let generator = IcsGenerator::default();
+    let course = Course {
+        name: "大学物理".to_string(),
+        teacher: Some("张三,李四".to_string()),
+        ..Default::default()
+    };
+    assert!(
+        generator
+            .build_class_description(&course)
+            .contains("任课教师: 张三、李四")
+    );
+}
+
+#[test]
+fn test_class_description_without_teacher() {
+    let generator = IcsGenerator::new(IcsOptions {
+        include_teacher: false,
+        ..Default::default()
+    });
+    let course = Course {
+        name: "高等数学".to_string(),
+        code: Some("A1100010".to_string()),
+        teacher: Some("张老师".to_string()),
+        course_type: Some("必修".to_string()),
+        ..Default::default()
+    };
+
+    let description = generator.build_class_description(&course);
+    assert!(!description.contains("张老师"));
+    assert!(!description.contains("任课教师"));
+    assert!(description.contains("A1100010"));
+    assert!(description.contains("该课程是必修课"));
+}
+
+#[test]
+fn test_exam_reminders() {
+    use crate::ReminderSpec;
+    let tz = crate::CQUPT_TZ;
+    let course = |name: &str, course_type: &str| Course {
+        name: name.to_string(),
+        start_time: tz.with_ymd_and_hms(2026, 1, 8, 14, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2026, 1, 8, 16, 0, 0).unwrap(),
+        course_type: Some(course_type.to_string()),
+        ..Default::default()
+    };
+    let generator = IcsGenerator::new(IcsOptions {
+        exam_reminders: vec![ReminderSpec::minutes(24 * 60)],
+        ..Default::default()
+    });
+    let event = |course: &Course| {
+        let mut ics = String::new();
+        generator
+            .add_course_event(
+                &mut ics,
+                &CourseWithRecurrence {
+                    course: course.clone(),
+                    recurrence: None,
+                    stable_uid: String::new(),
+                },
+                None,
+                &mut LocationCache::default(),
+                "20250901T000000Z",
+            )
+            .unwrap();
+        ics
+    };
+
+    let exam = event(&course("线性代数 (考试)", "考试"));
+    assert!(exam.contains("DESCRIPTION:考试提醒\r\nTRIGGER:-PT1440M\r\n"));
+    assert!(!exam.contains("TRIGGER:-PT15M"));
+
+    let class = event(&course("高等数学", "必修"));
+    assert!(class.contains("DESCRIPTION:课程提醒\r\nTRIGGER:-PT15M\r\n"));
+    assert!(!class.contains("TRIGGER:-PT1440M"));
+}
+
+#[test]
+fn test_stable_uid_collisions_are_disambiguated() {
+    let tz = crate::CQUPT_TZ;
+    // 同名同时段、教师与地点不同的两门课
+    let course = |teacher: &str, location: &str| Course {
+        name: "体育".to_string(),
+        teacher: Some(teacher.to_string()),
+        location: Some(location.to_string()),
+        start_time: tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2025, 9, 8, 9, 40, 0).unwrap(),
+        weeks: Some((1..=16).collect()),
+        weekday: Some(1),
+        ..Default::default()
+    };
+    let response = tests::response(vec![
+        course("张老师", "风雨操场"),
+        course("李老师", "游泳馆"),
+    ]);
+
+    let generator = IcsGenerator::new(IcsOptions {
+        stable_uids: true,
+        ..Default::default()
+    });
+    let uids = |ics: String| {
+        ics.lines()
+            .filter_map(|line| line.strip_prefix("UID:"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let first = uids(generator.generate(&response).unwrap());
+    assert_eq!(first.len(), 2);
+    assert_ne!(first[0], first[1]);
+    assert_eq!(first[1], first[0].replace("@cqupt-ics", "-2@cqupt-ics"));
+
+    // 重新生成时 UID 保持稳定
+    assert_eq!(uids(generator.generate(&response).unwrap()), first);
+}
+
+#[test]
+fn test_event_sequences() {
+    let tz = crate::CQUPT_TZ;
+    let course = |name: &str, location: &str| Course {
+        name: name.to_string(),
+        location: Some(location.to_string()),
+        start_time: tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2025, 9, 8, 9, 40, 0).unwrap(),
+        weeks: Some((1..=16).collect()),
+        weekday: Some(1),
+        ..Default::default()
+    };
+    /// 按事件顺序提取 (UID, SEQUENCE)
+    fn uid_sequences(ics: &str) -> Vec<(String, String)> {
+        let field = |prefix: &str| {
+            ics.lines()
+                .filter_map(|line| line.strip_prefix(prefix))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        field("UID:").into_iter().zip(field("SEQUENCE:")).collect()
+    }
+
+    let generator = IcsGenerator::default();
+    let mut sequences = EventSequences::default();
+    let first = generator
+        .generate_with_sequences(
+            &tests::response(vec![course("高等数学", "2101"), course("大学英语", "3208")]),
+            &mut sequences,
+        )
+        .unwrap();
+    let first = uid_sequences(&first);
+    assert_eq!(first[0].1, "0");
+    assert_eq!(first[1].1, "0");
+
+    // 重新生成相同内容，序号不变
+    let same = generator
+        .generate_with_sequences(
+            &tests::response(vec![course("高等数学", "2101"), course("大学英语", "3208")]),
+            &mut sequences,
+        )
+        .unwrap();
+    assert_eq!(uid_sequences(&same), first);
+
+    // 修改地点，UID 不变而序号递增，未修改的课程保持原序号
+    let moved = generator
+        .generate_with_sequences(
+            &tests::response(vec![course("高等数学", "4101"), course("大学英语", "3208")]),
+            &mut sequences,
+        )
+        .unwrap();
+    let moved = uid_sequences(&moved);
+    assert_eq!(moved[0], (first[0].0.clone(), "1".to_string()));
+    assert_eq!(moved[1], first[1]);
+    assert_eq!(sequences.get(&first[0].0), Some(1));
+
+    // 序号记录可以持久化
+    let restored: EventSequences =
+        serde_json::from_str(&serde_json::to_string(&sequences).unwrap()).unwrap();
+    assert_eq!(restored.get(&first[0].0), Some(1));
+
+    // 未记录序号时使用随机 UID 与 SEQUENCE:0
+    let plain = generator
+        .generate(&tests::response(vec![course("高等数学", "2101")]))
+        .unwrap();
+    assert!(plain.contains("SEQUENCE:0\r\n"));
+    assert!(!plain.contains(&first[0].0));
+}
+
+#[test]
+fn test_generate_split() {
+    let tz = crate::CQUPT_TZ;
+    let at = |day: u32, hour: u32| tz.with_ymd_and_hms(2025, 9, day, hour, 0, 0).unwrap();
+    let course = |name: &str, course_type: Option<&str>| Course {
+        name: name.to_string(),
+        start_time: at(8, 8),
+        end_time: at(8, 9),
+        course_type: course_type.map(str::to_string),
+        ..Default::default()
+    };
+    let response = tests::response(vec![
+        course("高等数学", Some("必修")),
+        course("体育", Some("选修")),
+        course("线性代数", Some("考试")),
+    ]);
+    let generator = IcsGenerator::new(IcsOptions {
+        calendar_name: Some("课程表".to_string()),
+        ..Default::default()
+    });
+
+    // 没有自定义日程，不输出 custom 日历
+    let calendars = generator.generate_split(&response, SplitBy::Kind).unwrap();
+    assert_eq!(calendars.len(), 2);
+    let classes = &calendars["classes"];
+    assert!(classes.contains("X-WR-CALNAME:课程表 - 课程\r\n"));
+    assert_eq!(classes.matches("BEGIN:VEVENT").count(), 2);
+    let exams = &calendars["exams"];
+    assert!(exams.contains("X-WR-CALNAME:课程表 - 考试\r\n"));
+    assert_eq!(exams.matches("BEGIN:VEVENT").count(), 1);
+    for ics in calendars.values() {
+        IcsGenerator::validate(ics).unwrap();
+    }
+
+    let calendars = generator
+        .generate_split(&response, SplitBy::CourseType)
+        .unwrap();
+    let mut keys: Vec<_> = calendars.keys().map(String::as_str).collect();
+    keys.sort();
+    assert_eq!(keys, ["必修", "考试", "选修"]);
+    assert!(calendars["选修"].contains("X-WR-CALNAME:课程表 - 选修\r\n"));
+}
+
+#[test]
+fn test_calendar_id_is_stable() {
+    let autumn = Semester::from_date_str("2025-09-08").unwrap();
+    let spring = Semester::from_date_str("2026-03-02").unwrap();
+    let id = calendar_id("redrock", "2023000000", &autumn);
+    assert_eq!(id, calendar_id("redrock", "2023000000", &autumn));
+    assert!(id.starts_with("cqupt-ics-"));
+    assert_ne!(id, calendar_id("wecqupt", "2023000000", &autumn));
+    assert_ne!(id, calendar_id("redrock", "2023000001", &autumn));
+    assert_ne!(id, calendar_id("redrock", "2023000000", &spring));
+
+    let response = CourseResponse {
+        courses: Vec::new(),
+        semester: autumn,
+        generated_at: Utc::now().with_timezone(&crate::CQUPT_TZ),
+        warnings: Vec::new(),
+    };
+    let generator = IcsGenerator::new(IcsOptions {
+        calendar_id: Some(id.clone()),
+        ..Default::default()
+    });
+    let relcalid = format!("X-WR-RELCALID:{}\r\n", id);
+    assert!(generator.generate(&response).unwrap().contains(&relcalid));
+    assert!(generator.generate(&response).unwrap().contains(&relcalid));
+    assert!(
+        !IcsGenerator::default()
+            .generate(&response)
+            .unwrap()
+            .contains("X-WR-RELCALID")
+    );
+}
+
+#[test]
+fn test_custom_schedule_as_todo() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start = tz.with_ymd_and_hms(2025, 9, 10, 19, 0, 0).unwrap();
+    let response = CourseResponse {
+        courses: vec![
+            Course {
+                name: "高等数学".to_string(),
+                start_time: start - chrono::Duration::hours(11),
+                end_time: start - chrono::Duration::hours(9),
+                ..Default::default()
+            },
+            Course {
+                name: "提交实验报告".to_string(),
+                course_type: Some("自定义日程".to_string()),
+                start_time: start,
+                end_time: start + chrono::Duration::hours(1),
+                ..Default::default()
+            },
+        ],
+        semester: Semester::from_date_str("2025-09-08").unwrap(),
+        generated_at: start,
+        warnings: Vec::new(),
+    };
+
+    let ics = IcsGenerator::new(IcsOptions {
+        custom_as_todo: true,
+        ..Default::default()
+    })
+    .generate(&response)
+    .unwrap();
+    IcsGenerator::validate(&ics).unwrap();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert_eq!(ics.matches("BEGIN:VTODO").count(), 1);
+    let todo = &ics[ics.find("BEGIN:VTODO").unwrap()..ics.find("END:VTODO").unwrap()];
+    assert!(todo.contains("DUE:20250910T110000Z\r\n"));
+    assert!(todo.contains("SUMMARY:提交实验报告\r\n"));
+    assert!(todo.contains("TRIGGER;RELATED=END:-PT15M\r\n"));
+    assert!(!todo.contains("DTSTART"));
+    assert!(!todo.contains("DTEND"));
+
+    // 按周重复的待办：DTSTART 为开始时间，DUE 为结束时间，提醒相对开始
+    let mut weekly = response.clone();
+    weekly.courses[1].weeks = Some(vec![1, 2, 3]);
+    weekly.courses[1].weekday = Some(3);
+    let ics = IcsGenerator::new(IcsOptions {
+        custom_as_todo: true,
+        ..Default::default()
+    })
+    .generate(&weekly)
+    .unwrap();
+    IcsGenerator::validate(&ics).unwrap();
+    let todo = &ics[ics.find("BEGIN:VTODO").unwrap()..ics.find("END:VTODO").unwrap()];
+    assert!(todo.contains("DTSTART:20250910T110000Z\r\n"));
+    assert!(todo.contains("DUE:20250910T120000Z\r\n"));
+    assert!(todo.contains("TRIGGER:-PT15M\r\n"));
+    assert!(todo.contains("RRULE:"));
+
+    // 校验同样覆盖待办
+    let broken = ics.replace("DUE:20250910T120000Z", "DUE:20250910T100000Z");
+    assert!(IcsGenerator::validate(&broken).is_err());
+
+    // 默认仍输出为事件
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+    assert!(!ics.contains("VTODO"));
+}
+
+#[test]
+fn test_content_filter() {
+    let tz = crate::CQUPT_TZ;
+    let at = |day: u32, hour: u32| tz.with_ymd_and_hms(2025, 9, day, hour, 0, 0).unwrap();
+    let course = |name: &str, course_type: Option<&str>, note: Option<&str>| Course {
+        name: name.to_string(),
+        start_time: at(8, 8),
+        end_time: at(8, 9),
+        course_type: course_type.map(str::to_string),
+        note: note.map(str::to_string),
+        ..Default::default()
+    };
+    let full = tests::response(vec![
+        course("高等数学", Some("必修"), None),
+        course("线性代数", Some("考试"), None),
+        course("社团活动", None, Some("自定义日程: 招新宣讲")),
+        course("班会", Some("自定义日程"), None),
+    ]);
+    let summaries = |filter: ContentFilter, response: &CourseResponse| {
+        let ics = IcsGenerator::new(IcsOptions {
+            content_filter: filter,
+            description_style: DescriptionStyle::None,
+            ..Default::default()
+        })
+        .generate(response)
+        .unwrap();
+        ics.lines()
+            .filter_map(|line| line.strip_prefix("SUMMARY:"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(summaries(ContentFilter::All, &full).len(), 4);
+    assert_eq!(summaries(ContentFilter::ClassesOnly, &full), ["高等数学"]);
+    assert_eq!(summaries(ContentFilter::ExamsOnly, &full).len(), 1);
+    assert_eq!(
+        summaries(ContentFilter::CustomOnly, &full),
+        ["社团活动", "班会"]
+    );
+
+    // 没有考试时仍输出合法的空日历
+    let no_exams = tests::response(vec![course("高等数学", Some("必修"), None)]);
+    let ics = IcsGenerator::new(IcsOptions {
+        content_filter: ContentFilter::ExamsOnly,
+        ..Default::default()
+    })
+    .generate(&no_exams)
+    .unwrap();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(!ics.contains("BEGIN:VEVENT"));
+}
+
+#[test]
+fn test_streamed_output_matches_buffered() {
+    let tz = crate::CQUPT_TZ;
+    let course = |name: &str, day: u32| Course {
+        name: name.to_string(),
+        location: Some("2101".to_string()),
+        start_time: tz.with_ymd_and_hms(2025, 9, day, 8, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2025, 9, day, 9, 40, 0).unwrap(),
+        weeks: Some(vec![1, 2, 4, 5]),
+        weekday: Some(day - 7),
+        ..Default::default()
+    };
+    let response = tests::response(vec![course("高等数学", 8), course("大学英语", 9)]);
+    // 使用稳定 UID，并去掉 DTSTAMP 以便逐字节比较
+    let generator = IcsGenerator::new(IcsOptions {
+        stable_uids: true,
+        ..Default::default()
+    });
+    let strip_dtstamp = |ics: &str| {
+        ics.split_inclusive("\r\n")
+            .filter(|line| !line.starts_with("DTSTAMP:"))
+            .collect::<String>()
+    };
+
+    /// 记录每次写入，确认按事件分块写出
+    #[derive(Default)]
+    struct ChunkWriter(Vec<Vec<u8>>);
+    impl Write for ChunkWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut streamed = ChunkWriter::default();
+    generator
+        .generate_to_writer(&response, &mut streamed)
+        .unwrap();
+    assert_eq!(streamed.0.len(), 4);
+    let streamed = String::from_utf8(streamed.0.concat()).unwrap();
+    let buffered = generator.generate(&response).unwrap();
+    assert_eq!(
+        strip_dtstamp(&streamed).as_bytes(),
+        strip_dtstamp(&buffered).as_bytes()
+    );
+}
+
+#[test]
+fn test_all_day_course_event() {
+    let tz = crate::CQUPT_TZ;
+    let course = Course {
+        name: "社团活动".to_string(),
+        start_time: tz.with_ymd_and_hms(2025, 9, 9, 0, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2025, 9, 10, 0, 0, 0).unwrap(),
+        weeks: Some(vec![1, 2, 4]),
+        weekday: Some(2),
+        all_day: true,
+        ..Default::default()
+    };
+    let response = tests::response(vec![course]);
+
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    assert!(ics.contains("DTSTART;VALUE=DATE:20250909\r\n"));
+    assert!(ics.contains("DTEND;VALUE=DATE:20250910\r\n"));
+    assert!(ics.contains(";UNTIL=20250930;"));
+    assert!(ics.contains("EXDATE;VALUE=DATE:20250923\r\n"));
+    assert!(!ics.contains("DTSTART:"));
+}
+
+#[test]
+fn test_date_window_clamps_recurrence() {
+    let tz = crate::CQUPT_TZ;
+    let date = |month: u32, day: u32| NaiveDate::from_ymd_opt(2025, month, day).unwrap();
+    let course = |name: &str, weeks: Vec<u32>| Course {
+        name: name.to_string(),
+        start_time: tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2025, 9, 8, 9, 40, 0).unwrap(),
+        weeks: Some(weeks),
+        weekday: Some(1),
+        ..Default::default()
+    };
+    let exam = Course {
+        name: "线性代数".to_string(),
+        start_time: tz.with_ymd_and_hms(2026, 1, 8, 14, 0, 0).unwrap(),
+        end_time: tz.with_ymd_and_hms(2026, 1, 8, 16, 0, 0).unwrap(),
+        course_type: Some("考试".to_string()),
+        ..Default::default()
+    };
+
+    // 第 1-16 周，从第 5 周（10-06）开始、到第 10 周（11-10）为止
+    let window = (date(10, 1), date(11, 12));
+    let clamped = clamp_to_window(&course("高等数学", (1..=16).collect()), window.0, window.1)
+        .unwrap()
+        .into_owned();
+    assert_eq!(
+        clamped.start_time,
+        tz.with_ymd_and_hms(2025, 10, 6, 8, 0, 0).unwrap()
+    );
+    assert_eq!(clamped.weeks, Some((5..=10).collect()));
+
+    // 全部上课都在窗口之前的课程与窗口外的考试被丢弃
+    assert!(clamp_to_window(&course("军事理论", vec![1, 2, 3]), window.0, window.1).is_none());
+    assert!(clamp_to_window(&exam, window.0, window.1).is_none());
+
+    let response = tests::response(vec![
+        course("高等数学", (1..=16).collect()),
+        course("军事理论", vec![1, 2, 3]),
+        exam,
+    ]);
+    let ics = IcsGenerator::new(IcsOptions {
+        date_window: Some(window),
+        ..Default::default()
+    })
+    .generate(&response)
+    .unwrap();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(ics.contains("DTSTART:20251006T000000Z\r\n"));
+    assert!(ics.contains("RRULE:FREQ=WEEKLY;UNTIL=20251110T000000Z;BYDAY=MO\r\n"));
+}
+
+#[test]
+fn test_calendar_header() {
+    let response = tests::response(Vec::new());
+
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    assert!(ics.contains(&format!("PRODID:{}\r\n", DEFAULT_PRODID)));
+    assert!(ics.contains("X-WR-TIMEZONE:Asia/Shanghai\r\n"));
+    assert!(!ics.contains("X-WR-CALDESC"));
+
+    let ics = IcsGenerator::new(IcsOptions {
+        prodid: Some("-//Example//Fork Calendar//EN".to_string()),
+        calendar_description: Some("本学期课程, 含考试".to_string()),
+        ..Default::default()
+    })
+    .generate(&response)
+    .unwrap();
+    assert!(ics.contains("PRODID:-//Example//Fork Calendar//EN\r\n"));
+    assert!(!ics.contains(DEFAULT_PRODID));
+    assert!(ics.contains("X-WR-CALDESC:本学期课程\\, 含考试\r\n"));
+}
+
+#[test]
+fn test_multi_weekday_rule_is_split() {
+    let tz = crate::CQUPT_TZ;
+    // 周三开始的课程，规则同时包含周一与周三
+    let start = tz.with_ymd_and_hms(2025, 9, 10, 8, 0, 0).unwrap();
+    let course = Course {
+        name: "体育".to_string(),
+        start_time: start,
+        end_time: start + chrono::Duration::minutes(90),
+        weeks: Some(vec![1, 2, 3]),
+        weekday: Some(3),
+        ..Default::default()
+    };
+    let rule = RecurrenceRule {
+        frequency: "WEEKLY".to_string(),
+        interval: 1,
+        until: Some(start + chrono::Duration::weeks(2)),
+        count: None,
+        by_day: Some(vec![3, 1]),
+        exception_dates: vec![
+            tz.with_ymd_and_hms(2025, 9, 15, 8, 0, 0).unwrap(),
+            start + chrono::Duration::weeks(1),
+        ],
+    };
+
+    let events = split_by_weekday(course, rule);
+    assert_eq!(events.len(), 2);
+    let (monday, monday_rule) = &events[0];
+    assert_eq!(
+        monday.start_time,
+        tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap()
+    );
+    assert_eq!(monday.weekday, Some(1));
+    assert_eq!(monday_rule.by_day, Some(vec![1]));
+    assert_eq!(
+        monday_rule.until,
+        Some(tz.with_ymd_and_hms(2025, 9, 22, 8, 0, 0).unwrap())
+    );
+    assert_eq!(
+        monday_rule.exception_dates,
+        vec![tz.with_ymd_and_hms(2025, 9, 15, 8, 0, 0).unwrap()]
+    );
+    let (wednesday, wednesday_rule) = &events[1];
+    assert_eq!(wednesday.start_time, start);
+    assert_eq!(wednesday_rule.by_day, Some(vec![3]));
+    assert_eq!(
+        wednesday_rule.exception_dates,
+        vec![start + chrono::Duration::weeks(1)]
+    );
+
+    // 单个星期的规则保持不变
+    let single = split_by_weekday(
+        wednesday.clone(),
+        RecurrenceRule {
+            by_day: Some(vec![3]),
+            ..wednesday_rule.clone()
+        },
+    );
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].0.start_time, start);
+}
+
+#[test]
+fn test_invalid_by_day_is_rejected() {
+    let tz = crate::CQUPT_TZ;
+    let generator = IcsGenerator::default();
+    let rule = |by_day: Vec<u32>| RecurrenceRule {
+        frequency: "WEEKLY".to_string(),
+        interval: 1,
+        until: None,
+        count: Some(4),
+        by_day: Some(by_day),
+        exception_dates: Vec::new(),
+    };
+
+    let mut ics = String::new();
+    generator
+        .add_recurrence_rule(&mut ics, &rule(vec![1, 7]), false)
+        .unwrap();
+    assert_eq!(ics, "RRULE:FREQ=WEEKLY;COUNT=4;BYDAY=MO,SU\r\n");
+
+    for invalid in [vec![9], vec![0], vec![1, 8]] {
+        let mut ics = String::new();
+        assert!(matches!(
+            generator.add_recurrence_rule(&mut ics, &rule(invalid), false),
+            Err(Error::IcsGeneration(_))
+        ));
+        assert!(!ics.contains("BYDAY"));
+    }
+
+    // 课程的星期超出范围时生成失败
+    let start = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    assert!(matches!(
+        generator.create_recurrence_rule(Cow::Owned(vec![1, 2, 3]), None, 9, &start, None,),
+        Err(Error::IcsGeneration(_))
+    ));
+}
+
+#[test]
+fn test_class_description_weeks() {
+    assert_eq!(compress_weeks(&[1, 2, 3, 5, 6, 8]), "1-3,5-6,8");
+    assert_eq!(compress_weeks(&[8, 1, 2, 2]), "1-2,8");
+    assert_eq!(compress_weeks(&[4]), "4");
+    assert_eq!(compress_weeks(&[]), "");
+
+    let generator = IcsGenerator::default();
+    let course = Course {
+        name: "高等数学".to_string(),
+        weeks: Some(vec![1, 2, 3, 4, 5, 6, 7, 8, 10, 12]),
+        ..Default::default()
+    };
+    assert!(
+        generator
+            .build_class_description(&course)
+            .contains("行课周次：1-8,10,12")
+    );
+
+    // 已有节次信息时周次只出现一次
+    let with_lessons = Course {
+        begin_lesson: Some(1),
+        lesson_duration: Some(2),
+        ..course.clone()
+    };
+    let description = generator.build_class_description(&with_lessons);
+    assert!(description.contains("在第1,2,3,4,5,6,7,8,10,12周 1-2节行课"));
+    assert!(!description.contains("行课周次"));
+
+    let single = Course {
+        weeks: Some(vec![3]),
+        ..course
+    };
+    assert!(
+        generator
+            .build_class_description(&single)
+            .contains("本学期第3周")
+    );
+}
+
+/// 测试共用的课表与课程构造
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 学期从 2025-09-08 开始、生成于 2025-09-01 的课表
+    pub(super) fn response(courses: Vec<Course>) -> CourseResponse {
+        let tz = crate::CQUPT_TZ;
+        CourseResponse {
+            courses,
+            semester: Semester {
+                start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+                weeks_total: None,
+            },
+            generated_at: tz.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// 从 `start` 开始、持续 `minutes` 分钟的课程
+    pub(super) fn course(name: &str, start: DateTime<FixedOffset>, minutes: i64) -> Course {
+        Course {
+            name: name.to_string(),
+            start_time: start,
+            end_time: start + chrono::Duration::minutes(minutes),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod escape_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// escape_text 预期保留下来的内容
+    ///
+    /// ical 解析时会去掉行尾的空白以及值开头的冒号，这里一并处理。
+    fn sanitize(text: &str) -> String {
+        let kept: String = text
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .chars()
+            .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+            .collect();
+        kept.trim_end_matches([' ', '\t'])
+            .trim_start_matches(':')
+            .to_string()
+    }
+
+    /// 生成只含单个课程的日历，再用 ical 解析出 SUMMARY
+    fn round_trip_summary(name: &str) -> String {
+        let tz = crate::CQUPT_TZ;
+        let response = tests::response(vec![Course {
+            name: name.to_string(),
+            start_time: tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap(),
+            end_time: tz.with_ymd_and_hms(2025, 9, 8, 9, 40, 0).unwrap(),
+            ..Default::default()
+        }]);
+        let ics = IcsGenerator::default().generate(&response).unwrap();
+
+        let calendar = IcalParser::new(ics.as_bytes())
+            .next()
+            .expect("no calendar")
+            .expect("failed to parse generated calendar");
+        let summary = calendar.events[0]
+            .properties
+            .iter()
+            .find(|p| p.name == "SUMMARY")
+            .and_then(|p| p.value.clone())
+            .unwrap_or_default();
+        unescape_text(&summary)
+    }
+
+    #[test]
+    fn escape_text_handles_special_characters() {
+        let generator = IcsGenerator::default();
+        assert_eq!(generator.escape_text(r"a\nb"), r"a\\nb");
+        assert_eq!(generator.escape_text("a\r\nb\rc"), r"a\nb\nc");
+        assert_eq!(generator.escape_text("a,b;c:d"), r"a\,b\;c:d");
+        assert_eq!(generator.escape_text("a\u{b}b\u{0}c\td"), "abc\td");
+    }
+
+    #[test]
+    fn tricky_course_names_round_trip() {
+        let corpus = [
+            "高等数学（上）",
+            "大学物理A：力学, 热学; 光学",
+            r"C\C++程序设计 \n 实验",
+            "形势与政策\r\n第二讲",
+            "数据结构\u{b}课程设计",
+            "🎓 毕业设计 🚀",
+            "英语听说 👩‍🏫; Unit 3",
+            "",
+        ];
+        for name in corpus {
+            assert_eq!(round_trip_summary(name), sanitize(name), "name: {:?}", name);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_course_names_round_trip(
+            name in "[\\\\,;:\r\n\t\u{b}\u{7f} a-zA-Z0-9中文课程设计（）😀🎓👩‍🏫]{0,40}"
+        ) {
+            prop_assert_eq!(round_trip_summary(&name), sanitize(&name));
+        }
+    }
+}
+
+#[test]
+fn test_validate_generated_ics() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![Course {
+        name: "高等数学".to_string(),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        weeks: Some(vec![1, 2, 3]),
+        weekday: Some(1),
+        ..Default::default()
+    }]);
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    assert!(IcsGenerator::validate(&ics).is_ok());
+
+    let broken = ics
+        .lines()
+        .filter(|line| !line.starts_with("UID:") && !line.starts_with("DTEND"))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    match IcsGenerator::validate(&broken) {
+        Err(Error::IcsGeneration(message)) => {
+            assert!(message.contains("UID"), "{message}");
+            assert!(message.contains("DTEND/DURATION"), "{message}");
+        }
+        other => panic!("expected validation error, got {other:?}"),
+    }
+    assert!(IcsGenerator::validate("").is_err());
+}
+
+#[test]
+fn test_rrule_string_round_trip() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let rule = RecurrenceRule {
+        frequency: "WEEKLY".to_string(),
+        interval: 2,
+        until: Some(tz.with_ymd_and_hms(2025, 12, 29, 9, 40, 0).unwrap()),
+        count: None,
+        by_day: Some(vec![1]),
+        exception_dates: Vec::new(),
+    };
+    let rrule = rule.to_rrule_string().unwrap();
+    assert_eq!(
+        rrule,
+        "FREQ=WEEKLY;INTERVAL=2;UNTIL=20251229T014000Z;BYDAY=MO"
+    );
+
+    let parsed = RecurrenceRule::parse(&format!("RRULE:{rrule}")).unwrap();
+    assert_eq!(parsed.frequency, "WEEKLY");
+    assert_eq!(parsed.interval, 2);
+    assert_eq!(parsed.until, rule.until);
+    assert_eq!(parsed.count, None);
+    assert_eq!(parsed.by_day, Some(vec![1]));
+    assert_eq!(parsed.to_rrule_string().unwrap(), rrule);
+
+    let daily = RecurrenceRule::parse("FREQ=DAILY;COUNT=3").unwrap();
+    assert_eq!((daily.interval, daily.count), (1, Some(3)));
+    assert!(RecurrenceRule::parse("INTERVAL=2;BYDAY=MO").is_err());
+    assert!(RecurrenceRule::parse("FREQ=WEEKLY;INTERVAL=x").is_err());
+}
+
+#[test]
+fn test_location_detail_levels() {
+    use crate::LocationDetail;
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![Course {
+        name: "高等数学".to_string(),
+        location: Some("2101".to_string()),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        ..Default::default()
+    }]);
+    let generate = |location_detail| {
+        IcsGenerator::new(IcsOptions {
+            location_detail,
+            ..Default::default()
+        })
+        .generate(&response)
+        .unwrap()
+    };
+
+    let plain = generate(LocationDetail::PlainName);
+    assert!(plain.contains("LOCATION:2101\r\n"));
+    assert!(!plain.contains("GEO:"));
+    assert!(!plain.contains("X-APPLE-STRUCTURED-LOCATION"));
+
+    let with_geo = generate(LocationDetail::NameWithGeo);
+    assert!(with_geo.contains("LOCATION:2101\r\nGEO:"));
+    assert!(!with_geo.contains("X-APPLE-STRUCTURED-LOCATION"));
+
+    let full = generate(LocationDetail::Full);
+    assert!(full.contains("GEO:"));
+    assert!(full.contains("X-APPLE-STRUCTURED-LOCATION"));
+    assert!(!full.contains("LOCATION:2101\r\n"));
+
+    assert_eq!(
+        "name-with-geo".parse::<LocationDetail>(),
+        Ok(LocationDetail::NameWithGeo)
+    );
+}
+
+#[test]
+fn test_fixed_dtstamp_output_is_reproducible() {
+    const GOLDEN: &str = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+PRODID:-//CQUPT ICS//CQUPT Course Calendar//CN\r\n\
+CALSCALE:GREGORIAN\r\n\
+METHOD:PUBLISH\r\n\
+X-WR-CALNAME:CQUPT课程表\r\n\
+X-WR-TIMEZONE:Asia/Shanghai\r\n\
+BEGIN:VEVENT\r\n\
+UID:2e90fcd946c6e601@cqupt-ics\r\n\
+DTSTAMP:20250901T000000Z\r\n\
+SEQUENCE:0\r\n\
+DTSTART:20250908T000000Z\r\n\
+DTEND:20250908T014000Z\r\n\
+SUMMARY:高等数学 - 2101\r\n\
+LOCATION:2101\r\n\
+DESCRIPTION:A1110010，行课周次：1-3\r\n\
+BEGIN:VALARM\r\n\
+ACTION:DISPLAY\r\n\
+DESCRIPTION:课程提醒\r\n\
+TRIGGER:-PT15M\r\n\
+END:VALARM\r\n\
+RRULE:FREQ=WEEKLY;UNTIL=20250922T000000Z;BYDAY=MO\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![Course {
+        name: "高等数学".to_string(),
+        code: Some("A1110010".to_string()),
+        location: Some("2101".to_string()),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(100),
+        weeks: Some(vec![1, 2, 3]),
+        weekday: Some(1),
+        ..Default::default()
+    }]);
+    let generator = IcsGenerator::new(IcsOptions {
+        stable_uids: true,
+        location_detail: crate::LocationDetail::PlainName,
+        fixed_dtstamp: Some(Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap()),
+        ..Default::default()
+    });
+
+    let ics = generator.generate(&response).unwrap();
+    assert_eq!(ics, generator.generate(&response).unwrap());
+    assert_eq!(ics, GOLDEN);
+}
+
+#[test]
+fn test_dtstamp_from_response_and_stable_holiday_uids() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let response = CourseResponse {
+        courses: Vec::new(),
+        semester: Semester {
+            start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+            weeks_total: None,
+        },
+        generated_at: tz.with_ymd_and_hms(2025, 9, 1, 8, 0, 0).unwrap(),
+        warnings: Vec::new(),
+    };
+    let holidays = vec![HolidayEvent {
+        name: "国庆节".to_string(),
+        start: NaiveDate::from_ymd_opt(2025, 10, 1).unwrap(),
+        end: NaiveDate::from_ymd_opt(2025, 10, 8).unwrap(),
+    }];
+    let generate = |stable_uids, dtstamp_from_response| {
+        IcsGenerator::new(IcsOptions {
+            include_holiday_events: true,
+            stable_uids,
+            dtstamp_from_response,
+            ..Default::default()
+        })
+        .with_holiday_events(holidays.clone())
+        .generate(&response)
+        .unwrap()
+    };
+
+    let ics = generate(true, true);
+    assert!(ics.contains("DTSTAMP:20250901T000000Z\r\n"));
+    assert_eq!(ics, generate(true, true));
+    // 与 fixed_dtstamp 一致，DTSTAMP 可复现时放假日 UID 也可复现
+    assert_eq!(generate(false, true), generate(false, true));
+    let uid = |ics: &str| {
+        ics.lines()
+            .find(|line| line.starts_with("UID:"))
+            .map(str::to_string)
+    };
+    assert_ne!(uid(&generate(false, false)), uid(&generate(false, false)));
+}
+
+#[test]
+fn test_omit_empty_description() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![tests::course("形势与政策", start_time, 100)]);
+    let generate = |omit_empty_description| {
+        IcsGenerator::new(IcsOptions {
+            omit_empty_description,
+            reminder_minutes: None,
+            ..Default::default()
+        })
+        .generate(&response)
+        .unwrap()
+    };
+
+    assert!(generate(false).contains("DESCRIPTION:暂无课程详情\r\n"));
+    assert!(!generate(true).contains("DESCRIPTION"));
+}
+
+#[test]
+fn test_use_duration_replaces_dtend() {
+    use chrono::TimeZone;
+    let tz = crate::CQUPT_TZ;
+    let start_time = tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap();
+    let response = tests::response(vec![Course {
+        name: "高等数学".to_string(),
+        start_time,
+        end_time: start_time + chrono::Duration::minutes(90),
+        weeks: Some(vec![1, 2]),
+        weekday: Some(1),
+        ..Default::default()
+    }]);
+    let generate = |use_duration| {
+        IcsGenerator::new(IcsOptions {
+            use_duration,
+            ..Default::default()
+        })
+        .generate(&response)
+        .unwrap()
+    };
+
+    let ics = generate(true);
+    assert!(ics.contains("DTSTART:20250908T000000Z\r\nDURATION:PT90M\r\n"));
+    assert!(!ics.contains("DTEND"));
+    assert!(IcsGenerator::validate(&ics).is_ok());
+    assert_eq!(
+        parse(&ics).unwrap().courses[0].end_time,
+        response.courses[0].end_time
+    );
+
+    let ics = generate(false);
+    assert!(ics.contains("DTEND:20250908T013000Z\r\n"));
+    assert!(!ics.contains("DURATION"));
+
+    assert_eq!(
+        parse_ics_duration("P1DT1H30M").unwrap(),
+        chrono::Duration::minutes(24 * 60 + 90)
+    );
+    assert!(parse_ics_duration("PT90").is_err());
+}
@@ -12,7 +12,7 @@ pub mod providers;
 pub mod types;
 
 // Re-export core types and error handling
-pub use error::{Error, Result};
+pub use error::{AuthFailReason, Error, Result};
 pub use types::*;
 
 /// Commonly used items
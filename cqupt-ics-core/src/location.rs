@@ -1,13 +1,72 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{PoisonError, RwLock},
+};
 
 use regex::Regex;
+use serde::Serialize;
 use serde_json;
 
-use crate::{LocationMapping, Result};
+use crate::{LocationDetail, LocationMapping, Result};
+
+/// 模糊匹配的默认相似度阈值（Jaro-Winkler）
+pub const DEFAULT_FUZZY_THRESHOLD: f64 = 0.85;
+
+/// 表示线上课程的默认地点关键字
+pub const DEFAULT_ONLINE_KEYWORDS: &[&str] = &[
+    "线上",
+    "在线",
+    "网课",
+    "腾讯会议",
+    "雨课堂",
+    "钉钉",
+    "学习通",
+    "超星",
+    "zoom",
+];
+
+/// 线上课程输出的地点
+const ONLINE_LOCATION: &str = "线上教学";
+
+/// 地点是否包含任一线上关键字（ASCII 不区分大小写）
+pub fn is_online_location<S: AsRef<str>>(loc: &str, keywords: &[S]) -> bool {
+    let loc = loc.to_ascii_lowercase();
+    keywords
+        .iter()
+        .map(|keyword| keyword.as_ref().to_ascii_lowercase())
+        .any(|keyword| !keyword.is_empty() && loc.contains(&keyword))
+}
+
+/// 模糊匹配时两者字符数之比的下限，避免过短的输入命中长名称
+const MIN_FUZZY_LENGTH_RATIO: f64 = 0.5;
+
+/// 名称中第一段编号（阿拉伯数字或中文数字），如「第八教学楼」中的「八」
+fn leading_numeral(name: &str) -> Option<&str> {
+    let is_numeral = |c: char| c.is_ascii_digit() || "零一二三四五六七八九十百".contains(c);
+    let start = name.find(is_numeral)?;
+    let rest = &name[start..];
+    let end = rest.find(|c: char| !is_numeral(c)).unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// 能否参与模糊匹配：长度相近且编号一致，「第八教学楼」不会匹配到「第一教学楼」
+fn fuzzy_candidate(input: &str, original: &str) -> bool {
+    let (a, b) = (input.chars().count(), original.chars().count());
+    if (a.min(b) as f64) < a.max(b) as f64 * MIN_FUZZY_LENGTH_RATIO {
+        return false;
+    }
+    leading_numeral(input) == leading_numeral(original)
+}
 
 /// 位置管理器
 pub struct LocationManager {
     mappings: HashMap<String, LocationMapping>,
+    fuzzy_threshold: f64,
+    /// 线上课程的地点关键字，命中时不输出地理坐标
+    online_keywords: Vec<String>,
+    /// 非精确匹配的标准化结果缓存，映射或阈值变化时清空
+    normalized: RwLock<HashMap<String, String>>,
 }
 
 impl LocationManager {
@@ -15,9 +74,37 @@ impl LocationManager {
     pub fn new() -> Self {
         Self {
             mappings: HashMap::new(),
+            fuzzy_threshold: DEFAULT_FUZZY_THRESHOLD,
+            online_keywords: DEFAULT_ONLINE_KEYWORDS
+                .iter()
+                .map(|keyword| keyword.to_string())
+                .collect(),
+            normalized: RwLock::default(),
         }
     }
 
+    /// 设置模糊匹配的相似度阈值（0.0-1.0），低于阈值的候选不会被采用
+    pub fn with_fuzzy_threshold(mut self, threshold: f64) -> Self {
+        self.fuzzy_threshold = threshold.clamp(0.0, 1.0);
+        self.invalidate_normalized();
+        self
+    }
+
+    /// 替换线上课程的地点关键字
+    pub fn with_online_keywords<I, S>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.online_keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 地点是否为线上课程
+    pub fn is_online(&self, loc: &str) -> bool {
+        is_online_location(loc, &self.online_keywords)
+    }
+
     /// 从JSON字符串加载位置映射
     pub fn load_from_json(&mut self, json_data: &str) -> Result<()> {
         let mappings: Vec<LocationMapping> = serde_json::from_str(json_data)?;
@@ -25,27 +112,80 @@ impl LocationManager {
         for mapping in mappings {
             self.mappings.insert(mapping.original.clone(), mapping);
         }
+        self.invalidate_normalized();
 
         Ok(())
     }
 
+    /// 在默认映射的基础上加载JSON位置映射
+    ///
+    /// 与默认映射的 `original` 相同时，以导入的映射为准。
+    pub fn load_from_json_merge(json_data: &str) -> Result<Self> {
+        let mut manager = Self::default();
+        manager.load_from_json(json_data)?;
+        Ok(manager)
+    }
+
     /// 添加位置映射
     pub fn add_mapping(&mut self, mapping: LocationMapping) {
         self.mappings.insert(mapping.original.clone(), mapping);
+        self.invalidate_normalized();
+    }
+
+    /// 清空标准化结果缓存
+    fn invalidate_normalized(&mut self) {
+        self.normalized
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
     }
 
     /// 标准化位置名称
+    ///
+    /// 非精确匹配的结果会被缓存，重复的教室不再遍历全部映射。
     pub fn normalize_location(&self, original: &str) -> String {
         // 首先尝试精确匹配
         if let Some(mapping) = self.mappings.get(original) {
             return mapping.normalized.clone();
         }
 
-        // 如果没有精确匹配，尝试模糊匹配
-        for mapping in self.mappings.values() {
-            if original.contains(&mapping.original) || mapping.original.contains(original) {
-                return mapping.normalized.clone();
-            }
+        if let Some(cached) = self
+            .normalized
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(original)
+        {
+            return cached.clone();
+        }
+        let normalized = self.fuzzy_normalize(original);
+        self.normalized
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(original.to_string(), normalized.clone());
+        normalized
+    }
+
+    /// 批量标准化位置名称，结果与输入一一对应
+    pub fn normalize_many(&self, originals: &[&str]) -> Vec<String> {
+        originals
+            .iter()
+            .map(|original| self.normalize_location(original))
+            .collect()
+    }
+
+    /// 模糊匹配位置名称，没有达到阈值的映射时做基本清理
+    fn fuzzy_normalize(&self, original: &str) -> String {
+        // 取相似度最高且达到阈值的映射
+        let best = self
+            .mappings
+            .values()
+            .filter(|mapping| fuzzy_candidate(original, &mapping.original))
+            .map(|mapping| (strsim::jaro_winkler(original, &mapping.original), mapping))
+            .filter(|(score, _)| *score >= self.fuzzy_threshold)
+            // 分数相同时取原始名称较小者，保证结果稳定
+            .max_by(|(a, x), (b, y)| a.total_cmp(b).then_with(|| y.original.cmp(&x.original)));
+        if let Some((_, mapping)) = best {
+            return mapping.normalized.clone();
         }
 
         // 如果都没有匹配，进行基本的清理
@@ -80,71 +220,81 @@ impl LocationManager {
     /// 根据位置生成带有地理坐标的ICS位置信息
     /// 对应Python中的get_location函数
     pub fn get_location_with_geo(&self, loc: &str) -> String {
-        // 提取四位数教室号
-        let room = self.extract_room_number(loc);
-
-        let custom_geo = if loc.contains("YF") {
-            r#"LOCATION:重庆邮电大学-逸夫科技楼\n崇文路2号重庆邮电大学
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学-逸夫科技楼\\n崇文路2号重庆邮电大学:geo:29.535617,106.607390"#
-        } else if loc.contains("SL") {
-            r#"LOCATION:重庆邮电大学数理学院\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学数理学院\\n崇文路2号重庆邮电大学内:geo:29.530599,106.605454"#
-        } else if loc.contains("综合实验") || loc.contains("实验实训室") {
-            r#"LOCATION:重庆邮电大学综合实验大楼\n南山路新力村
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学综合实验大楼\\n南山路新力村:geo:29.524289,106.605595"#
-        } else if loc.contains("风华") || loc == "运动场1" {
-            r#"LOCATION:风华运动场\n南山街道重庆邮电大学5栋
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=风华运动场\\n南山街道重庆邮电大学5栋:geo:29.532757,106.607510"#
-        } else if loc.contains("太极") {
-            r#"LOCATION:重庆邮电大学-太极体育场\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学-太极体育场\\n崇文路2号重庆邮电大学内:geo:29.532940,106.609072"#
-        } else if loc.contains("乒乓球") {
-            r#"LOCATION:风雨操场(乒乓球馆)\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=风雨操场(乒乓球馆)\\n崇文路2号重庆邮电大学内:geo:29.534230,106.608516"#
-        } else if loc.contains("篮球") || loc.contains("排球") {
-            r#"LOCATION:重庆邮电学院篮球排球馆\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电学院篮球排球馆\\n崇文路2号重庆邮电大学内:geo:29.534025,106.609148"#
-        } else if loc.contains("仙桃A08") {
-            r#"LOCATION:重庆仙桃数据谷A08\n中国重庆市渝北区金山大道仙桃国际大数据谷体验中心
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆仙桃数据谷A08\\n中国重庆市渝北区金山大道仙桃国际大数据谷体验中心:geo:29.739791,106.55661"#
-        } else if loc.contains("仙桃运动场") {
-            r#"LOCATION:仙桃体育公园\n中国重庆市渝北区金山大道仙桃国际大数据谷体验中心
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=仙桃体育公园\\n中国重庆市渝北区仙桃街道数据谷东路仙桃国际数据谷内:geo:29.745789,106.55749"#
-        } else if room.starts_with('1') {
-            r#"LOCATION:重庆邮电大学-光电工程学院\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学-光电工程学院\\n崇文路2号重庆邮电大学内:geo:29.531478,106.605921"#
-        } else if room.starts_with('2') {
-            r#"LOCATION:重庆邮电大学二教学楼\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学二教学楼\\n崇文路2号重庆邮电大学内:geo:29.532703,106.606747"#
-        } else if room.starts_with('3') {
-            r#"LOCATION:重庆邮电大学第三教学楼\n崇文路2号
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学第三教学楼\\n崇文路2号:geo:29.535119,106.609114"#
-        } else if room.starts_with('4') {
-            r#"LOCATION:重庆邮电大学第四教学楼\n崇文路2号
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学第四教学楼\\n崇文路2号:geo:29.536107,106.608759"#
-        } else if room.starts_with('5') {
-            r#"LOCATION:重庆邮电大学-国际学院\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学-国际学院\\n崇文路2号重庆邮电大学内:geo:29.536131,106.610090"#
-        } else if room.starts_with('8') {
-            r#"LOCATION:重庆邮电大学八教学楼A栋\n崇文路2号重庆邮电大学内
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学八教学楼A栋\\n崇文路2号重庆邮电大学内:geo:29.535322,106.611020"#
-        } else {
-            // Fallback
-            r#"LOCATION:重庆邮电大学\n崇文路2号
-X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学\\n崇文路2号:geo:29.530807,106.607617"#
-        };
+        self.get_location_with_detail(loc, LocationDetail::Full)
+    }
 
-        // 提取geo坐标并格式化最终结果
-        let geo_part = custom_geo
-            .split("geo:")
-            .nth(1)
-            .unwrap_or("29.530807,106.607617")
-            .replace(',', ";");
+    /// 按指定详细程度生成ICS位置信息
+    ///
+    /// `PlainName` 只输出原始地点，`NameWithGeo` 额外输出 GEO，`Full` 同 [`LocationManager::get_location_with_geo`]。
+    /// 线上课程不对应任何教学楼，除 `PlainName` 外只输出 `LOCATION:线上教学`。
+    pub fn get_location_with_detail(&self, loc: &str, detail: LocationDetail) -> String {
+        if detail == LocationDetail::PlainName {
+            return format!("LOCATION:{}\r\n", loc);
+        }
+        if self.is_online(loc) {
+            return format!("LOCATION:{}\r\n", ONLINE_LOCATION);
+        }
+        let (_, place) = self.match_geo_rule(loc);
+        let geo_ics = place.geo.replace(',', ";");
+        if detail == LocationDetail::NameWithGeo {
+            return format!("LOCATION:{}\r\nGEO:{}\r\n", loc, geo_ics);
+        }
+        let structured_address = place.structured_address.unwrap_or(place.address);
 
-        // 统一换行为 CRLF 并确保 GEO 行以 CRLF 结尾，避免与后续字段粘连
-        let custom_geo_crlf = custom_geo.replace('\n', "\r\n");
         // 注意：不在前面添加 CRLF，避免与上一行之间产生空白行。
-        format!("{}\r\nGEO:{}\r\n", custom_geo_crlf, geo_part)
+        format!(
+            "LOCATION:{title}\\n{address}\r\n\
+             X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE={title}\\\\n{structured_address}:geo:{geo}\r\n\
+             GEO:{geo_ics}\r\n",
+            title = place.title,
+            address = place.address,
+            geo = place.geo,
+        )
+    }
+
+    /// 同 [`LocationManager::get_location_with_geo`]，重复的位置直接从缓存返回
+    pub fn get_location_with_geo_cached(&self, loc: &str, cache: &mut LocationCache) -> String {
+        self.get_location_with_detail_cached(loc, LocationDetail::Full, cache)
+    }
+
+    /// 同 [`LocationManager::get_location_with_detail`]，重复的位置直接从缓存返回
+    ///
+    /// 缓存只按地点区分，同一个缓存应始终使用相同的详细程度。
+    pub fn get_location_with_detail_cached(
+        &self,
+        loc: &str,
+        detail: LocationDetail,
+        cache: &mut LocationCache,
+    ) -> String {
+        if let Some(cached) = cache.entries.get(loc) {
+            cache.hits += 1;
+            return cached.clone();
+        }
+        let resolved = self.get_location_with_detail(loc, detail);
+        cache.entries.insert(loc.to_string(), resolved.clone());
+        resolved
+    }
+
+    /// 解释位置命中了哪条地理规则，用于排查建筑匹配错误
+    pub fn explain_location(&self, loc: &str) -> LocationExplanation {
+        let (matched_rule, place) = self.match_geo_rule(loc);
+        LocationExplanation {
+            matched_rule: matched_rule.to_string(),
+            room_number: self.extract_room_number(loc),
+            title: place.title.to_string(),
+            coords: place.coords(),
+        }
+    }
+
+    /// 按顺序匹配地理规则，均未命中时返回兜底位置
+    fn match_geo_rule(&self, loc: &str) -> (GeoMatcher, &'static GeoPlace) {
+        // 提取四位数教室号
+        let room = self.extract_room_number(loc);
+        GEO_RULES
+            .iter()
+            .find(|(matcher, _)| matcher.matches(loc, &room))
+            .map(|(matcher, place)| (*matcher, place))
+            .unwrap_or((GeoMatcher::Fallback, &FALLBACK_PLACE))
     }
 
     /// 提取四位数教室号
@@ -158,6 +308,259 @@ X-APPLE-STRUCTURED-LOCATION;VALUE=URI;X-TITLE=重庆邮电大学\\n崇文路2号
     }
 }
 
+/// 单次生成内的位置解析缓存，课表中大量课程共享同一教室
+#[derive(Debug, Default)]
+pub struct LocationCache {
+    entries: HashMap<String, String>,
+    hits: usize,
+}
+
+impl LocationCache {
+    /// 已缓存的位置数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 是否尚未缓存任何位置
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 命中缓存的次数
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
+
+/// 位置规则匹配结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationExplanation {
+    /// 命中的规则，如 `RoomPrefix('4')`、`Contains("YF")`、`Fallback`
+    pub matched_rule: String,
+    /// 提取出的四位数教室号，未找到时为 "6666"
+    pub room_number: String,
+    /// 地点名称
+    pub title: String,
+    /// 坐标 (纬度, 经度)
+    pub coords: (f64, f64),
+}
+
+/// 地理规则的匹配方式
+#[derive(Debug, Clone, Copy)]
+enum GeoMatcher {
+    /// 位置包含指定文本
+    Contains(&'static str),
+    /// 位置与指定文本完全相同
+    Equals(&'static str),
+    /// 教室号以指定数字开头
+    RoomPrefix(char),
+    /// 未命中任何规则
+    Fallback,
+}
+
+impl GeoMatcher {
+    fn matches(&self, loc: &str, room: &str) -> bool {
+        match self {
+            Self::Contains(needle) => loc.contains(needle),
+            Self::Equals(value) => loc == *value,
+            Self::RoomPrefix(prefix) => room.starts_with(*prefix),
+            Self::Fallback => true,
+        }
+    }
+}
+
+impl fmt::Display for GeoMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contains(needle) => write!(f, "Contains({:?})", needle),
+            Self::Equals(value) => write!(f, "Equals({:?})", value),
+            Self::RoomPrefix(prefix) => write!(f, "RoomPrefix({:?})", prefix),
+            Self::Fallback => write!(f, "Fallback"),
+        }
+    }
+}
+
+/// 地理位置信息
+#[derive(Debug)]
+struct GeoPlace {
+    title: &'static str,
+    address: &'static str,
+    /// X-APPLE-STRUCTURED-LOCATION 使用的地址，缺省与 `address` 相同
+    structured_address: Option<&'static str>,
+    /// "纬度,经度"
+    geo: &'static str,
+}
+
+impl GeoPlace {
+    const fn new(title: &'static str, address: &'static str, geo: &'static str) -> Self {
+        Self {
+            title,
+            address,
+            structured_address: None,
+            geo,
+        }
+    }
+
+    fn coords(&self) -> (f64, f64) {
+        let (lat, lon) = self.geo.split_once(',').unwrap_or_default();
+        (
+            lat.parse().unwrap_or_default(),
+            lon.parse().unwrap_or_default(),
+        )
+    }
+}
+
+const FALLBACK_PLACE: GeoPlace = GeoPlace::new("重庆邮电大学", "崇文路2号", "29.530807,106.607617");
+
+/// 地理规则，按顺序匹配
+const GEO_RULES: &[(GeoMatcher, GeoPlace)] = &[
+    (
+        GeoMatcher::Contains("YF"),
+        GeoPlace::new(
+            "重庆邮电大学-逸夫科技楼",
+            "崇文路2号重庆邮电大学",
+            "29.535617,106.607390",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("SL"),
+        GeoPlace::new(
+            "重庆邮电大学数理学院",
+            "崇文路2号重庆邮电大学内",
+            "29.530599,106.605454",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("综合实验"),
+        GeoPlace::new(
+            "重庆邮电大学综合实验大楼",
+            "南山路新力村",
+            "29.524289,106.605595",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("实验实训室"),
+        GeoPlace::new(
+            "重庆邮电大学综合实验大楼",
+            "南山路新力村",
+            "29.524289,106.605595",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("风华"),
+        GeoPlace::new(
+            "风华运动场",
+            "南山街道重庆邮电大学5栋",
+            "29.532757,106.607510",
+        ),
+    ),
+    (
+        GeoMatcher::Equals("运动场1"),
+        GeoPlace::new(
+            "风华运动场",
+            "南山街道重庆邮电大学5栋",
+            "29.532757,106.607510",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("太极"),
+        GeoPlace::new(
+            "重庆邮电大学-太极体育场",
+            "崇文路2号重庆邮电大学内",
+            "29.532940,106.609072",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("乒乓球"),
+        GeoPlace::new(
+            "风雨操场(乒乓球馆)",
+            "崇文路2号重庆邮电大学内",
+            "29.534230,106.608516",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("篮球"),
+        GeoPlace::new(
+            "重庆邮电学院篮球排球馆",
+            "崇文路2号重庆邮电大学内",
+            "29.534025,106.609148",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("排球"),
+        GeoPlace::new(
+            "重庆邮电学院篮球排球馆",
+            "崇文路2号重庆邮电大学内",
+            "29.534025,106.609148",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("仙桃A08"),
+        GeoPlace::new(
+            "重庆仙桃数据谷A08",
+            "中国重庆市渝北区金山大道仙桃国际大数据谷体验中心",
+            "29.739791,106.556610",
+        ),
+    ),
+    (
+        GeoMatcher::Contains("仙桃运动场"),
+        GeoPlace {
+            title: "仙桃体育公园",
+            address: "中国重庆市渝北区金山大道仙桃国际大数据谷体验中心",
+            structured_address: Some("中国重庆市渝北区仙桃街道数据谷东路仙桃国际数据谷内"),
+            geo: "29.745789,106.557490",
+        },
+    ),
+    (
+        GeoMatcher::RoomPrefix('1'),
+        GeoPlace::new(
+            "重庆邮电大学-光电工程学院",
+            "崇文路2号重庆邮电大学内",
+            "29.531478,106.605921",
+        ),
+    ),
+    (
+        GeoMatcher::RoomPrefix('2'),
+        GeoPlace::new(
+            "重庆邮电大学二教学楼",
+            "崇文路2号重庆邮电大学内",
+            "29.532703,106.606747",
+        ),
+    ),
+    (
+        GeoMatcher::RoomPrefix('3'),
+        GeoPlace::new(
+            "重庆邮电大学第三教学楼",
+            "崇文路2号",
+            "29.535119,106.609114",
+        ),
+    ),
+    (
+        GeoMatcher::RoomPrefix('4'),
+        GeoPlace::new(
+            "重庆邮电大学第四教学楼",
+            "崇文路2号",
+            "29.536107,106.608759",
+        ),
+    ),
+    (
+        GeoMatcher::RoomPrefix('5'),
+        GeoPlace::new(
+            "重庆邮电大学-国际学院",
+            "崇文路2号重庆邮电大学内",
+            "29.536131,106.610090",
+        ),
+    ),
+    (
+        GeoMatcher::RoomPrefix('8'),
+        GeoPlace::new(
+            "重庆邮电大学八教学楼A栋",
+            "崇文路2号重庆邮电大学内",
+            "29.535322,106.611020",
+        ),
+    ),
+];
+
 impl Default for LocationManager {
     fn default() -> Self {
         let mut manager = Self::new();
@@ -199,3 +602,187 @@ impl Default for LocationManager {
         manager
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geo_rules_are_within_campus_bounds() {
+        let places = GEO_RULES
+            .iter()
+            .map(|(_, place)| place)
+            .chain([&FALLBACK_PLACE]);
+        for place in places {
+            let (lat, lon) = place.coords();
+            assert!(
+                (29.4..=29.8).contains(&lat) && (106.4..=106.8).contains(&lon),
+                "{} is out of bounds: {}",
+                place.title,
+                place.geo
+            );
+            // 统一保留 6 位小数，位数不同通常意味着复制时截断
+            for part in place.geo.split(',') {
+                let decimals = part.split_once('.').map_or(0, |(_, d)| d.len());
+                assert_eq!(
+                    decimals, 6,
+                    "{} has imprecise geo: {}",
+                    place.title, place.geo
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn explain_location_reports_matched_rule() {
+        let manager = LocationManager::default();
+
+        let explanation = manager.explain_location("4101");
+        assert_eq!(explanation.matched_rule, "RoomPrefix('4')");
+        assert_eq!(explanation.room_number, "4101");
+        assert_eq!(explanation.title, "重庆邮电大学第四教学楼");
+        assert_eq!(explanation.coords, (29.536107, 106.608759));
+
+        let explanation = manager.explain_location("YF3208");
+        assert_eq!(explanation.matched_rule, r#"Contains("YF")"#);
+        assert_eq!(explanation.room_number, "3208");
+
+        assert_eq!(
+            manager.explain_location("运动场1").matched_rule,
+            r#"Equals("运动场1")"#
+        );
+
+        let explanation = manager.explain_location("线上");
+        assert_eq!(explanation.matched_rule, "Fallback");
+        assert_eq!(explanation.room_number, "6666");
+        assert_eq!(explanation.title, "重庆邮电大学");
+    }
+
+    #[test]
+    fn merge_import_keeps_defaults() {
+        let json = r#"[{
+            "original": "第一教学楼",
+            "normalized": "1教",
+            "building": "第一教学楼",
+            "room": null,
+            "campus": "南山校区"
+        }]"#;
+
+        let manager = LocationManager::load_from_json_merge(json).unwrap();
+
+        assert_eq!(manager.normalize_location("第一教学楼"), "1教");
+        assert_eq!(manager.normalize_location("第二教学楼"), "二教");
+        assert_eq!(
+            manager.get_all_mappings().len(),
+            LocationManager::default().get_all_mappings().len()
+        );
+    }
+
+    #[test]
+    fn fuzzy_match_respects_threshold() {
+        let manager = LocationManager::default();
+        assert_eq!(manager.normalize_location("第一教"), "一教");
+        assert_eq!(manager.normalize_location("第三教学楼3208"), "三教");
+        // 过短的输入不应误匹配到教学楼
+        assert_eq!(manager.normalize_location("1"), "1");
+        // 编号不同的教学楼不应被映射到相近的已知教学楼
+        assert_eq!(manager.normalize_location("第八教学楼"), "第八");
+        assert_eq!(manager.normalize_location("第四教学楼4101"), "第四4101");
+        assert_eq!(manager.normalize_location("第十一教学楼"), "第十一");
+        assert_eq!(manager.normalize_location("教学楼"), "");
+
+        let strict = LocationManager::default().with_fuzzy_threshold(0.95);
+        assert_eq!(strict.normalize_location("第一教"), "第一教");
+    }
+
+    #[test]
+    fn normalize_cache_is_invalidated_by_new_mappings() {
+        let mut manager = LocationManager::default();
+        assert_eq!(manager.normalize_location("第一教"), "一教");
+        assert_eq!(manager.normalize_location("逸夫科技"), "逸夫科技");
+        assert_eq!(
+            manager.normalize_many(&["第一教", " 逸夫楼 ", "第一教学楼"]),
+            ["一教", "逸夫楼", "一教"]
+        );
+
+        manager.add_mapping(LocationMapping {
+            original: "第一教".to_string(),
+            normalized: "一教学楼".to_string(),
+            building: None,
+            room: None,
+            campus: None,
+        });
+        manager.add_mapping(LocationMapping {
+            original: "逸夫科技楼".to_string(),
+            normalized: "逸夫".to_string(),
+            building: None,
+            room: None,
+            campus: None,
+        });
+        assert_eq!(manager.normalize_location("第一教"), "一教学楼");
+        assert_eq!(manager.normalize_location("逸夫科技"), "逸夫");
+    }
+
+    #[test]
+    fn online_locations_have_no_geo() {
+        let manager = LocationManager::default();
+        assert!(manager.is_online("腾讯会议"));
+        assert!(manager.is_online("线上教学(ZOOM)"));
+        assert!(!manager.is_online("4307"));
+
+        assert_eq!(
+            manager.get_location_with_geo("腾讯会议 123-456-789"),
+            "LOCATION:线上教学\r\n"
+        );
+        assert!(manager.get_location_with_geo("4307").contains("\r\nGEO:"));
+        assert!(
+            !manager
+                .get_location_with_detail("雨课堂", LocationDetail::NameWithGeo)
+                .contains("GEO:")
+        );
+
+        let course = |location: &str| crate::Course {
+            location: Some(location.to_string()),
+            ..Default::default()
+        };
+        assert!(course("雨课堂").is_online());
+        assert!(!course("4307").is_online());
+
+        let custom = LocationManager::default().with_online_keywords(["飞书"]);
+        assert!(custom.is_online("飞书会议"));
+        assert!(custom.get_location_with_geo("腾讯会议").contains("GEO:"));
+    }
+
+    #[test]
+    fn normalize_is_thread_safe() {
+        let manager = LocationManager::default();
+        let inputs = ["第一教", "第三教学楼3208", "1", "逸夫楼"];
+        let expected = inputs.map(|input| LocationManager::default().normalize_location(input));
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        assert_eq!(manager.normalize_many(&inputs), expected);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn cached_geo_lookup_resolves_each_location_once() {
+        let manager = LocationManager::default();
+        let mut cache = LocationCache::default();
+        let locations = ["2101", "3208", "2101", "YF3208", "2101", "3208"];
+
+        for loc in locations {
+            assert_eq!(
+                manager.get_location_with_geo_cached(loc, &mut cache),
+                manager.get_location_with_geo(loc)
+            );
+        }
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.hits(), 3);
+    }
+}
@@ -1,8 +1,10 @@
 pub mod base;
+pub mod file;
+pub mod mock;
 pub mod redrock;
 pub mod wecqupt;
 
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use chrono::FixedOffset;
@@ -134,8 +136,10 @@ pub trait Provider: Send + Sync {
     ///
     /// Returns the timezone used by this provider for time calculations.
     /// This is used to ensure consistent timezone handling across all
-    /// provider operations.
-    fn timezone(&self) -> FixedOffset;
+    /// provider operations. Defaults to [`crate::CQUPT_TZ`].
+    fn timezone(&self) -> FixedOffset {
+        crate::CQUPT_TZ
+    }
 
     /// Authenticate and get token
     async fn authenticate<'a, 'b>(
@@ -171,10 +175,35 @@ pub trait Provider: Send + Sync {
         token: &Self::Token,
     ) -> Result<chrono::DateTime<FixedOffset>>;
 
+    /// Revoke token on the remote side
+    /// Called by `Wrapper::logout` before the cached token is removed.
+    /// Default is a no-op for providers without a logout endpoint
+    async fn revoke_token(&self, _token: &Self::Token) -> Result<()> {
+        Ok(())
+    }
+
     /// Token TTL
     fn token_ttl(&self) -> Duration {
         Duration::from_secs(3600 * 24) // 24 hours default
     }
+
+    /// What the provider's course data supports
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
+
+    /// Upstream API root, used for connectivity checks; `None` for offline providers
+    fn api_root(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Provider capability descriptor, exposed through `/providers` and the CLI listing
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ProviderCapabilities {
+    /// Courses carry `weeks`/`weekday`, so RRULEs can be emitted directly
+    /// instead of expanding every occurrence
+    pub recurrence_native: bool,
 }
 
 /// Provider wrapper with caching
@@ -186,6 +215,15 @@ pub trait ProviderWrapper: Send + Sync {
     /// Provider description
     fn description(&self) -> &str;
 
+    /// Provider capabilities
+    fn capabilities(&self) -> ProviderCapabilities;
+
+    /// Timezone used by the wrapped provider
+    fn timezone(&self) -> FixedOffset;
+
+    /// Upstream API root of the wrapped provider
+    fn api_root(&self) -> Option<&str>;
+
     /// Validate credentials
     async fn validate(&self, request: &CourseRequest) -> Result<()>;
 
@@ -196,6 +234,25 @@ pub trait ProviderWrapper: Send + Sync {
     async fn logout(&self, request: &CourseRequest) -> Result<()>;
 }
 
+/// Cache key for per-semester data such as course responses
+///
+/// Includes the semester start date (`request.semester`, or the detected
+/// current semester when unset) so entries from a previous term never collide
+/// with the current one and simply expire
+pub fn semester_cache_key(provider: &str, kind: &str, request: &CourseRequest) -> String {
+    let semester = request
+        .semester
+        .clone()
+        .unwrap_or_else(crate::SemesterDetector::create_current_semester);
+    format!(
+        "{}:{}:{}:{}",
+        provider,
+        kind,
+        request.credentials.username,
+        semester.start_date.format("%Y-%m-%d")
+    )
+}
+
 pub trait IntoStatic: Sized {
     fn into_static(self) -> &'static Self {
         let p: &'static mut Self = Box::leak(Box::new(self));
@@ -210,14 +267,50 @@ impl<T: 'static> IntoStatic for T {}
 pub struct Wrapper<P: Provider + 'static, C: CacheBackend + 'static> {
     provider: P,
     cache_manager: CacheManager<C>,
+    deadline: Option<Duration>,
+    curfew_wait: Option<Duration>,
 }
 
+/// Interval between retries while waiting out the curfew window
+const CURFEW_RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
 impl<P: Provider + 'static, C: CacheBackend + 'static> Wrapper<P, C> {
     /// Create new wrapper
     pub fn new(provider: P, cache_manager: CacheManager<C>) -> Self {
         Self {
             provider,
             cache_manager,
+            deadline: None,
+            curfew_wait: None,
+        }
+    }
+
+    /// Keep retrying `get_courses` while the school is in its curfew window
+    ///
+    /// On `Error::CurfewTime` the wrapper sleeps and retries until the call
+    /// succeeds or `max_wait` is used up, then returns the last error.
+    /// Meant for unattended jobs; the deadline still bounds the whole call.
+    pub fn retry_on_curfew(mut self, max_wait: Duration) -> Self {
+        self.curfew_wait = Some(max_wait);
+        self
+    }
+
+    /// Set an overall deadline for `get_courses`, including the retry
+    ///
+    /// Exceeding it yields `Error::Timeout`.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Effective deadline for a request
+    ///
+    /// `request.timeout` can only shorten the wrapper deadline, never extend it.
+    fn deadline_for(&self, request: &CourseRequest) -> Option<Duration> {
+        let requested = request.timeout.map(|secs| Duration::from_secs(secs as u64));
+        match (self.deadline, requested) {
+            (Some(deadline), Some(requested)) => Some(deadline.min(requested)),
+            (deadline, requested) => deadline.or(requested),
         }
     }
 
@@ -230,6 +323,11 @@ impl<P: Provider + 'static, C: CacheBackend + 'static> Wrapper<P, C> {
         )
     }
 
+    /// Generate cache key for per-semester data, see [`semester_cache_key`]
+    pub fn semester_cache_key(&self, kind: &str, request: &CourseRequest) -> String {
+        semester_cache_key(self.provider.name(), kind, request)
+    }
+
     /// Get cached token or authenticate
     async fn get_or_create_token(&self, request: &CourseRequest) -> Result<P::Token> {
         let cache_key = self.token_cache_key(request);
@@ -267,6 +365,13 @@ impl<P: Provider + 'static, C: CacheBackend + 'static> Wrapper<P, C> {
 
         Ok(token)
     }
+    /// Drop the cached token locally without revoking it upstream
+    async fn invalidate_token(&self, request: &CourseRequest) -> Result<()> {
+        self.cache_manager
+            .remove_token_cache(&self.token_cache_key(request))
+            .await
+    }
+
     async fn get_courses_once(&self, request: &mut CourseRequest) -> Result<CourseResponse> {
         let token = self.get_or_create_token(request).await?;
         let mut c: Context<P::ContextType> = Context::default();
@@ -275,12 +380,59 @@ impl<P: Provider + 'static, C: CacheBackend + 'static> Wrapper<P, C> {
                 .provider
                 .get_semester_start(c.as_param(), request, &token)
                 .await?;
-            request.semester = Some(crate::Semester { start_date: sem });
+            request.semester = Some(crate::Semester {
+                start_date: sem,
+                weeks_total: None,
+            });
         }
         self.provider
             .get_courses(c.as_param(), request, &token)
             .await
     }
+
+    async fn get_courses_with_retry(&self, request: &mut CourseRequest) -> Result<CourseResponse> {
+        match self.get_courses_once(request).await {
+            Ok(courses) => Ok(courses),
+            // Retrying right away cannot end the curfew
+            Err(e @ crate::Error::CurfewTime(())) => Err(e),
+            Err(e) => {
+                // On Auth error, drop the cached token and retry once; the session is
+                // left alive upstream so transient failures don't force a new login
+                if matches!(e, crate::Error::Authentication { .. }) {
+                    self.invalidate_token(request).await?;
+                }
+                self.get_courses_once(request).await
+            }
+        }
+    }
+
+    async fn get_courses_through_curfew(
+        &self,
+        request: &mut CourseRequest,
+    ) -> Result<CourseResponse> {
+        let Some(max_wait) = self.curfew_wait else {
+            return self.get_courses_with_retry(request).await;
+        };
+        let started = tokio::time::Instant::now();
+        loop {
+            match self.get_courses_with_retry(request).await {
+                Err(crate::Error::CurfewTime(())) => {
+                    let remaining = max_wait.saturating_sub(started.elapsed());
+                    if remaining.is_zero() {
+                        return Err(crate::Error::CurfewTime(()));
+                    }
+                    let wait = remaining.min(CURFEW_RETRY_INTERVAL);
+                    tracing::info!(
+                        "{} is in curfew, retrying in {}s",
+                        self.provider.name(),
+                        wait.as_secs()
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                result => return result,
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -293,38 +445,95 @@ impl<P: Provider + 'static, C: CacheBackend + 'static> ProviderWrapper for Wrapp
         self.provider.description()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.provider.capabilities()
+    }
+
+    fn timezone(&self) -> FixedOffset {
+        self.provider.timezone()
+    }
+
+    fn api_root(&self) -> Option<&str> {
+        self.provider.api_root()
+    }
+
     async fn validate(&self, request: &CourseRequest) -> Result<()> {
         let _token = self.get_or_create_token(request).await?;
         Ok(())
     }
 
     async fn get_courses(&self, request: &mut CourseRequest) -> Result<CourseResponse> {
-        match self.get_courses_once(request).await {
-            Ok(courses) => Ok(courses),
-            Err(e) => {
-                // On Auth error, clear the token cache and retry once
-                if matches!(
-                    e,
-                    crate::Error::Authentication(_) | crate::Error::Provider { .. }
-                ) {
-                    self.logout(request).await?;
-                }
-                self.get_courses_once(request).await
+        match self.deadline_for(request) {
+            Some(deadline) => {
+                tokio::time::timeout(deadline, self.get_courses_through_curfew(request))
+                    .await
+                    .map_err(|_| crate::Error::Timeout)?
             }
+            None => self.get_courses_through_curfew(request).await,
         }
     }
 
     async fn logout(&self, request: &CourseRequest) -> Result<()> {
-        self.cache_manager
-            .remove_token_cache(&self.token_cache_key(request))
-            .await?;
+        let cache_key = self.token_cache_key(request);
+        // Revoke failures are not fatal, the local cache is cleared anyway
+        if let Ok(Some(token)) = self
+            .cache_manager
+            .get_cached_token::<P::Token>(&cache_key)
+            .await
+            && let Err(e) = self.provider.revoke_token(&token).await
+        {
+            tracing::warn!("Failed to revoke {} token: {}", self.provider.name(), e);
+        }
+        self.cache_manager.remove_token_cache(&cache_key).await?;
         Ok(())
     }
 }
 
+/// Lets leaked built-in providers share storage with `Arc`-registered ones
+struct StaticProvider(&'static dyn ProviderWrapper);
+
+#[async_trait]
+impl ProviderWrapper for StaticProvider {
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    fn description(&self) -> &str {
+        self.0.description()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.0.capabilities()
+    }
+
+    fn timezone(&self) -> FixedOffset {
+        self.0.timezone()
+    }
+
+    fn api_root(&self) -> Option<&str> {
+        self.0.api_root()
+    }
+
+    async fn validate(&self, request: &CourseRequest) -> Result<()> {
+        self.0.validate(request).await
+    }
+
+    async fn get_courses(&self, request: &mut CourseRequest) -> Result<CourseResponse> {
+        self.0.get_courses(request).await
+    }
+
+    async fn logout(&self, request: &CourseRequest) -> Result<()> {
+        self.0.logout(request).await
+    }
+}
+
 /// Provider registry
+///
+/// Built-in providers are leaked once via [`IntoStatic`]; providers constructed
+/// at runtime can be added with [`ProviderRegistry::register_arc`] and are
+/// dropped when unregistered and no longer in use.
 pub struct ProviderRegistry {
-    providers: HashMap<String, &'static dyn ProviderWrapper>,
+    providers: HashMap<String, Arc<dyn ProviderWrapper>>,
 }
 
 impl ProviderRegistry {
@@ -335,20 +544,35 @@ impl ProviderRegistry {
         }
     }
 
-    /// Register a provider
+    /// Register a provider that lives for the whole process
     pub fn register(&mut self, provider: &'static dyn ProviderWrapper) {
+        self.register_arc(Arc::new(StaticProvider(provider)));
+    }
+
+    /// Register a shared provider, replacing any provider with the same name
+    pub fn register_arc(&mut self, provider: Arc<dyn ProviderWrapper>) {
         self.providers.insert(provider.name().to_string(), provider);
     }
 
+    /// Remove a provider by name, returning it if it was registered
+    pub fn unregister(&mut self, name: &str) -> Option<Arc<dyn ProviderWrapper>> {
+        self.providers.remove(name)
+    }
+
     /// Get provider by name
-    pub fn get_provider(&self, name: &str) -> Option<&'static dyn ProviderWrapper> {
-        self.providers.get(name).copied()
+    pub fn get_provider(&self, name: &str) -> Option<Arc<dyn ProviderWrapper>> {
+        self.providers.get(name).cloned()
     }
 
     /// List all providers
     pub fn list_providers(&self) -> impl Iterator<Item = (&str, &str)> {
         self.providers.values().map(|p| (p.name(), p.description()))
     }
+
+    /// Iterate over all registered providers
+    pub fn providers(&self) -> impl Iterator<Item = &dyn ProviderWrapper> {
+        self.providers.values().map(|provider| provider.as_ref())
+    }
 }
 
 impl Default for ProviderRegistry {
@@ -356,3 +580,518 @@ impl Default for ProviderRegistry {
         Self::new()
     }
 }
+
+/// Options for [`build_registry`]
+#[derive(Debug, Clone, Default)]
+pub struct RegistryOptions {
+    /// Overall deadline applied to every provider's `get_courses`
+    pub deadline: Option<Duration>,
+    /// Register the offline mock provider, meant for tests and demos
+    pub include_mock: bool,
+    /// How the file provider finds files; `None` leaves it unregistered
+    pub file_provider: Option<file::FileLookup>,
+    /// Override for the redrock API root
+    pub redrock_api_root: Option<String>,
+    /// Override for the wecqupt API root
+    pub wecqupt_api_root: Option<String>,
+    /// Wait budget for [`Wrapper::retry_on_curfew`]; `None` fails fast
+    pub curfew_wait: Option<Duration>,
+    /// Hours during which a redrock `500` is treated as the curfew; `None` uses the default
+    pub curfew_window: Option<redrock::CurfewWindow>,
+}
+
+/// Build a registry with the network providers (redrock, wecqupt) sharing `cache`
+///
+/// The mock and file providers are opt-in through [`RegistryOptions`].
+pub fn build_default_registry<C: CacheBackend + Clone + 'static>(cache: C) -> ProviderRegistry {
+    build_registry(cache, RegistryOptions::default())
+        .expect("default registry options are always valid")
+}
+
+/// Build a registry with the built-in providers sharing `cache`, applying `options`
+///
+/// Returns [`crate::Error::Config`] if an API root override is invalid.
+pub fn build_registry<C: CacheBackend + Clone + 'static>(
+    cache: C,
+    options: RegistryOptions,
+) -> Result<ProviderRegistry> {
+    fn register<P: Provider + 'static, C: CacheBackend + 'static>(
+        registry: &mut ProviderRegistry,
+        provider: P,
+        cache: C,
+        options: &RegistryOptions,
+    ) {
+        let mut wrapper = Wrapper::new(provider, CacheManager::new(cache));
+        if let Some(deadline) = options.deadline {
+            wrapper = wrapper.with_deadline(deadline);
+        }
+        if let Some(max_wait) = options.curfew_wait {
+            wrapper = wrapper.retry_on_curfew(max_wait);
+        }
+        registry.register(wrapper.into_static());
+    }
+
+    let mut redrock = redrock::RedrockProvider::new();
+    if let Some(api_root) = &options.redrock_api_root {
+        redrock = redrock.with_api_root(api_root)?;
+    }
+    if let Some(window) = options.curfew_window {
+        redrock = redrock.with_curfew_window(window);
+    }
+    let mut wecqupt = wecqupt::WecquptProvider::new();
+    if let Some(api_root) = &options.wecqupt_api_root {
+        wecqupt = wecqupt.with_api_root(api_root)?;
+    }
+
+    let mut registry = ProviderRegistry::new();
+    register(&mut registry, redrock, cache.clone(), &options);
+    register(&mut registry, wecqupt, cache.clone(), &options);
+    if options.include_mock {
+        register(
+            &mut registry,
+            mock::MockProvider::new(),
+            cache.clone(),
+            &options,
+        );
+    }
+    if let Some(lookup) = options.file_provider.clone() {
+        register(
+            &mut registry,
+            file::FileProvider::with_lookup(lookup),
+            cache,
+            &options,
+        );
+    }
+    Ok(registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{Credentials, cache::MemoryCache};
+
+    /// 获取课程时故意休眠的provider
+    #[derive(Default)]
+    struct SlowProvider {
+        delay: Duration,
+        /// revoke_token 被调用的次数
+        revoked: std::sync::atomic::AtomicUsize,
+        /// 剩余需要返回 CurfewTime 的次数
+        curfews: std::sync::atomic::AtomicUsize,
+        /// authenticate 被调用的次数
+        logins: std::sync::atomic::AtomicUsize,
+        /// 下一次获取课程时返回的错误
+        failure: Mutex<Option<crate::Error>>,
+    }
+
+    #[async_trait]
+    impl Provider for SlowProvider {
+        type Token = String;
+        type ContextType = ();
+
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "slow provider for tests"
+        }
+
+        async fn authenticate<'a, 'b>(
+            &'a self,
+            _context: ParamContext<'b, Self::ContextType>,
+            _request: &CourseRequest,
+        ) -> Result<Self::Token> {
+            self.logins
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok("token".to_string())
+        }
+
+        async fn validate_token(&self, _token: &Self::Token) -> Result<bool> {
+            Ok(true)
+        }
+
+        async fn refresh_token(&self, token: &Self::Token) -> Result<Self::Token> {
+            Ok(token.clone())
+        }
+
+        async fn revoke_token(&self, _token: &Self::Token) -> Result<()> {
+            self.revoked
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn get_courses<'a, 'b>(
+            &'a self,
+            _context: ParamContext<'b, Self::ContextType>,
+            request: &mut CourseRequest,
+            _token: &Self::Token,
+        ) -> Result<CourseResponse> {
+            tokio::time::sleep(self.delay).await;
+            if let Some(e) = self.failure.lock().unwrap().take() {
+                return Err(e);
+            }
+            if self
+                .curfews
+                .fetch_update(
+                    std::sync::atomic::Ordering::SeqCst,
+                    std::sync::atomic::Ordering::SeqCst,
+                    |left| left.checked_sub(1),
+                )
+                .is_ok()
+            {
+                return Err(crate::Error::CurfewTime(()));
+            }
+            Ok(CourseResponse {
+                courses: Vec::new(),
+                semester: request.semester.clone().unwrap(),
+                generated_at: Utc::now().with_timezone(&self.timezone()),
+                warnings: Vec::new(),
+            })
+        }
+
+        async fn get_semester_start<'a, 'b>(
+            &'a self,
+            _context: ParamContext<'b, Self::ContextType>,
+            _request: &mut CourseRequest,
+            _token: &Self::Token,
+        ) -> Result<chrono::DateTime<FixedOffset>> {
+            Ok(self
+                .timezone()
+                .with_ymd_and_hms(2025, 9, 8, 0, 0, 0)
+                .unwrap())
+        }
+    }
+
+    fn request() -> CourseRequest {
+        CourseRequest {
+            credentials: Credentials {
+                username: "2023000000".to_string(),
+                password: "password".into(),
+                extra: HashMap::new(),
+            },
+            semester: None,
+            timeout: None,
+            options: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn deadline_exceeded_returns_timeout() {
+        let wrapper = Wrapper::new(
+            SlowProvider {
+                delay: Duration::from_secs(5),
+                ..Default::default()
+            },
+            CacheManager::new(MemoryCache::default()),
+        )
+        .with_deadline(Duration::from_millis(50));
+
+        let result = wrapper.get_courses(&mut request()).await;
+        assert!(matches!(result, Err(crate::Error::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn request_timeout_returns_timeout() {
+        let wrapper = Wrapper::new(
+            SlowProvider {
+                delay: Duration::from_secs(5),
+                ..Default::default()
+            },
+            CacheManager::new(MemoryCache::default()),
+        );
+        let mut request = CourseRequest {
+            timeout: Some(1),
+            ..request()
+        };
+
+        let started = std::time::Instant::now();
+        let result = wrapper.get_courses(&mut request).await;
+        assert!(matches!(result, Err(crate::Error::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn curfew_is_retried_within_budget() {
+        let wrapper = |max_wait: Option<Duration>| {
+            let wrapper = Wrapper::new(
+                SlowProvider {
+                    curfews: 1.into(),
+                    ..Default::default()
+                },
+                CacheManager::new(MemoryCache::default()),
+            );
+            match max_wait {
+                Some(max_wait) => wrapper.retry_on_curfew(max_wait),
+                None => wrapper,
+            }
+        };
+
+        let result = wrapper(None).get_courses(&mut request()).await;
+        assert!(matches!(result, Err(crate::Error::CurfewTime(()))));
+
+        let retrying = wrapper(Some(Duration::from_millis(50)));
+        assert!(retrying.get_courses(&mut request()).await.is_ok());
+        assert_eq!(
+            retrying
+                .provider
+                .curfews
+                .load(std::sync::atomic::Ordering::SeqCst),
+            0
+        );
+    }
+
+    #[test]
+    fn request_timeout_only_shortens_deadline() {
+        let wrapper = Wrapper::new(
+            SlowProvider {
+                delay: Duration::ZERO,
+                ..Default::default()
+            },
+            CacheManager::new(MemoryCache::default()),
+        )
+        .with_deadline(Duration::from_secs(45));
+
+        let with_timeout = |timeout| CourseRequest {
+            timeout,
+            ..request()
+        };
+        assert_eq!(
+            wrapper.deadline_for(&with_timeout(None)),
+            Some(Duration::from_secs(45))
+        );
+        assert_eq!(
+            wrapper.deadline_for(&with_timeout(Some(10))),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            wrapper.deadline_for(&with_timeout(Some(120))),
+            Some(Duration::from_secs(45))
+        );
+    }
+
+    #[tokio::test]
+    async fn deadline_not_exceeded_returns_courses() {
+        let wrapper = Wrapper::new(
+            SlowProvider {
+                delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+            CacheManager::new(MemoryCache::default()),
+        )
+        .with_deadline(Duration::from_secs(5));
+
+        let response = wrapper.get_courses(&mut request()).await.unwrap();
+        assert!(response.courses.is_empty());
+    }
+
+    #[tokio::test]
+    async fn logout_revokes_cached_token() {
+        let wrapper = Wrapper::new(
+            SlowProvider::default(),
+            CacheManager::new(MemoryCache::default()),
+        );
+        let revoked = || {
+            wrapper
+                .provider
+                .revoked
+                .load(std::sync::atomic::Ordering::SeqCst)
+        };
+
+        // 没有缓存的 token 时不需要撤销
+        wrapper.logout(&request()).await.unwrap();
+        assert_eq!(revoked(), 0);
+
+        wrapper.get_courses(&mut request()).await.unwrap();
+        wrapper.logout(&request()).await.unwrap();
+        assert_eq!(revoked(), 1);
+        assert!(
+            !wrapper
+                .cache_manager
+                .has_token_cache(&wrapper.token_cache_key(&request()))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn retry_keeps_session_on_provider_error() {
+        let wrapper = |failure| {
+            Wrapper::new(
+                SlowProvider {
+                    failure: Mutex::new(Some(failure)),
+                    ..Default::default()
+                },
+                CacheManager::new(MemoryCache::default()),
+            )
+        };
+        let count = |counter: &std::sync::atomic::AtomicUsize| {
+            counter.load(std::sync::atomic::Ordering::SeqCst)
+        };
+
+        // 上游暂时故障：重试时沿用缓存的 token，不撤销会话
+        let transient = wrapper(crate::Error::Provider {
+            provider: "slow".to_string(),
+            message: "HTTP 502".to_string(),
+        });
+        transient.get_courses(&mut request()).await.unwrap();
+        assert_eq!(count(&transient.provider.logins), 1);
+        assert_eq!(count(&transient.provider.revoked), 0);
+
+        // 认证失败：只清除本地缓存并重新登录
+        let expired = wrapper(crate::Error::authentication(
+            crate::AuthFailReason::Expired,
+            "expired",
+        ));
+        expired.get_courses(&mut request()).await.unwrap();
+        assert_eq!(count(&expired.provider.logins), 2);
+        assert_eq!(count(&expired.provider.revoked), 0);
+    }
+
+    #[test]
+    fn capabilities_pass_through_wrapper() {
+        let wrapper = Wrapper::new(
+            SlowProvider::default(),
+            CacheManager::new(MemoryCache::default()),
+        );
+        assert!(!ProviderWrapper::capabilities(&wrapper).recurrence_native);
+        assert!(
+            crate::providers::redrock::RedrockProvider::new()
+                .capabilities()
+                .recurrence_native
+        );
+    }
+
+    #[test]
+    fn default_registry_lists_builtin_providers() {
+        let names = |registry: &ProviderRegistry| {
+            let mut names: Vec<_> = registry
+                .list_providers()
+                .map(|(name, _)| name.to_string())
+                .collect();
+            names.sort_unstable();
+            names
+        };
+        // mock 与 file 需要显式开启
+        let registry = build_default_registry(MemoryCache::default());
+        assert_eq!(names(&registry), ["redrock", "wecqupt"]);
+        let options = RegistryOptions {
+            include_mock: true,
+            file_provider: Some(file::FileLookup::Username),
+            ..Default::default()
+        };
+        let registry = build_registry(MemoryCache::default(), options).unwrap();
+        assert_eq!(names(&registry), ["file", "mock", "redrock", "wecqupt"]);
+
+        let options = RegistryOptions {
+            redrock_api_root: Some("ftp://example.com".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            build_registry(MemoryCache::default(), options),
+            Err(crate::Error::Config(_))
+        ));
+    }
+
+    #[test]
+    fn arc_providers_can_be_registered_and_dropped() {
+        let mut registry = ProviderRegistry::new();
+        registry.register(
+            Wrapper::new(
+                mock::MockProvider::new(),
+                CacheManager::new(MemoryCache::default()),
+            )
+            .into_static(),
+        );
+        // 运行时根据用户提供的路径构造
+        let provider: Arc<dyn ProviderWrapper> = Arc::new(Wrapper::new(
+            file::FileProvider::new("schedule.ics"),
+            CacheManager::new(MemoryCache::default()),
+        ));
+        registry.register_arc(provider.clone());
+        assert_eq!(Arc::strong_count(&provider), 2);
+
+        let found = registry.get_provider("file").unwrap();
+        assert!(Arc::ptr_eq(&found, &provider));
+        assert_eq!(registry.providers().count(), 2);
+        drop(found);
+
+        assert!(registry.unregister("file").is_some());
+        assert!(registry.get_provider("file").is_none());
+        assert_eq!(Arc::strong_count(&provider), 1);
+        assert!(registry.get_provider("mock").is_some());
+    }
+
+    #[test]
+    fn api_root_passes_through_wrapper() {
+        let options = RegistryOptions {
+            include_mock: true,
+            file_provider: Some(file::FileLookup::Username),
+            ..Default::default()
+        };
+        let registry = build_registry(MemoryCache::default(), options).unwrap();
+        let root = |name| {
+            registry
+                .get_provider(name)
+                .unwrap()
+                .api_root()
+                .map(str::to_string)
+        };
+        assert_eq!(
+            root("redrock").as_deref(),
+            Some("https://be-prod.redrock.cqupt.edu.cn")
+        );
+        assert!(root("wecqupt").is_some());
+        assert_eq!(root("mock"), None);
+        assert_eq!(root("file"), None);
+    }
+
+    #[test]
+    fn providers_share_timezone() {
+        use crate::providers::{
+            file::FileProvider, mock::MockProvider, redrock::RedrockProvider,
+            wecqupt::WecquptProvider,
+        };
+        let offsets = [
+            RedrockProvider::new().timezone(),
+            WecquptProvider::new().timezone(),
+            MockProvider::new().timezone(),
+            FileProvider::in_dir(std::env::temp_dir()).timezone(),
+            SlowProvider::default().timezone(),
+        ];
+        assert!(offsets.iter().all(|&tz| tz == crate::CQUPT_TZ));
+    }
+
+    #[test]
+    fn semester_cache_key_includes_start_date() {
+        let wrapper = Wrapper::new(
+            SlowProvider::default(),
+            CacheManager::new(MemoryCache::default()),
+        );
+        let with_start = |date: &str| CourseRequest {
+            semester: Some(crate::Semester::from_date_str(date).unwrap()),
+            ..request()
+        };
+
+        let autumn = wrapper.semester_cache_key("courses", &with_start("2025-09-08"));
+        let spring = wrapper.semester_cache_key("courses", &with_start("2026-03-02"));
+        assert_eq!(autumn, "slow:courses:2023000000:2025-09-08");
+        assert_ne!(autumn, spring);
+        assert_ne!(autumn, wrapper.token_cache_key(&with_start("2025-09-08")));
+
+        // 未指定学期时使用推算的当前学期
+        let detected = crate::SemesterDetector::create_current_semester();
+        assert!(
+            wrapper
+                .semester_cache_key("courses", &request())
+                .ends_with(&detected.start_date.format("%Y-%m-%d").to_string())
+        );
+    }
+}
@@ -1,14 +1,28 @@
-use crate::{CourseRequest, CourseResponse, Error, Result};
+use crate::{AuthFailReason, CourseRequest, CourseResponse, Error, Result};
 use base64::{Engine as _, engine::general_purpose};
 use chrono::Utc;
-use reqwest::{Client, ClientBuilder};
+use reqwest::{
+    Client, ClientBuilder, Url,
+    header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT},
+};
 use serde::{Deserialize, de::Deserializer};
 use std::time::Duration;
 
+/// 每个主机保留的空闲连接数默认值
+pub const DEFAULT_POOL_MAX_IDLE_PER_HOST: usize = 16;
+/// 空闲连接的默认保留时间
+pub const DEFAULT_POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
 /// 基础provider结构
 pub struct BaseProviderBuilder {
     pub client_builder: ClientBuilder,
     pub info: ProviderInfo,
+    /// 每个请求默认携带的请求头，构建时写入 client
+    pub headers: HeaderMap,
+    /// 每个主机保留的空闲连接数，构建时写入 client
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接保留时间，`None` 表示不回收
+    pub pool_idle_timeout: Option<Duration>,
 }
 
 pub struct BaseProvider {
@@ -23,26 +37,59 @@ pub struct ProviderInfo {
 
 impl BaseProviderBuilder {
     pub fn new(info: ProviderInfo) -> Self {
-        let client_builder = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("CQUPT-ICS-Rust/0.1.0")
-            .default_headers({
-                let mut headers = reqwest::header::HeaderMap::new();
-                headers.insert("Accept", "*/*".parse().unwrap());
-                headers.insert("Content-Type", "application/json".parse().unwrap());
-                headers.insert(
-                    "Accept-Encoding",
-                    "br;q=1.0, gzip;q=0.9, deflate;q=0.8".parse().unwrap(),
-                );
-                headers
-            });
+        let client_builder = Client::builder().timeout(Duration::from_secs(30));
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, "CQUPT-ICS-Rust/0.1.0".parse().unwrap());
+        headers.insert("Accept", "*/*".parse().unwrap());
+        headers.insert("Content-Type", "application/json".parse().unwrap());
+        headers.insert(
+            "Accept-Encoding",
+            "br;q=1.0, gzip;q=0.9, deflate;q=0.8".parse().unwrap(),
+        );
 
         Self {
             client_builder,
             info,
+            headers,
+            pool_max_idle_per_host: DEFAULT_POOL_MAX_IDLE_PER_HOST,
+            pool_idle_timeout: Some(DEFAULT_POOL_IDLE_TIMEOUT),
         }
     }
 
+    /// 设置每个主机保留的空闲连接数
+    ///
+    /// 突发请求时复用连接，减少与校园接口的 TLS 握手。
+    pub fn with_pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.pool_max_idle_per_host = max_idle;
+        self
+    }
+
+    /// 设置空闲连接保留时间，传入 `None` 表示不回收
+    pub fn with_pool_idle_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.pool_idle_timeout = timeout.into();
+        self
+    }
+
+    /// 设置默认请求头，已存在的同名请求头会被覆盖
+    ///
+    /// 名称或值不合法时返回 [`Error::Config`]。
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| Error::Config(format!("Invalid header name '{}': {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::Config(format!("Invalid value for header '{}': {}", name, e)))?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// 设置 User-Agent
+    ///
+    /// 部分校园接口会拒绝 User-Agent 不符合预期客户端的请求，
+    /// 例如掌上重邮接口需要 App 的 UA。
+    pub fn with_user_agent(self, user_agent: &str) -> Result<Self> {
+        self.with_header(USER_AGENT.as_str(), user_agent)
+    }
+
     pub fn new_with_timeout(info: ProviderInfo, timeout_secs: u64) -> Self {
         let mut s = Self::new(info);
         s.client_builder = s.client_builder.timeout(Duration::from_secs(timeout_secs));
@@ -52,6 +99,9 @@ impl BaseProviderBuilder {
     pub fn build(self) -> BaseProvider {
         let client = self
             .client_builder
+            .default_headers(self.headers)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
             .build()
             .expect("Failed to create HTTP client");
 
@@ -84,17 +134,50 @@ impl BaseProvider {
         }
     }
 
+    /// 上游响应解析失败
+    pub fn deserialize_error(&self, context: impl Into<String>) -> Error {
+        Error::Deserialize {
+            provider: self.info.name.clone(),
+            context: context.into(),
+        }
+    }
+
     /// 创建空的课程响应
     pub fn empty_response(&self, request: &CourseRequest) -> CourseResponse {
-        let tz = chrono::FixedOffset::east_opt(8 * 3600).unwrap(); // UTC+8
         CourseResponse {
             courses: Vec::new(),
             semester: request.semester.clone().unwrap(),
-            generated_at: Utc::now().with_timezone(&tz),
+            generated_at: Utc::now().with_timezone(&crate::CQUPT_TZ),
+            warnings: Vec::new(),
         }
     }
 }
 
+/// 校验接口根地址
+///
+/// 仅接受带主机名的 http/https 地址，返回的 URL 路径总以 `/` 结尾，便于 `join`。
+pub fn parse_api_root(api_root: &str) -> Result<Url> {
+    let mut url = Url::parse(api_root)
+        .map_err(|e| Error::Config(format!("Invalid API root '{}': {}", api_root, e)))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(Error::Config(format!(
+            "Invalid API root '{}': expected an http(s) URL",
+            api_root
+        )));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(Error::Config(format!(
+            "Invalid API root '{}': query and fragment are not allowed",
+            api_root
+        )));
+    }
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Ok(url)
+}
+
 #[derive(Debug, Deserialize)]
 struct Claims {
     #[serde(deserialize_with = "de_exp")]
@@ -137,7 +220,10 @@ fn decode_base64_flex(s: &str) -> Result<Vec<u8>> {
     if let Ok(b) = general_purpose::STANDARD_NO_PAD.decode(s) {
         return Ok(b);
     }
-    Err(Error::Authentication("Base64 decode failed".to_string()))
+    Err(Error::authentication(
+        AuthFailReason::Unknown,
+        "Base64 decode failed",
+    ))
 }
 
 pub fn is_token_expired(token: &str) -> Result<bool> {
@@ -155,8 +241,85 @@ pub fn is_token_expired(token: &str) -> Result<bool> {
         let now = Utc::now().timestamp() as u64;
         Ok(claims.exp <= now)
     } else {
-        Err(Error::Authentication(
-            "Token format not recognized (need 2 or 3 segments)".to_string(),
+        Err(Error::authentication(
+            AuthFailReason::Unknown,
+            "Token format not recognized (need 2 or 3 segments)",
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header, method},
+    };
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "test".to_string(),
+            description: "test".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn header_overrides_reach_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("user-agent", "custom-agent/1.0"))
+            .and(header("x-client", "cqupt-ics"))
+            .and(header("accept", "*/*"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let base = BaseProviderBuilder::new(info())
+            .with_user_agent("custom-agent/1.0")
+            .unwrap()
+            .with_header("X-Client", "cqupt-ics")
+            .unwrap()
+            .build();
+        let response = base.client.get(server.uri()).send().await.unwrap();
+        assert!(response.status().is_success());
+    }
+
+    #[test]
+    fn invalid_header_is_config_error() {
+        assert!(matches!(
+            BaseProviderBuilder::new(info()).with_header("bad header", "x"),
+            Err(Error::Config(_))
+        ));
+        assert!(matches!(
+            BaseProviderBuilder::new(info()).with_user_agent("line\nbreak"),
+            Err(Error::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn pool_settings_are_applied() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let builder = BaseProviderBuilder::new(info())
+            .with_pool_max_idle_per_host(2)
+            .with_pool_idle_timeout(Duration::from_secs(5));
+        assert_eq!(builder.pool_max_idle_per_host, 2);
+        assert_eq!(builder.pool_idle_timeout, Some(Duration::from_secs(5)));
+
+        let base = builder.build();
+        for _ in 0..2 {
+            let response = base.client.get(server.uri()).send().await.unwrap();
+            assert!(response.status().is_success());
+        }
+
+        let builder = BaseProviderBuilder::new(info()).with_pool_idle_timeout(None);
+        assert_eq!(builder.pool_idle_timeout, None);
+        builder.build();
+    }
+}
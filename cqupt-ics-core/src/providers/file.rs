@@ -0,0 +1,284 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::{
+    AuthFailReason, CourseRequest, CourseResponse, Error, Result,
+    providers::{BaseProvider, BaseProviderBuilder, ParamContext, Provider, ProviderInfo},
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 文件来源
+enum FileSource {
+    /// 固定读取某个文件
+    Fixed(PathBuf),
+    /// 在目录下按用户名查找文件，用户名不能包含路径
+    Dir(PathBuf),
+    /// 用户名即文件路径
+    Username,
+}
+
+/// 注册 file provider 时按用户名查找文件的方式
+#[derive(Debug, Clone)]
+pub enum FileLookup {
+    /// 在目录下查找以用户名命名的文件，见 [`FileProvider::in_dir`]
+    InDir(PathBuf),
+    /// 用户名即文件路径，见 [`FileProvider::from_username`]
+    Username,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileToken {
+    pub path: PathBuf,
+}
+
+/// 本地文件 provider
+///
+/// 读取已导出的 JSON `CourseResponse` 或本项目生成的 ICS，不访问网络，
+/// 可在没有账号密码的情况下重新生成日历（例如重新应用节假日、修改提醒）。
+pub struct FileProvider {
+    base: BaseProvider,
+    source: FileSource,
+}
+
+impl FileProvider {
+    /// 固定读取 `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self::with_source(FileSource::Fixed(path.into()))
+    }
+
+    /// 读取 `dir` 下以用户名命名的文件
+    ///
+    /// 用户名只能是单个文件名，不能包含 `/` 或 `..`，适合对外提供服务时使用。
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        Self::with_source(FileSource::Dir(dir.into()))
+    }
+
+    /// 将用户名直接作为文件路径，仅适合本地命令行使用
+    pub fn from_username() -> Self {
+        Self::with_source(FileSource::Username)
+    }
+
+    /// 按 `lookup` 查找文件
+    pub fn with_lookup(lookup: FileLookup) -> Self {
+        match lookup {
+            FileLookup::InDir(dir) => Self::in_dir(dir),
+            FileLookup::Username => Self::from_username(),
+        }
+    }
+
+    fn with_source(source: FileSource) -> Self {
+        let builder = BaseProviderBuilder::new(ProviderInfo {
+            name: "file".to_string(),
+            description: "本地 JSON/ICS 文件".to_string(),
+        });
+
+        Self {
+            base: builder.build(),
+            source,
+        }
+    }
+
+    /// 根据请求确定要读取的文件
+    fn resolve_path(&self, request: &CourseRequest) -> Result<PathBuf> {
+        let username = request.credentials.username.trim();
+        match &self.source {
+            FileSource::Fixed(path) => Ok(path.clone()),
+            FileSource::Dir(dir) => {
+                let mut components = Path::new(username).components();
+                match (components.next(), components.next()) {
+                    (Some(Component::Normal(name)), None) => Ok(dir.join(name)),
+                    _ => Err(Error::authentication(
+                        AuthFailReason::BadCredentials,
+                        format!("Invalid file name: {}", username),
+                    )),
+                }
+            }
+            FileSource::Username if username.is_empty() => Err(Error::authentication(
+                AuthFailReason::BadCredentials,
+                "文件路径不能为空",
+            )),
+            FileSource::Username => Ok(PathBuf::from(username)),
+        }
+    }
+
+    /// 读取并解析文件，内容以 `BEGIN:VCALENDAR` 开头时按 ICS 解析，否则按 JSON 解析
+    async fn load(&self, path: &Path) -> Result<CourseResponse> {
+        let content = tokio::fs::read_to_string(path).await.map_err(|e| {
+            self.base
+                .custom_error(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        if content.trim_start().starts_with("BEGIN:VCALENDAR") {
+            crate::ics::parse(&content)
+        } else {
+            serde_json::from_str(&content).map_err(|e| {
+                self.base
+                    .custom_error(format!("Failed to parse {}: {}", path.display(), e))
+            })
+        }
+    }
+
+    /// 从上下文取出文件内容，不存在时读取文件
+    async fn loaded<'b>(
+        &self,
+        context: ParamContext<'b, CourseResponse>,
+        token: &FileToken,
+    ) -> Result<CourseResponse> {
+        match context {
+            Some(ctx) => {
+                if ctx.is_empty() {
+                    ctx.set(self.load(&token.path).await?);
+                }
+                ctx.as_ref()
+                    .cloned()
+                    .ok_or_else(|| self.base.custom_error("Failed to load file"))
+            }
+            None => self.load(&token.path).await,
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for FileProvider {
+    type Token = FileToken;
+    type ContextType = CourseResponse;
+
+    fn name(&self) -> &str {
+        &self.base.info.name
+    }
+
+    fn description(&self) -> &str {
+        &self.base.info.description
+    }
+
+    async fn authenticate<'a, 'b>(
+        &'a self,
+        _context: ParamContext<'b, Self::ContextType>,
+        request: &CourseRequest,
+    ) -> Result<Self::Token> {
+        let path = self.resolve_path(request)?;
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Err(Error::authentication(
+                AuthFailReason::BadCredentials,
+                format!("文件不存在: {}", path.display()),
+            ));
+        }
+        Ok(FileToken { path })
+    }
+
+    async fn validate_token(&self, token: &Self::Token) -> Result<bool> {
+        Ok(tokio::fs::try_exists(&token.path).await.unwrap_or(false))
+    }
+
+    async fn refresh_token(&self, token: &Self::Token) -> Result<Self::Token> {
+        Ok(token.clone())
+    }
+
+    async fn get_semester_start<'a, 'b>(
+        &'a self,
+        context: ParamContext<'b, Self::ContextType>,
+        _request: &mut CourseRequest,
+        token: &Self::Token,
+    ) -> Result<DateTime<FixedOffset>> {
+        Ok(self.loaded(context, token).await?.semester.start_date)
+    }
+
+    async fn get_courses<'a, 'b>(
+        &'a self,
+        context: ParamContext<'b, Self::ContextType>,
+        request: &mut CourseRequest,
+        token: &Self::Token,
+    ) -> Result<CourseResponse> {
+        let mut response = self.loaded(context, token).await?;
+        if let Some(semester) = &request.semester {
+            response.semester = semester.clone();
+        }
+        response.generated_at = Utc::now().with_timezone(&self.timezone());
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        Credentials,
+        ics::IcsGenerator,
+        providers::{Context, mock::MockProvider},
+    };
+
+    fn request(username: &str) -> CourseRequest {
+        CourseRequest {
+            credentials: Credentials {
+                username: username.to_string(),
+                password: Default::default(),
+                extra: HashMap::new(),
+            },
+            semester: None,
+            timeout: None,
+            options: Default::default(),
+        }
+    }
+
+    async fn fetch<P: Provider>(provider: &P, request: &mut CourseRequest) -> CourseResponse {
+        let mut ctx = Context::default();
+        let token = provider.authenticate(None, request).await.unwrap();
+        let start = provider
+            .get_semester_start(ctx.as_param(), request, &token)
+            .await
+            .unwrap();
+        request.semester = Some(crate::Semester {
+            start_date: start,
+            weeks_total: None,
+        });
+        provider
+            .get_courses(ctx.as_param(), request, &token)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn reads_json_and_ics_exports() {
+        let dir = std::env::temp_dir().join(format!("cqupt-ics-file-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+
+        let mut mock_request = request("2023000000");
+        let original = fetch(&MockProvider::new(), &mut mock_request).await;
+        tokio::fs::write(
+            dir.join("export.json"),
+            serde_json::to_string(&original).unwrap(),
+        )
+        .await
+        .unwrap();
+        tokio::fs::write(
+            dir.join("export.ics"),
+            IcsGenerator::default().generate(&original).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let provider = FileProvider::in_dir(&dir);
+        let json = fetch(&provider, &mut request("export.json")).await;
+        assert_eq!(json.courses.len(), original.courses.len());
+        assert_eq!(json.semester.start_date, original.semester.start_date);
+
+        let ics = fetch(&provider, &mut request("export.ics")).await;
+        assert_eq!(ics.courses.len(), original.courses.len());
+        assert_eq!(ics.courses[0].start_time, original.courses[0].start_time);
+
+        for name in ["../export.json", "missing.json", ""] {
+            assert!(matches!(
+                provider.authenticate(None, &request(name)).await,
+                Err(Error::Authentication {
+                    reason: AuthFailReason::BadCredentials,
+                    ..
+                })
+            ));
+        }
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    AuthFailReason, Course, CourseRequest, CourseResponse, Error, Result,
+    providers::{
+        BaseProvider, BaseProviderBuilder, ParamContext, Provider, ProviderCapabilities,
+        ProviderInfo,
+    },
+};
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 固定的学期开始日期 (2025-09-08，星期一)
+const SEMESTER_START: (i32, u32, u32) = (2025, 9, 8);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockToken {
+    pub username: String,
+}
+
+/// 离线 mock provider
+///
+/// 不访问任何网络，返回一组固定的课程、考试与自定义日程，
+/// 用于示例演示与端到端测试。
+pub struct MockProvider {
+    base: BaseProvider,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        let builder = BaseProviderBuilder::new(ProviderInfo {
+            name: "mock".to_string(),
+            description: "离线演示数据".to_string(),
+        });
+
+        Self {
+            base: builder.build(),
+        }
+    }
+
+    /// 计算指定周次、星期和时刻对应的时间
+    fn at(
+        &self,
+        semester_start: &DateTime<FixedOffset>,
+        week: u32,
+        weekday: u32,
+        (hour, minute): (i64, i64),
+    ) -> DateTime<FixedOffset> {
+        crate::types::week_day_date(semester_start, week, weekday)
+            + chrono::Duration::hours(hour)
+            + chrono::Duration::minutes(minute)
+    }
+
+    /// 生成固定的演示课程
+    fn build_courses(&self, semester_start: &DateTime<FixedOffset>) -> Vec<Course> {
+        let mut courses = vec![
+            Course {
+                name: "高等数学A(上)".to_string(),
+                code: Some("A1100010".to_string()),
+                teacher: Some("张老师".to_string()),
+                teachers: vec!["张老师".to_string()],
+                location: Some("2101".to_string()),
+                start_time: self.at(semester_start, 1, 1, (8, 0)),
+                end_time: self.at(semester_start, 1, 1, (9, 40)),
+                course_type: Some("必修".to_string()),
+                weeks: Some((1..=16).collect()),
+                weekday: Some(1),
+                begin_lesson: Some(1),
+                lesson_duration: Some(2),
+                current_week: Some(1),
+                ..Default::default()
+            },
+            Course {
+                name: "大学英语".to_string(),
+                code: Some("A1200020".to_string()),
+                teacher: Some("李老师".to_string()),
+                teachers: vec!["李老师".to_string()],
+                location: Some("3208".to_string()),
+                start_time: self.at(semester_start, 1, 3, (10, 15)),
+                end_time: self.at(semester_start, 1, 3, (11, 55)),
+                course_type: Some("必修".to_string()),
+                weeks: Some((1..=15).step_by(2).collect()),
+                weekday: Some(3),
+                begin_lesson: Some(3),
+                lesson_duration: Some(2),
+                current_week: Some(1),
+                ..Default::default()
+            },
+            Course {
+                name: "程序设计基础".to_string(),
+                code: Some("A2100030".to_string()),
+                teacher: Some("王老师".to_string()),
+                teachers: vec!["王老师".to_string()],
+                location: Some("综合实验楼B405".to_string()),
+                start_time: self.at(semester_start, 1, 4, (14, 0)),
+                end_time: self.at(semester_start, 1, 4, (15, 40)),
+                course_type: Some("选修".to_string()),
+                weeks: Some(vec![1, 2, 3, 4, 6, 7, 8]),
+                weekday: Some(4),
+                begin_lesson: Some(5),
+                lesson_duration: Some(2),
+                current_week: Some(1),
+                ..Default::default()
+            },
+        ];
+
+        courses.push(Course {
+            name: "线性代数 (考试)".to_string(),
+            location: Some("4101".to_string()),
+            start_time: self.at(semester_start, 18, 4, (14, 0)),
+            end_time: self.at(semester_start, 18, 4, (16, 0)),
+            course_type: Some("考试".to_string()),
+            exam_type: Some("期末".to_string()),
+            seat: Some("12".to_string()),
+            status: Some("正常".to_string()),
+            raw_week: Some("18".to_string()),
+            ..Default::default()
+        });
+
+        courses.push(Course {
+            name: "社团活动".to_string(),
+            code: Some("1".to_string()),
+            start_time: self.at(semester_start, 3, 6, (19, 0)),
+            end_time: self.at(semester_start, 3, 6, (20, 40)),
+            note: Some("自定义日程: 红岩网校招新宣讲".to_string()),
+            weeks: Some(vec![3]),
+            weekday: Some(6),
+            begin_lesson: Some(9),
+            lesson_duration: Some(2),
+            current_week: Some(1),
+            ..Default::default()
+        });
+
+        courses
+    }
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    type Token = MockToken;
+    type ContextType = ();
+
+    fn name(&self) -> &str {
+        &self.base.info.name
+    }
+
+    fn description(&self) -> &str {
+        &self.base.info.description
+    }
+
+    async fn authenticate<'a>(
+        &'a self,
+        _context: ParamContext<'_, Self::ContextType>,
+        request: &CourseRequest,
+    ) -> Result<Self::Token> {
+        if request.credentials.username.trim().is_empty() {
+            return Err(Error::authentication(
+                AuthFailReason::BadCredentials,
+                "用户名不能为空",
+            ));
+        }
+        Ok(MockToken {
+            username: request.credentials.username.clone(),
+        })
+    }
+
+    async fn validate_token(&self, token: &Self::Token) -> Result<bool> {
+        Ok(!token.username.is_empty())
+    }
+
+    async fn refresh_token(&self, token: &Self::Token) -> Result<Self::Token> {
+        Ok(token.clone())
+    }
+
+    async fn get_semester_start<'a, 'b>(
+        &'a self,
+        _context: ParamContext<'b, Self::ContextType>,
+        _request: &mut CourseRequest,
+        _token: &Self::Token,
+    ) -> Result<DateTime<FixedOffset>> {
+        let (year, month, day) = SEMESTER_START;
+        self.timezone()
+            .with_ymd_and_hms(year, month, day, 0, 0, 0)
+            .single()
+            .ok_or_else(|| self.base.custom_error("Invalid mock semester start"))
+    }
+
+    async fn get_courses<'a, 'b>(
+        &'a self,
+        _context: ParamContext<'b, Self::ContextType>,
+        request: &mut CourseRequest,
+        _token: &Self::Token,
+    ) -> Result<CourseResponse> {
+        let semester = request
+            .semester
+            .as_ref()
+            .ok_or_else(|| self.base.custom_error("Semester start date is required"))?;
+
+        Ok(CourseResponse {
+            courses: self.build_courses(&semester.start_date),
+            semester: semester.clone(),
+            generated_at: Utc::now().with_timezone(&self.timezone()),
+            warnings: Vec::new(),
+        })
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            recurrence_native: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{Credentials, Semester, ics::IcsGenerator, providers::Context};
+
+    #[tokio::test]
+    async fn mock_courses_generate_calendar() {
+        let provider = MockProvider::new();
+        let mut request = CourseRequest {
+            credentials: Credentials {
+                username: "2023000000".to_string(),
+                password: Default::default(),
+                extra: HashMap::new(),
+            },
+            semester: None,
+            timeout: None,
+            options: Default::default(),
+        };
+        let mut ctx: Context<()> = Context::default();
+
+        let token = provider.authenticate(None, &request).await.unwrap();
+        let start = provider
+            .get_semester_start(ctx.as_param(), &mut request, &token)
+            .await
+            .unwrap();
+        request.semester = Some(Semester {
+            start_date: start,
+            weeks_total: None,
+        });
+        let response = provider
+            .get_courses(ctx.as_param(), &mut request, &token)
+            .await
+            .unwrap();
+
+        assert_eq!(response.courses.len(), 5);
+        assert_eq!(
+            response.courses[0].start_time,
+            provider
+                .timezone()
+                .with_ymd_and_hms(2025, 9, 8, 8, 0, 0)
+                .unwrap()
+        );
+
+        let ics = IcsGenerator::default().generate(&response).unwrap();
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 5);
+        assert_eq!(ics.matches("RRULE:").count(), 3);
+        assert!(provider.capabilities().recurrence_native);
+        assert!(ics.contains("[期末考试] 线性代数 (考试) - 4101"));
+    }
+}
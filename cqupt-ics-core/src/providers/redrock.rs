@@ -1,29 +1,49 @@
 use std::collections::HashMap;
 
 use crate::{
-    Course, CourseRequest, CourseResponse, Error, Result,
+    AuthFailReason, Course, CourseRequest, CourseResponse, Error, Result, SemesterDetector,
     prelude::*,
-    providers::{BaseProvider, ParamContext, ParamContextExt, Provider},
+    providers::{BaseProvider, ParamContext, ParamContextExt, Provider, parse_api_root},
 };
 use async_trait::async_trait;
-use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
-const LESSON_TIMES: [(usize, usize); 12] = [
-    (8 * 60, 8 * 60 + 45),        // 第1节: 08:00-08:45
-    (8 * 60 + 55, 9 * 60 + 40),   // 第2节: 08:55-09:40
-    (10 * 60 + 15, 11 * 60),      // 第3节: 10:15-11:00
-    (11 * 60 + 55, 11 * 60 + 55), // 第4节: 11:15-11:55
-    (14 * 60, 14 * 60 + 45),      // 第5节: 14:00-14:45
-    (14 * 60 + 55, 15 * 60 + 40), // 第6节: 14:55-15:40
-    (16 * 60 + 15, 17 * 60),      // 第7节: 16:15-17:00
-    (17 * 60 + 10, 17 * 60 + 55), // 第8节: 17:10-17:55
-    (19 * 60, 19 * 60 + 45),      // 第9节: 19:00-19:45
-    (19 * 60 + 55, 20 * 60 + 40), // 第10节: 19:55-20:40
-    (20 * 60 + 50, 21 * 60 + 35), // 第11节: 20:50-21:35
-    (21 * 60 + 45, 22 * 60 + 30), // 第12节: 21:45-22:30
-];
+/// 考试既无结束时间也无时长时使用的默认时长（分钟）
+const DEFAULT_EXAM_DURATION_MINUTES: u32 = 120;
+/// 未对齐节次的自定义日程默认时长（分钟）
+const DEFAULT_CUSTOM_DURATION_MINUTES: i64 = 60;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// 节次是否落在课表内（开始与结束节次都在 1..=12）
+fn is_lesson_aligned(begin_lesson: u32, period: u32) -> bool {
+    begin_lesson >= 1 && period >= 1 && begin_lesson + period - 1 <= LESSONS_PER_DAY as u32
+}
+
+/// 按周模式展开 `begin..=end` 的上课周
+///
+/// 支持 `single`/`单`（单周）、`double`/`双`（双周），其余（`all`/`全`）视为每周
+fn weeks_from_model(begin: u32, end: u32, model: &str) -> Vec<u32> {
+    let parity = match model.trim() {
+        "single" | "单" | "单周" => Some(1),
+        "double" | "双" | "双周" => Some(0),
+        _ => None,
+    };
+    (begin..=end)
+        .filter(|week| parity.is_none_or(|parity| week % 2 == parity))
+        .collect()
+}
+
+/// 课程的上课周
+///
+/// `week` 为空时（部分异常响应）按 `week_begin..=week_end` 与 `weekModel` 推算
+fn class_weeks(class: &RedrockClass) -> Vec<u32> {
+    if !class.week.is_empty() || class.week_begin == 0 {
+        return class.week.clone();
+    }
+    weeks_from_model(class.week_begin, class.week_end, &class.week_model)
+}
 
 /// Redrock API响应数据结构
 #[allow(dead_code)]
@@ -98,8 +118,11 @@ struct RedrockExam {
     course: String,
     #[serde(rename = "begin_time")]
     begin_time: String,
-    #[serde(rename = "end_time")]
+    #[serde(rename = "end_time", default)]
     end_time: String,
+    /// 考试时长，例如 "120分钟"
+    #[serde(default)]
+    duration: Option<String>,
     status: String,
     classroom: String,
     #[serde(rename = "type")]
@@ -133,22 +156,126 @@ struct RedrockCustomScheduleDate {
     week: Vec<u32>,
 }
 
+/// 学校网络宵禁时段（北京时间），`start` 晚于 `end` 时表示跨越午夜
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurfewWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl CurfewWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    /// 时刻是否落在宵禁时段内（含开始，不含结束）
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl Default for CurfewWindow {
+    /// 23:00 至次日 07:00
+    fn default() -> Self {
+        Self::new(
+            NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        )
+    }
+}
+
+impl std::str::FromStr for CurfewWindow {
+    type Err = Error;
+
+    /// 解析 `HH:MM-HH:MM` 形式的时段，如 `23:00-07:00`
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").ok();
+        s.split_once('-')
+            .and_then(|(start, end)| Some(Self::new(parse(start)?, parse(end)?)))
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "Invalid curfew window '{}', expected HH:MM-HH:MM",
+                    s
+                ))
+            })
+    }
+}
+
 pub struct RedrockProvider {
     base: BaseProvider,
+    /// 接口根地址（不含结尾的 `/`）
+    api_root: String,
+    /// 接口主机名，用于 Host 请求头
+    api_host: String,
+    /// 无法从接口推算学期开始时间时，是否回退到按日期估算
+    semester_fallback: bool,
+    /// 课表接口返回 500 时视为宵禁的时段
+    curfew_window: CurfewWindow,
 }
 
 impl RedrockProvider {
-    const API_ROOT: &'static str = "https://be-prod.redrock.cqupt.edu.cn";
+    const DEFAULT_API_ROOT: &'static str = "https://be-prod.redrock.cqupt.edu.cn";
     pub fn new() -> Self {
-        let mut base = BaseProviderBuilder::new(ProviderInfo {
+        // 接口要求掌上重邮 App 的 UA
+        let base = BaseProviderBuilder::new(ProviderInfo {
             name: "redrock".to_string(),
             description: "掌上重邮 API".to_string(),
-        });
-        base.client_builder = base
-            .client_builder
-            .user_agent("zhang shang zhong you/6.1.1 (iPhone; iOS 14.6; Scale/3.00)");
+        })
+        .with_user_agent("zhang shang zhong you/6.1.1 (iPhone; iOS 14.6; Scale/3.00)")
+        .unwrap();
+
+        Self {
+            base: base.build(),
+            api_root: Self::DEFAULT_API_ROOT.to_string(),
+            api_host: Self::DEFAULT_API_ROOT
+                .trim_start_matches("https://")
+                .to_string(),
+            semester_fallback: true,
+            curfew_window: CurfewWindow::default(),
+        }
+    }
+
+    /// 设置接口根地址，用于测试或镜像
+    ///
+    /// 地址必须是 http/https URL，否则返回 [`Error::Config`]。
+    pub fn with_api_root(mut self, api_root: &str) -> Result<Self> {
+        let url = parse_api_root(api_root)?;
+        self.api_host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => unreachable!("parse_api_root guarantees a host"),
+        };
+        self.api_root = url.as_str().trim_end_matches('/').to_string();
+        Ok(self)
+    }
 
-        Self { base: base.build() }
+    /// 设置是否允许回退到按日期估算的学期开始时间
+    ///
+    /// 关闭后，`now_week` 与 `version` 均无法解析时直接返回错误。
+    pub fn with_semester_fallback(mut self, enabled: bool) -> Self {
+        self.semester_fallback = enabled;
+        self
+    }
+
+    /// 设置宵禁时段，只有此时段内课表接口的 500 才视为宵禁
+    pub fn with_curfew_window(mut self, window: CurfewWindow) -> Self {
+        self.curfew_window = window;
+        self
+    }
+
+    /// 课表接口的非成功响应对应的错误
+    ///
+    /// 宵禁期间接口固定返回 500，其余时段的 500 是真实的服务端错误。
+    fn schedule_status_error(&self, status: StatusCode, now: NaiveTime) -> Error {
+        if status == StatusCode::INTERNAL_SERVER_ERROR && self.curfew_window.contains(now) {
+            Error::CurfewTime(())
+        } else {
+            self.base.custom_error(format!("HTTP {} error", status))
+        }
     }
 }
 
@@ -249,7 +376,7 @@ impl RedrockProvider {
         student_id: &str,
         token: &RedrockToken,
     ) -> Result<RedrockResponse> {
-        let url = format!("{}/magipoke-jwzx/kebiao", Self::API_ROOT);
+        let url = format!("{}/magipoke-jwzx/kebiao", self.api_root);
 
         let mut data = HashMap::new();
         data.insert(
@@ -269,18 +396,13 @@ impl RedrockProvider {
             .map_err(|e| self.base.handle_error_req(e))?;
 
         if !response.status().is_success() {
-            if response.status() == StatusCode::INTERNAL_SERVER_ERROR {
-                return Err(crate::Error::CurfewTime(()));
-            } else {
-                return Err(self
-                    .base
-                    .custom_error(format!("HTTP {} error", response.status())));
-            }
+            let now = Utc::now().with_timezone(&CQUPT_TZ).time();
+            return Err(self.schedule_status_error(response.status(), now));
         }
 
         response.json().await.map_err(|e| {
             self.base
-                .custom_error(format!("Failed to parse response: {}", e))
+                .deserialize_error(format!("class schedule: {}", e))
         })
     }
 
@@ -289,7 +411,7 @@ impl RedrockProvider {
         &self,
         token: &RedrockToken,
     ) -> Result<RedrockCustomScheduleResponse> {
-        let url = format!("{}/magipoke-reminder/Person/getTransaction", Self::API_ROOT);
+        let url = format!("{}/magipoke-reminder/Person/getTransaction", self.api_root);
 
         let response = self
             .base
@@ -309,7 +431,7 @@ impl RedrockProvider {
 
         let r: RedrockCustomScheduleResponse = response.json().await.map_err(|e| {
             self.base
-                .custom_error(format!("Failed to parse response: {}", e))
+                .deserialize_error(format!("custom schedule: {}", e))
         })?;
 
         if r.status != 200 {
@@ -322,11 +444,14 @@ impl RedrockProvider {
     }
 
     /// 获取课程表数据
+    ///
+    /// 单门课程转换失败时跳过该课程，并将原因写入 `warnings`。
     async fn get_class_schedule(
         &self,
         context: &mut Context<RedrockResponse>,
         request: &mut CourseRequest,
         token: &RedrockToken,
+        warnings: &mut Vec<String>,
     ) -> Result<(Vec<Course>, u32)> {
         let start_date = request
             .semester
@@ -337,19 +462,33 @@ impl RedrockProvider {
             })?
             .start_date;
 
+        let lessons = request.options.lesson_times.clone().unwrap_or_default();
         let mut courses = Vec::new();
-        let redrock_response = match context.as_ref() {
-            Some(data) => data,
-            None => {
-                &self
-                    .get_class_schedule_data(&request.credentials.username, token)
-                    .await?
-            }
-        };
+        // 与 get_semester_start 共用同一份课表，一次生成只请求一次 kebiao
+        if context.is_empty() {
+            context.set(
+                self.get_class_schedule_data(&request.credentials.username, token)
+                    .await?,
+            );
+        }
+        let redrock_response = context.as_ref().ok_or_else(|| {
+            self.base
+                .custom_error("Failed to get RedrockResponse from context".to_string())
+        })?;
         for class in &redrock_response.data {
-            let course =
-                self.convert_class_to_course(class, &start_date, redrock_response.now_week)?;
-            courses.push(course);
+            match self.convert_class_to_courses(
+                class,
+                &start_date,
+                redrock_response.now_week,
+                &lessons,
+            ) {
+                Ok(converted) => courses.extend(converted),
+                Err(e) => {
+                    let warning = format!("Skipped class '{}': {}", class.course, e);
+                    tracing::warn!("{}", warning);
+                    warnings.push(warning);
+                }
+            }
         }
 
         Ok((courses, redrock_response.now_week))
@@ -361,7 +500,7 @@ impl RedrockProvider {
         student_id: &str,
         semester_start: &DateTime<FixedOffset>,
     ) -> Result<(Vec<Course>, u32)> {
-        let url = format!("{}/magipoke-jwzx/examSchedule", Self::API_ROOT);
+        let url = format!("{}/magipoke-jwzx/examSchedule", self.api_root);
 
         let mut data = HashMap::new();
         data.insert(
@@ -386,10 +525,10 @@ impl RedrockProvider {
                 .custom_error(format!("HTTP {} error", response.status())));
         }
 
-        let exam_response: ExamResponse = response.json().await.map_err(|e| {
-            self.base
-                .custom_error(format!("Failed to parse response: {}", e))
-        })?;
+        let exam_response: ExamResponse = response
+            .json()
+            .await
+            .map_err(|e| self.base.deserialize_error(format!("exam schedule: {}", e)))?;
 
         // 转换考试为Course结构
         let mut exams = Vec::new();
@@ -414,50 +553,84 @@ impl RedrockProvider {
                     .custom_error("Semester start date is required".to_string())
             })?
             .start_date;
+        let lessons = request.options.lesson_times.clone().unwrap_or_default();
         let custom_response = self.get_custom_schedule_data(token).await?;
         let mut courses = Vec::new();
         for custom in &custom_response.data {
-            let custom_courses = self.convert_custom_schedule_to_course(custom, &start_date, 0)?;
+            let custom_courses =
+                self.convert_custom_schedule_to_course(custom, &start_date, 0, &lessons)?;
             courses.extend(custom_courses);
         }
         Ok(courses)
     }
 
     /// 将课程转换为Course结构
-    fn convert_class_to_course(
+    ///
+    /// 跨越午休或晚饭的连堂课拆成多段，每段一个Course。
+    fn convert_class_to_courses(
         &self,
         class: &RedrockClass,
         base_date: &DateTime<FixedOffset>,
         current_week: u32,
+        lessons: &LessonSchedule,
+    ) -> Result<Vec<Course>> {
+        lessons
+            .split_at_breaks(class.begin_lesson, class.period)
+            .into_iter()
+            .map(|(begin_lesson, period)| {
+                self.convert_class_segment(
+                    class,
+                    begin_lesson,
+                    period,
+                    base_date,
+                    current_week,
+                    lessons,
+                )
+            })
+            .collect()
+    }
+
+    /// 将课程中从 `begin_lesson` 起连上 `period` 节的一段转换为Course
+    fn convert_class_segment(
+        &self,
+        class: &RedrockClass,
+        begin_lesson: u32,
+        period: u32,
+        base_date: &DateTime<FixedOffset>,
+        current_week: u32,
+        lessons: &LessonSchedule,
     ) -> Result<Course> {
+        let weeks = class_weeks(class);
         // 计算第一次上课时间（取第一个上课周）
-        let first_week = *class
-            .week
+        let first_week = *weeks
             .first()
             .ok_or_else(|| self.base.custom_error("Course has no week data"))?;
 
         let (start_time, end_time) = self.calculate_class_time(
             first_week,
             class.hash_day + 1,
-            class.begin_lesson,
-            class.period,
+            begin_lesson,
+            period,
             base_date,
+            lessons,
         )?;
 
+        let teachers = split_teachers(&class.teacher);
         Ok(Course {
             name: class.course.clone(),
             code: Some(class.course_num.clone()),
-            teacher: Some(class.teacher.clone()),
+            teacher: join_teachers(&teachers),
+            teachers,
             location: Some(class.classroom.clone()),
             start_time,
             end_time,
             course_type: Some(class.course_type.clone()),
 
             // 提供原始数据供 ICS 模块使用
-            weeks: Some(class.week.clone()),
+            weeks: Some(weeks),
             weekday: Some(class.hash_day + 1), // 转换为1-7格式
-            begin_lesson: Some(class.begin_lesson),
-            lesson_duration: Some(class.period),
+            begin_lesson: Some(begin_lesson),
+            lesson_duration: Some(period),
 
             // 显示相关字段
             raw_week: Some(class.raw_week.clone()),
@@ -480,12 +653,36 @@ impl RedrockProvider {
             &exam.weekday,
             semester_start,
         )?;
-        let end_time = self.parse_exam_time_with_date(
+        let end_time = match self.parse_exam_time_with_date(
             &exam.end_time,
             &exam.week,
             &exam.weekday,
             semester_start,
-        )?;
+        ) {
+            // 结束早于开始且相差不足一天，说明只给出时分的考试跨过了零点
+            Ok(end_time)
+                if end_time < start_time && end_time + chrono::Duration::days(1) > start_time =>
+            {
+                end_time + chrono::Duration::days(1)
+            }
+            Ok(end_time) => end_time,
+            Err(e) => {
+                let duration_minutes = exam
+                    .duration
+                    .as_deref()
+                    .and_then(parse_duration_minutes)
+                    .unwrap_or_else(|| {
+                        tracing::warn!(
+                            "Exam {} has no usable end time or duration ({}), assuming {} minutes",
+                            exam.course,
+                            e,
+                            DEFAULT_EXAM_DURATION_MINUTES
+                        );
+                        DEFAULT_EXAM_DURATION_MINUTES
+                    });
+                start_time + chrono::Duration::minutes(duration_minutes as i64)
+            }
+        };
 
         Ok(Course {
             name: format!("{} (考试)", exam.course),
@@ -497,6 +694,7 @@ impl RedrockProvider {
             // 考试相关字段
             exam_type: Some(exam.exam_type.clone()),
             seat: exam.seat.clone(),
+            seats: exam.seat.as_deref().map(parse_seats).unwrap_or_default(),
             status: Some(exam.status.clone()),
             raw_week: Some(exam.week.clone()),
             ..Default::default()
@@ -508,16 +706,34 @@ impl RedrockProvider {
         custom: &RedrockCustomSchedule,
         base_date: &DateTime<FixedOffset>,
         current_week: u32,
+        lessons: &LessonSchedule,
     ) -> Result<Vec<Course>> {
         let mut courses = Vec::with_capacity(custom.date.len());
         for item in &custom.date {
-            let (start_time, end_time) = self.calculate_class_time(
-                item.week.first().copied().unwrap_or(1),
-                item.day + 1,
-                item.begin_lesson,
-                item.period,
-                base_date,
-            )?;
+            let first_week = item.week.first().copied().unwrap_or(1);
+            let aligned = is_lesson_aligned(item.begin_lesson, item.period);
+            let (start_time, end_time) = if aligned {
+                self.calculate_class_time(
+                    first_week,
+                    item.day + 1,
+                    item.begin_lesson,
+                    item.period,
+                    base_date,
+                    lessons,
+                )?
+            } else {
+                // 不在课表节次内：按 `time`（当天分钟数）计算，没有时间时视为全天
+                let date = self.class_date(first_week, item.day + 1, base_date);
+                if custom.time == 0 || custom.time >= MINUTES_PER_DAY {
+                    (date, date + chrono::Duration::days(1))
+                } else {
+                    let start = date + chrono::Duration::minutes(custom.time as i64);
+                    (
+                        start,
+                        start + chrono::Duration::minutes(DEFAULT_CUSTOM_DURATION_MINUTES),
+                    )
+                }
+            };
             courses.push(Course {
                 name: custom.title.clone(),
                 code: Some(custom.id.to_string()),
@@ -528,16 +744,28 @@ impl RedrockProvider {
 
                 // 提供原始数据供 ICS 模块使用
                 weeks: Some(item.week.clone()),
-                weekday: Some(item.day),
-                begin_lesson: Some(item.begin_lesson),
-                lesson_duration: Some(item.period),
+                weekday: Some(item.day + 1), // 转换为1-7格式
+                begin_lesson: aligned.then_some(item.begin_lesson),
+                lesson_duration: aligned.then_some(item.period),
                 current_week: Some(current_week),
+                all_day: !aligned && end_time - start_time == chrono::Duration::days(1),
 
                 ..Default::default()
             });
         }
         Ok(courses)
     }
+
+    /// 计算指定周次、星期当天零点的时间
+    fn class_date(
+        &self,
+        week_num: u32,
+        weekday: u32,
+        base_date: &DateTime<FixedOffset>,
+    ) -> DateTime<FixedOffset> {
+        crate::types::week_day_date(base_date, week_num, weekday)
+    }
+
     /// 计算课程的具体上课时间
     fn calculate_class_time(
         &self,
@@ -546,30 +774,21 @@ impl RedrockProvider {
         begin_lesson: u32,
         period: u32,
         base_date: &DateTime<FixedOffset>,
+        lessons: &LessonSchedule,
     ) -> Result<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
         // 直接使用DateTime<FixedOffset>计算日期
-        let days_since_monday = base_date.weekday().num_days_from_monday();
-        let semester_start_monday = if base_date.weekday() != chrono::Weekday::Mon {
-            *base_date - chrono::Duration::days(days_since_monday as i64)
-        } else {
-            *base_date
-        };
-        let target_week_monday =
-            semester_start_monday + chrono::Duration::weeks((week_num - 1) as i64);
-        let class_date_base = target_week_monday + chrono::Duration::days((weekday - 1) as i64);
+        let class_date_base = self.class_date(week_num, weekday, base_date);
 
-        if begin_lesson == 0 || begin_lesson > LESSON_TIMES.len() as u32 {
+        let Some((start_minutes, _)) = lessons.get(begin_lesson) else {
             return Err(self
                 .base
                 .custom_error(format!("Invalid lesson number: {}", begin_lesson)));
-        }
+        };
 
-        let start_minutes = LESSON_TIMES[(begin_lesson - 1) as usize].0;
         let end_lesson = begin_lesson + period - 1;
-        let end_minutes = if end_lesson <= LESSON_TIMES.len() as u32 {
-            LESSON_TIMES[(end_lesson - 1) as usize].1
-        } else {
-            start_minutes + (period * 45) as usize // 每节课45分钟
+        let end_minutes = match lessons.get(end_lesson) {
+            Some((_, end)) => end,
+            None => start_minutes + period * 45, // 每节课45分钟
         };
 
         // 直接在DateTime<FixedOffset>基础上加时间
@@ -625,10 +844,7 @@ impl RedrockProvider {
         })?;
 
         // 使用实际的学期开始日期来计算考试日期
-        let days_since_monday = semester_start.weekday().num_days_from_monday();
-        let monday = *semester_start - chrono::Duration::days(days_since_monday as i64);
-        let target_week_monday = monday + chrono::Duration::weeks((week_num - 1) as i64);
-        let exam_date_base = target_week_monday + chrono::Duration::days((weekday - 1) as i64);
+        let exam_date_base = crate::types::week_day_date(semester_start, week_num, weekday);
 
         // 直接在现有日期时间基础上设置时分秒
         let dt = exam_date_base
@@ -658,6 +874,16 @@ impl RedrockProvider {
     }
 }
 
+/// 解析考试时长字符串，例如 "120分钟"、"90"
+fn parse_duration_minutes(duration: &str) -> Option<u32> {
+    let digits = duration
+        .trim()
+        .trim_end_matches("分钟")
+        .trim_end_matches("min")
+        .trim();
+    digits.parse().ok().filter(|minutes| *minutes > 0)
+}
+
 #[async_trait]
 impl Provider for RedrockProvider {
     type Token = RedrockToken;
@@ -670,10 +896,6 @@ impl Provider for RedrockProvider {
         &self.base.info.description
     }
 
-    fn timezone(&self) -> FixedOffset {
-        FixedOffset::east_opt(8 * 3600).unwrap()
-    }
-
     async fn authenticate<'a>(
         &'a self,
         _context: ParamContext<'_, Self::ContextType>,
@@ -685,31 +907,34 @@ impl Provider for RedrockProvider {
         );
         let credentials = &request.credentials;
         tracing::info!("Authenticating user: {}", credentials.username);
-        let url = format!("{}/magipoke/token", Self::API_ROOT);
+        let url = format!("{}/magipoke/token", self.api_root);
         let mut data = HashMap::new();
-        data.insert("stuNum", credentials.username.clone());
-        data.insert("idNum", credentials.password.clone());
+        data.insert("stuNum", credentials.username.as_str());
+        data.insert("idNum", credentials.password.expose_secret());
         let response = self
             .base
             .client
             .post(&url)
-            .header("Host", Self::API_ROOT.trim_start_matches("https://"))
+            .header("Host", &self.api_host)
             .json(&data)
             .send()
             .await
             .map_err(|e| self.base.handle_error_req(e))?;
         if response.status() == StatusCode::BAD_REQUEST {
-            return Err(crate::Error::Authentication("密码错误".to_string()));
+            return Err(Error::authentication(
+                AuthFailReason::BadCredentials,
+                "密码错误",
+            ));
         }
         if response.status() != reqwest::StatusCode::OK {
             return Err(self
                 .base
                 .custom_error(format!("HTTP {} error", response.status())));
         }
-        response.json().await.map_err(|e| {
-            self.base
-                .custom_error(format!("Failed to parse response: {}", e))
-        })
+        response
+            .json()
+            .await
+            .map_err(|e| self.base.deserialize_error(format!("token: {}", e)))
     }
 
     async fn validate_token(&self, token: &Self::Token) -> Result<bool> {
@@ -742,12 +967,23 @@ impl Provider for RedrockProvider {
             }
         };
 
-        if redrock_response.now_week == 0 {
-            // 如果now_week为0，尝试从version字段解析学期开始时间
-            self.parse_semester_start_from_version(&redrock_response.version)
-        } else {
-            // 使用now_week计算学期开始时间
-            self.get_semester_start_from_now_week(redrock_response.now_week)
+        // 优先使用now_week计算学期开始时间，失败时尝试从version字段解析
+        let detected = self
+            .get_semester_start_from_now_week(redrock_response.now_week)
+            .or_else(|_| self.parse_semester_start_from_version(&redrock_response.version));
+
+        match detected {
+            Ok(start) => Ok(start),
+            Err(e) if self.semester_fallback => {
+                let start = SemesterDetector::create_current_semester().start_date;
+                tracing::warn!(
+                    "Failed to detect semester start from redrock ({}), falling back to {}",
+                    e,
+                    start.format("%Y-%m-%d")
+                );
+                Ok(start)
+            }
+            Err(e) => Err(e),
         }
     }
 
@@ -760,7 +996,10 @@ impl Provider for RedrockProvider {
         let ctx = context.ensure_valid()?;
         // 验证token
         if !self.validate_token(token).await? {
-            return Err(self.base.custom_error("Invalid or expired token"));
+            return Err(Error::authentication(
+                AuthFailReason::Expired,
+                "Invalid or expired token",
+            ));
         }
 
         tracing::info!(
@@ -768,31 +1007,43 @@ impl Provider for RedrockProvider {
             request.credentials.username
         );
 
-        let (courses, current_week) =
-            self.get_class_schedule(ctx, request, token)
+        let mut warnings = Vec::new();
+        let (courses, current_week) = if request.options.include_classes {
+            self.get_class_schedule(ctx, request, token, &mut warnings)
                 .await
                 .map_err(|e| {
                     tracing::error!("Failed to get class schedule: {}", e);
                     e
-                })?;
+                })?
+        } else {
+            (Vec::new(), 0)
+        };
 
         let semester_start = &request.semester.as_ref().unwrap().start_date;
-        let (exams, _) = self
-            .get_exam_schedule(&request.credentials.username, semester_start)
-            .await
-            .map_err(|e| {
-                tracing::warn!("Failed to get exam schedule: {}", e);
-                e
-            })
-            .unwrap_or_else(|_| (Vec::new(), 0));
+        let exams = if request.options.include_exams {
+            self.get_exam_schedule(&request.credentials.username, semester_start)
+                .await
+                .map(|(exams, _)| exams)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to get exam schedule: {}", e);
+                    warnings.push(format!("Failed to get exam schedule: {}", e));
+                    Vec::new()
+                })
+        } else {
+            Vec::new()
+        };
 
-        let custom_courses = self
-            .get_custom_schedule(request, token)
-            .await
-            .unwrap_or_else(|e| {
-                tracing::warn!("Failed to get custom schedule: {}", e);
-                Vec::new()
-            });
+        let custom_courses = if request.options.include_custom {
+            self.get_custom_schedule(request, token)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to get custom schedule: {}", e);
+                    warnings.push(format!("Failed to get custom schedule: {}", e));
+                    Vec::new()
+                })
+        } else {
+            Vec::new()
+        };
         // 合并课程和考试
         let mut all_courses = courses;
         all_courses.extend(exams);
@@ -808,12 +1059,13 @@ impl Provider for RedrockProvider {
             courses: all_courses,
             semester: request.semester.clone().unwrap(),
             generated_at: Utc::now().with_timezone(&self.timezone()),
+            warnings,
         })
     }
 
     async fn refresh_token(&self, token: &Self::Token) -> Result<Self::Token> {
         tracing::info!("Refreshing token for redrock");
-        let url = format!("{}/magipoke/token/refresh", Self::API_ROOT);
+        let url = format!("{}/magipoke/token/refresh", self.api_root);
 
         let mut data = HashMap::new();
         data.insert("refreshToken", &token.data.refresh_token);
@@ -822,7 +1074,7 @@ impl Provider for RedrockProvider {
             .base
             .client
             .post(&url)
-            .header("Host", Self::API_ROOT.trim_start_matches("https://"))
+            .header("Host", &self.api_host)
             .header("Accept", "*/*")
             .header("Connection", "keep-alive")
             .bearer_auth(&token.data.token)
@@ -839,10 +1091,10 @@ impl Provider for RedrockProvider {
             )));
         }
 
-        let refreshed_token: RedrockToken = response.json().await.map_err(|e| {
-            self.base
-                .custom_error(format!("Failed to parse refresh token response: {}", e))
-        })?;
+        let refreshed_token: RedrockToken = response
+            .json()
+            .await
+            .map_err(|e| self.base.deserialize_error(format!("refresh token: {}", e)))?;
 
         // 验证刷新后的token状态
         if self.validate_token(&refreshed_token).await? {
@@ -857,4 +1109,759 @@ impl Provider for RedrockProvider {
     fn token_ttl(&self) -> std::time::Duration {
         std::time::Duration::from_secs(3600 * 24 * 3)
     }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            recurrence_native: true,
+        }
+    }
+
+    fn api_root(&self) -> Option<&str> {
+        Some(&self.api_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redrock_response(now_week: u32, version: &str) -> RedrockResponse {
+        RedrockResponse {
+            data: Vec::new(),
+            info: "success".to_string(),
+            now_week,
+            status: 200,
+            stu_num: "2023000000".to_string(),
+            version: version.to_string(),
+        }
+    }
+
+    fn request() -> CourseRequest {
+        CourseRequest {
+            credentials: crate::Credentials {
+                username: "2023000000".to_string(),
+                password: "000000".into(),
+                extra: HashMap::new(),
+            },
+            semester: None,
+            timeout: None,
+            options: Default::default(),
+        }
+    }
+
+    fn token() -> RedrockToken {
+        RedrockToken {
+            data: RedrockTokenData {
+                refresh_token: String::new(),
+                token: String::new(),
+            },
+            info: String::new(),
+            status: 10000,
+        }
+    }
+
+    #[tokio::test]
+    async fn semester_start_falls_back_to_detector() {
+        let provider = RedrockProvider::new();
+        let mut ctx = Context::new(redrock_response(0, "not-a-version"));
+
+        let start = provider
+            .get_semester_start(ctx.as_param(), &mut request(), &token())
+            .await
+            .unwrap();
+
+        assert_eq!(start.weekday(), chrono::Weekday::Mon);
+        assert_eq!(start.offset(), &provider.timezone());
+        let now = Utc::now().with_timezone(&provider.timezone());
+        assert!(start <= now + chrono::Duration::weeks(1));
+        assert!(now - start < chrono::Duration::weeks(26));
+    }
+
+    #[tokio::test]
+    async fn semester_start_strict_mode_errors() {
+        let provider = RedrockProvider::new().with_semester_fallback(false);
+        let mut ctx = Context::new(redrock_response(0, "not-a-version"));
+
+        let result = provider
+            .get_semester_start(ctx.as_param(), &mut request(), &token())
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn semester_start_prefers_version_when_now_week_is_zero() {
+        let provider = RedrockProvider::new().with_semester_fallback(false);
+        let mut ctx = Context::new(redrock_response(0, "2025.9.8"));
+
+        let start = provider
+            .get_semester_start(ctx.as_param(), &mut request(), &token())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            start.date_naive(),
+            chrono::NaiveDate::from_ymd_opt(2025, 9, 8).unwrap()
+        );
+    }
+
+    fn exam(begin_time: &str, end_time: &str, duration: Option<&str>) -> RedrockExam {
+        RedrockExam {
+            course: "线性代数".to_string(),
+            begin_time: begin_time.to_string(),
+            end_time: end_time.to_string(),
+            duration: duration.map(str::to_string),
+            status: "正常".to_string(),
+            classroom: "4101".to_string(),
+            exam_type: "期末".to_string(),
+            week: "18".to_string(),
+            weekday: "4".to_string(),
+            seat: None,
+        }
+    }
+
+    #[test]
+    fn exam_end_time_falls_back_to_duration() {
+        let provider = RedrockProvider::new();
+        let semester_start = provider
+            .timezone()
+            .with_ymd_and_hms(2025, 9, 8, 0, 0, 0)
+            .unwrap();
+
+        let course = provider
+            .convert_exam_to_course(&exam("14:00", "14:00", None), &semester_start)
+            .unwrap();
+        assert_eq!(course.end_time, course.start_time);
+
+        let course = provider
+            .convert_exam_to_course(&exam("14:00", "", Some("90分钟")), &semester_start)
+            .unwrap();
+        assert_eq!(
+            course.start_time,
+            provider
+                .timezone()
+                .with_ymd_and_hms(2026, 1, 8, 14, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            course.end_time - course.start_time,
+            chrono::Duration::minutes(90)
+        );
+
+        let course = provider
+            .convert_exam_to_course(&exam("14:00", "", None), &semester_start)
+            .unwrap();
+        assert_eq!(
+            course.end_time - course.start_time,
+            chrono::Duration::minutes(DEFAULT_EXAM_DURATION_MINUTES as i64)
+        );
+    }
+
+    #[test]
+    fn exam_crossing_midnight_ends_next_day() {
+        let provider = RedrockProvider::new();
+        let tz = provider.timezone();
+        let semester_start = tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap();
+
+        let course = provider
+            .convert_exam_to_course(&exam("23:30", "00:30", None), &semester_start)
+            .unwrap();
+        assert_eq!(
+            course.start_time,
+            tz.with_ymd_and_hms(2026, 1, 8, 23, 30, 0).unwrap()
+        );
+        assert_eq!(
+            course.end_time,
+            tz.with_ymd_and_hms(2026, 1, 9, 0, 30, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn custom_schedule_outside_lesson_grid() {
+        let provider = RedrockProvider::new();
+        let semester_start = provider
+            .timezone()
+            .with_ymd_and_hms(2025, 9, 8, 0, 0, 0)
+            .unwrap();
+        let at = |day: u32, hour: u32, minute: u32| {
+            provider
+                .timezone()
+                .with_ymd_and_hms(2025, 9, day, hour, minute, 0)
+                .unwrap()
+        };
+        let custom = |time: u32, begin_lesson: u32, period: u32| RedrockCustomSchedule {
+            id: 1,
+            time,
+            title: "社团活动".to_string(),
+            content: "招新宣讲".to_string(),
+            date: vec![RedrockCustomScheduleDate {
+                begin_lesson,
+                period,
+                day: 2,
+                week: vec![1, 2],
+            }],
+        };
+        let convert = |custom: &RedrockCustomSchedule| {
+            provider
+                .convert_custom_schedule_to_course(
+                    custom,
+                    &semester_start,
+                    1,
+                    &LessonSchedule::default(),
+                )
+                .unwrap()
+                .remove(0)
+        };
+
+        // 对齐节次时沿用课表时间
+        let aligned = convert(&custom(0, 9, 2));
+        assert_eq!(aligned.start_time, at(10, 19, 0));
+        assert_eq!(aligned.end_time, at(10, 20, 40));
+        assert_eq!(aligned.begin_lesson, Some(9));
+        assert!(!aligned.all_day);
+
+        // 超出课表的节次按 time 计算
+        let timed = convert(&custom(22 * 60 + 40, 13, 2));
+        assert_eq!(timed.start_time, at(10, 22, 40));
+        assert_eq!(
+            timed.duration(),
+            chrono::Duration::minutes(DEFAULT_CUSTOM_DURATION_MINUTES)
+        );
+        assert_eq!(timed.begin_lesson, None);
+        assert!(!timed.all_day);
+
+        // 没有节次与时间时为全天事件
+        let all_day = convert(&custom(0, 0, 0));
+        assert!(all_day.all_day);
+        assert_eq!(all_day.start_time, at(10, 0, 0));
+        assert_eq!(all_day.end_time, at(11, 0, 0));
+        assert_eq!(all_day.weeks, Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn weeks_from_week_model() {
+        let odd: Vec<u32> = (1..=16).step_by(2).collect();
+        let even: Vec<u32> = (2..=16).step_by(2).collect();
+        let all: Vec<u32> = (1..=16).collect();
+
+        assert_eq!(weeks_from_model(1, 16, "单"), odd);
+        assert_eq!(weeks_from_model(1, 16, "single"), odd);
+        assert_eq!(weeks_from_model(1, 16, "双"), even);
+        assert_eq!(weeks_from_model(1, 16, "double"), even);
+        assert_eq!(weeks_from_model(1, 16, "全"), all);
+        assert_eq!(weeks_from_model(1, 16, "all"), all);
+        assert!(weeks_from_model(5, 4, "全").is_empty());
+    }
+
+    #[tokio::test]
+    async fn malformed_class_is_skipped() {
+        let provider = RedrockProvider::new();
+        let class = |course: &str, begin_lesson: u32| RedrockClass {
+            course: course.to_string(),
+            begin_lesson,
+            period: 2,
+            week: vec![1, 2],
+            ..Default::default()
+        };
+        let mut ctx = Context::new(RedrockResponse {
+            data: vec![
+                class("高等数学", 1),
+                class("坏数据", 0),
+                class("大学英语", 3),
+            ],
+            ..redrock_response(1, "2025.9.8")
+        });
+        let mut request = CourseRequest {
+            semester: Some(crate::Semester::from_date_str("2025-09-08").unwrap()),
+            ..request()
+        };
+
+        let mut warnings = Vec::new();
+        let (courses, _) = provider
+            .get_class_schedule(&mut ctx, &mut request, &token(), &mut warnings)
+            .await
+            .unwrap();
+        let names: Vec<_> = courses.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["高等数学", "大学英语"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("坏数据"));
+    }
+
+    #[test]
+    fn weeks_fall_back_to_begin_end() {
+        let class = |week: Vec<u32>, week_model: &str| RedrockClass {
+            week,
+            week_model: week_model.to_string(),
+            week_begin: 3,
+            week_end: 8,
+            ..Default::default()
+        };
+
+        assert_eq!(class_weeks(&class(vec![1, 2], "all")), vec![1, 2]);
+        assert_eq!(class_weeks(&class(vec![], "all")), vec![3, 4, 5, 6, 7, 8]);
+        assert_eq!(class_weeks(&class(vec![], "single")), vec![3, 5, 7]);
+        assert_eq!(class_weeks(&class(vec![], "double")), vec![4, 6, 8]);
+        assert!(class_weeks(&RedrockClass::default()).is_empty());
+
+        let provider = RedrockProvider::new();
+        let semester_start = provider
+            .timezone()
+            .with_ymd_and_hms(2025, 9, 8, 0, 0, 0)
+            .unwrap();
+        let courses = provider
+            .convert_class_to_courses(
+                &RedrockClass {
+                    begin_lesson: 1,
+                    period: 2,
+                    ..class(vec![], "double")
+                },
+                &semester_start,
+                1,
+                &LessonSchedule::default(),
+            )
+            .unwrap();
+        assert_eq!(courses.len(), 1);
+        assert_eq!(courses[0].weeks, Some(vec![4, 6, 8]));
+        assert_eq!(
+            courses[0].start_time,
+            provider
+                .timezone()
+                .with_ymd_and_hms(2025, 9, 29, 8, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn class_spanning_lunch_is_split() {
+        let provider = RedrockProvider::new();
+        let tz = provider.timezone();
+        let semester_start = tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap();
+        let courses = provider
+            .convert_class_to_courses(
+                &RedrockClass {
+                    course: "数学实验".to_string(),
+                    week: vec![1],
+                    begin_lesson: 4,
+                    period: 2,
+                    ..Default::default()
+                },
+                &semester_start,
+                1,
+                &LessonSchedule::default(),
+            )
+            .unwrap();
+
+        let times: Vec<_> = courses
+            .iter()
+            .map(|course| (course.begin_lesson, course.start_time, course.end_time))
+            .collect();
+        assert_eq!(
+            times,
+            vec![
+                (
+                    Some(4),
+                    tz.with_ymd_and_hms(2025, 9, 8, 11, 15, 0).unwrap(),
+                    tz.with_ymd_and_hms(2025, 9, 8, 11, 55, 0).unwrap(),
+                ),
+                (
+                    Some(5),
+                    tz.with_ymd_and_hms(2025, 9, 8, 14, 0, 0).unwrap(),
+                    tz.with_ymd_and_hms(2025, 9, 8, 14, 45, 0).unwrap(),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn duration_minutes_parsing() {
+        assert_eq!(parse_duration_minutes("120分钟"), Some(120));
+        assert_eq!(parse_duration_minutes(" 90 "), Some(90));
+        assert_eq!(parse_duration_minutes("0"), None);
+        assert_eq!(parse_duration_minutes("两小时"), None);
+    }
+
+    #[test]
+    fn server_error_is_curfew_only_at_night() {
+        let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let provider = RedrockProvider::new();
+        let error = StatusCode::INTERNAL_SERVER_ERROR;
+
+        assert!(matches!(
+            provider.schedule_status_error(error, at(2)),
+            Error::CurfewTime(())
+        ));
+        assert!(matches!(
+            provider.schedule_status_error(error, at(23)),
+            Error::CurfewTime(())
+        ));
+        assert!(matches!(
+            provider.schedule_status_error(error, at(12)),
+            Error::Provider { .. }
+        ));
+        assert!(matches!(
+            provider.schedule_status_error(StatusCode::BAD_GATEWAY, at(2)),
+            Error::Provider { .. }
+        ));
+
+        let window: CurfewWindow = "12:00-13:00".parse().unwrap();
+        assert!(window.contains(at(12)));
+        assert!(!window.contains(at(13)));
+        let provider = provider.with_curfew_window(window);
+        assert!(matches!(
+            provider.schedule_status_error(error, at(12)),
+            Error::CurfewTime(())
+        ));
+        assert!("23:00".parse::<CurfewWindow>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod http_tests {
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use serde_json::json;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{body_partial_json, body_string_contains, header, method, path},
+    };
+
+    use super::*;
+    use crate::{
+        Credentials,
+        cache::{CacheManager, MemoryCache},
+        providers::{ProviderWrapper, Wrapper},
+    };
+
+    const STUDENT_ID: &str = "2023000000";
+
+    /// 构造一个一小时后过期的 JWT
+    fn jwt() -> String {
+        let payload = json!({ "exp": Utc::now().timestamp() + 3600 }).to_string();
+        format!(
+            "{}.{}.sig",
+            URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#),
+            URL_SAFE_NO_PAD.encode(payload)
+        )
+    }
+
+    fn request() -> CourseRequest {
+        CourseRequest {
+            credentials: Credentials {
+                username: STUDENT_ID.to_string(),
+                password: "000000".into(),
+                extra: HashMap::new(),
+            },
+            semester: None,
+            timeout: None,
+            options: Default::default(),
+        }
+    }
+
+    async fn mount_token(server: &MockServer, token: &str) {
+        Mock::given(method("POST"))
+            .and(path("/magipoke/token"))
+            .and(body_partial_json(
+                json!({ "stuNum": STUDENT_ID, "idNum": "000000" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "refreshToken": "refresh", "token": token },
+                "info": "success",
+                "status": 10000
+            })))
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_schedule(server: &MockServer, token: &str) {
+        let bearer = format!("Bearer {}", token);
+        Mock::given(method("POST"))
+            .and(path("/magipoke-jwzx/kebiao"))
+            .and(header("authorization", bearer.as_str()))
+            .and(body_string_contains(format!("stu_num={}", STUDENT_ID)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "hash_day": 0,
+                    "hash_lesson": 0,
+                    "course": "高等数学A(上)",
+                    "teacher": "张老师",
+                    "course_num": "A1100010",
+                    "type": "必修",
+                    "classroom": "2101",
+                    "day": "星期一",
+                    "lesson": "一二节",
+                    "rawWeek": "1-16周",
+                    "weekModel": "all",
+                    "period": 2,
+                    "week": (1..=16).collect::<Vec<u32>>(),
+                    "begin_lesson": 1,
+                    "week_begin": 1,
+                    "week_end": 16
+                }],
+                "info": "success",
+                "nowWeek": 0,
+                "status": 200,
+                "stuNum": STUDENT_ID,
+                "version": "2025.9.8"
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/magipoke-jwzx/examSchedule"))
+            .and(body_string_contains(format!("stuNum={}", STUDENT_ID)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "course": "线性代数",
+                    "begin_time": "14:00",
+                    "end_time": "16:00",
+                    "status": "正常",
+                    "classroom": "4101",
+                    "type": "期末",
+                    "week": "18",
+                    "weekday": "4",
+                    "seat": "12"
+                }],
+                "nowWeek": 0
+            })))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/magipoke-reminder/Person/getTransaction"))
+            .and(header("authorization", bearer.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": 200,
+                "data": [{
+                    "id": 7,
+                    "time": 0,
+                    "title": "社团活动",
+                    "content": "招新宣讲",
+                    "date": [{ "begin_lesson": 9, "period": 2, "day": 5, "week": [3] }]
+                }]
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn fetches_classes_exams_and_custom_schedules() {
+        let server = MockServer::start().await;
+        let jwt = jwt();
+        mount_token(&server, &jwt).await;
+        mount_schedule(&server, &jwt).await;
+
+        let provider = RedrockProvider::new().with_api_root(&server.uri()).unwrap();
+        let mut request = request();
+        let mut ctx = Context::default();
+
+        let token = provider.authenticate(None, &request).await.unwrap();
+        assert_eq!(token.data.token, jwt);
+        let start = provider
+            .get_semester_start(ctx.as_param(), &mut request, &token)
+            .await
+            .unwrap();
+        request.semester = Some(crate::Semester {
+            start_date: start,
+            weeks_total: None,
+        });
+        let response = provider
+            .get_courses(ctx.as_param(), &mut request, &token)
+            .await
+            .unwrap();
+
+        let tz = provider.timezone();
+        assert_eq!(start, tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap());
+        assert_eq!(response.courses.len(), 3);
+
+        let class = &response.courses[0];
+        assert_eq!(class.name, "高等数学A(上)");
+        assert_eq!(class.teacher.as_deref(), Some("张老师"));
+        assert_eq!(class.weeks.as_ref().map(Vec::len), Some(16));
+        assert_eq!(
+            class.start_time,
+            tz.with_ymd_and_hms(2025, 9, 8, 8, 0, 0).unwrap()
+        );
+        assert_eq!(
+            class.end_time,
+            tz.with_ymd_and_hms(2025, 9, 8, 9, 40, 0).unwrap()
+        );
+
+        let exam = &response.courses[1];
+        assert_eq!(exam.name, "线性代数 (考试)");
+        assert_eq!(exam.course_type.as_deref(), Some("考试"));
+        assert_eq!(exam.seat.as_deref(), Some("12"));
+        assert_eq!(
+            exam.start_time,
+            tz.with_ymd_and_hms(2026, 1, 8, 14, 0, 0).unwrap()
+        );
+        assert_eq!(
+            exam.end_time,
+            tz.with_ymd_and_hms(2026, 1, 8, 16, 0, 0).unwrap()
+        );
+
+        let custom = &response.courses[2];
+        assert_eq!(custom.name, "社团活动");
+        assert_eq!(custom.code.as_deref(), Some("7"));
+        assert_eq!(custom.note.as_deref(), Some("自定义日程: 招新宣讲"));
+        assert_eq!(
+            custom.start_time,
+            tz.with_ymd_and_hms(2025, 9, 27, 19, 0, 0).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn kebiao_is_fetched_once_per_generation() {
+        let server = MockServer::start().await;
+        let jwt = jwt();
+        mount_token(&server, &jwt).await;
+        mount_schedule(&server, &jwt).await;
+
+        let wrapper = Wrapper::new(
+            RedrockProvider::new().with_api_root(&server.uri()).unwrap(),
+            CacheManager::new(MemoryCache::default()),
+        );
+        let kebiao_hits = || async {
+            server
+                .received_requests()
+                .await
+                .unwrap()
+                .iter()
+                .filter(|r| r.url.path().ends_with("/kebiao"))
+                .count()
+        };
+
+        // 未指定学期：推算学期开始与转换课程共用同一次请求
+        let response = wrapper.get_courses(&mut request()).await.unwrap();
+        assert_eq!(response.courses.len(), 3);
+        assert_eq!(kebiao_hits().await, 1);
+
+        // 指定学期时同样只请求一次
+        let mut request = CourseRequest {
+            semester: Some(crate::Semester::from_date_str("2025-09-08").unwrap()),
+            ..request()
+        };
+        wrapper.get_courses(&mut request).await.unwrap();
+        assert_eq!(kebiao_hits().await, 2);
+    }
+
+    #[tokio::test]
+    async fn disabled_exams_skip_exam_endpoint() {
+        let server = MockServer::start().await;
+        let jwt = jwt();
+        mount_token(&server, &jwt).await;
+        mount_schedule(&server, &jwt).await;
+
+        let provider = RedrockProvider::new().with_api_root(&server.uri()).unwrap();
+        let mut request = CourseRequest {
+            semester: Some(crate::Semester::from_date_str("2025-09-08").unwrap()),
+            options: ProviderOptions {
+                include_exams: false,
+                ..Default::default()
+            },
+            ..request()
+        };
+        let mut ctx = Context::default();
+        let token = provider.authenticate(None, &request).await.unwrap();
+        let response = provider
+            .get_courses(ctx.as_param(), &mut request, &token)
+            .await
+            .unwrap();
+
+        assert_eq!(response.courses.len(), 2);
+        assert!(response.courses.iter().all(|c| c.exam_type.is_none()));
+        let paths = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.url.path().to_string())
+            .collect::<Vec<_>>();
+        assert!(!paths.iter().any(|p| p.ends_with("/examSchedule")));
+        assert!(paths.iter().any(|p| p.ends_with("/getTransaction")));
+    }
+
+    #[tokio::test]
+    async fn disabled_classes_skip_schedule_endpoint() {
+        let server = MockServer::start().await;
+        let jwt = jwt();
+        mount_token(&server, &jwt).await;
+
+        let provider = RedrockProvider::new().with_api_root(&server.uri()).unwrap();
+        let mut request = CourseRequest {
+            semester: Some(crate::Semester::from_date_str("2025-09-08").unwrap()),
+            options: ProviderOptions {
+                include_classes: false,
+                include_custom: false,
+                ..Default::default()
+            },
+            ..request()
+        };
+        let mut ctx = Context::default();
+        let token = provider.authenticate(None, &request).await.unwrap();
+        let response = provider
+            .get_courses(ctx.as_param(), &mut request, &token)
+            .await
+            .unwrap();
+
+        assert!(response.courses.is_empty());
+        let paths = server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.url.path().to_string())
+            .collect::<Vec<_>>();
+        assert!(!paths.iter().any(|p| p.ends_with("/kebiao")));
+    }
+
+    #[tokio::test]
+    async fn rejected_password_is_authentication_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/magipoke/token"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+
+        let provider = RedrockProvider::new().with_api_root(&server.uri()).unwrap();
+        let result = provider.authenticate(None, &request()).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Authentication {
+                reason: AuthFailReason::BadCredentials,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn schedule_server_error_is_curfew() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/magipoke-jwzx/kebiao"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&server)
+            .await;
+
+        // 以当前时刻为中心设置宵禁时段，使结果不依赖测试运行的时间
+        let now = Utc::now().with_timezone(&CQUPT_TZ).time();
+        let hour = chrono::Duration::hours(1);
+        let provider = RedrockProvider::new()
+            .with_api_root(&server.uri())
+            .unwrap()
+            .with_curfew_window(CurfewWindow::new(now - hour, now + hour));
+        let token = RedrockToken {
+            data: RedrockTokenData {
+                refresh_token: String::new(),
+                token: jwt(),
+            },
+            info: String::new(),
+            status: 10000,
+        };
+        let result = provider
+            .get_semester_start(Context::default().as_param(), &mut request(), &token)
+            .await;
+
+        assert!(matches!(result, Err(Error::CurfewTime(()))));
+    }
 }
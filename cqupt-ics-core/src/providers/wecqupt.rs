@@ -1,17 +1,20 @@
 use crate::{
-    Course, CourseRequest, CourseResponse, Result,
+    AuthFailReason, Course, CourseRequest, CourseResponse, LessonSchedule, Result, join_teachers,
+    parse_seats,
     providers::{
-        BaseProvider, BaseProviderBuilder, ParamContext, ParamContextExt, Provider, ProviderInfo,
+        BaseProvider, BaseProviderBuilder, ParamContext, ParamContextExt, Provider,
+        ProviderCapabilities, ProviderInfo, parse_api_root,
     },
+    split_teachers,
 };
 use async_trait::async_trait;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64_STANDARD};
-use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveTime, TimeZone, Timelike, Utc};
 use reqwest::{StatusCode, Url, header};
 use rsa::{Pkcs1v15Encrypt, RsaPublicKey, pkcs8::DecodePublicKey as _, rand_core::OsRng};
 use serde::{Deserialize, Deserializer, Serialize};
 use std::{
-    collections::{BTreeSet, HashMap, hash_map::Entry},
+    collections::{BTreeSet, HashMap, HashSet, hash_map::Entry},
     hash::{self, Hash},
     ops::{Deref, DerefMut},
 };
@@ -19,6 +22,8 @@ use std::{
 const API_ROOT: &str = "https://we.cqupt.edu.cn/";
 const SCHEDULE_TYPES: &str = "[1,3,4]";
 const SCHEDULE_FETCH_WEEKS: i64 = 25;
+/// 默认每次请求的周数，避免服务端截断过长的时间段
+const DEFAULT_SCHEDULE_WINDOW_WEEKS: i64 = 8;
 const PUBLIC_KEY: &str = concat!(
     "-----BEGIN PUBLIC KEY-----\n",
     "MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAr9lk2DkxZdoK4KqKNJRW\n",
@@ -94,6 +99,8 @@ struct WecquptScheduleItemData {
     lecturer: Option<String>,
     chief_invigilator: Option<String>,
     deputy_invigilators: Option<Vec<String>>,
+    #[serde(default, alias = "credits", deserialize_with = "de_credit")]
+    credit: Option<f32>,
 }
 
 #[allow(dead_code)]
@@ -128,6 +135,25 @@ where
     NaiveDate::parse_from_str(&s, "%Y-%m-%d").map_err(serde::de::Error::custom)
 }
 
+/// 学分可能是数字或字符串（如 `"3.5"`），无法解析时视为没有学分
+fn de_credit<'de, D>(de: D) -> std::result::Result<Option<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Credit {
+        N(f32),
+        S(String),
+        Other(serde::de::IgnoredAny),
+    }
+    Ok(match Option::<Credit>::deserialize(de)? {
+        Some(Credit::N(n)) => Some(n),
+        Some(Credit::S(s)) => s.trim().parse().ok(),
+        Some(Credit::Other(_)) | None => None,
+    })
+}
+
 impl PartialEq for WecquptScheduleItem {
     fn eq(&self, other: &Self) -> bool {
         if !(self.type_id == other.type_id
@@ -221,6 +247,41 @@ impl WecquptSchedules {
     }
 }
 
+/// 将 `total_weeks` 周按 `window_weeks` 切分为若干 [开始, 结束] 日期区间
+fn schedule_windows(
+    start: NaiveDate,
+    total_weeks: i64,
+    window_weeks: i64,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let window_weeks = window_weeks.max(1);
+    (0..total_weeks)
+        .step_by(window_weeks as usize)
+        .map(|offset| {
+            let weeks = window_weeks.min(total_weeks - offset);
+            let begin = start + chrono::Duration::weeks(offset);
+            (
+                begin,
+                begin + chrono::Duration::weeks(weeks) - chrono::Duration::days(1),
+            )
+        })
+        .collect()
+}
+
+/// 合并各时间段的日程，窗口边界重复返回的同一日程只保留一次
+fn merge_schedule_windows(
+    windows: impl IntoIterator<Item = Vec<WecquptScheduleItem>>,
+) -> Vec<WecquptScheduleItem> {
+    let mut merged: Vec<WecquptScheduleItem> = Vec::new();
+    let mut seen = HashSet::new();
+    for item in windows.into_iter().flatten() {
+        // 同一课程每周的 Eq 相同，需再按日期区分
+        if seen.insert((item.clone(), item.date)) {
+            merged.push(item);
+        }
+    }
+    merged
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct WecquptToken {
     pub x_token: String,
@@ -231,6 +292,7 @@ pub struct WecquptProvider {
     base: BaseProvider,
     base_url: Url,
     public_key: RsaPublicKey,
+    window_weeks: i64,
 }
 
 #[derive(Serialize)]
@@ -241,6 +303,102 @@ struct LoginForm<'a> {
     verification_code: Option<&'a str>,
 }
 
+/// 根据登录失败的状态码与响应内容判断失败原因
+fn login_failure_reason(status: StatusCode, body: &str) -> Option<AuthFailReason> {
+    if body.contains("验证码") {
+        Some(AuthFailReason::CaptchaRequired)
+    } else if status == StatusCode::LOCKED
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || body.contains("锁定")
+        || body.contains("频繁")
+    {
+        Some(AuthFailReason::Locked)
+    } else if status == StatusCode::UNAUTHORIZED
+        || status == StatusCode::BAD_REQUEST
+        || body.contains("密码")
+    {
+        Some(AuthFailReason::BadCredentials)
+    } else {
+        None
+    }
+}
+
+/// 将日程备注中的 HTML 转为纯文本：去除标签、解码实体并压缩空白
+///
+/// 不含 `<` 与 `&` 的纯文本原样返回
+fn html_to_text(raw: &str) -> String {
+    if !raw.contains('<') && !raw.contains('&') {
+        return raw.to_string();
+    }
+
+    let mut text = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(pos) = rest.find(['<', '&']) {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('<') {
+            match rest.find('>') {
+                Some(end) => {
+                    let tag = rest[1..end].trim().to_ascii_lowercase();
+                    let name = tag.trim_start_matches('/').trim_end_matches('/').trim();
+                    let name = name.split_whitespace().next().unwrap_or("");
+                    if name == "br" || (tag.starts_with('/') && matches!(name, "p" | "div" | "li"))
+                    {
+                        text.push('\n');
+                    }
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push_str(rest);
+                    rest = "";
+                }
+            }
+        } else {
+            match rest
+                .find(';')
+                .filter(|&end| end <= 10)
+                .and_then(|end| decode_html_entity(&rest[1..end]).map(|c| (c, end)))
+            {
+                Some((c, end)) => {
+                    text.push(c);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+    text.push_str(rest);
+
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 解码单个 HTML 实体（不含 `&` 与 `;`），无法识别时返回 `None`
+fn decode_html_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let value = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(value)
+        }
+    }
+}
+
 impl WecquptProvider {
     pub fn new() -> Self {
         let builder = BaseProviderBuilder::new(ProviderInfo {
@@ -252,9 +410,26 @@ impl WecquptProvider {
             base: builder.build(),
             base_url: Url::parse(API_ROOT).unwrap().join("api/").unwrap(),
             public_key: RsaPublicKey::from_public_key_pem(PUBLIC_KEY).unwrap(),
+            window_weeks: DEFAULT_SCHEDULE_WINDOW_WEEKS,
         }
     }
 
+    /// 设置每次请求课表的周数，至少为 1
+    pub fn with_window_weeks(mut self, weeks: u32) -> Self {
+        self.window_weeks = i64::from(weeks.max(1));
+        self
+    }
+
+    /// 设置接口根地址，用于测试或镜像
+    ///
+    /// 地址必须是 http/https URL，否则返回 [`crate::Error::Config`]。
+    pub fn with_api_root(mut self, api_root: &str) -> Result<Self> {
+        self.base_url = parse_api_root(api_root)?
+            .join("api/")
+            .map_err(|e| crate::Error::Config(format!("Invalid API root '{}': {}", api_root, e)))?;
+        Ok(self)
+    }
+
     fn ensure_context<'a>(
         &'a self,
         context: ParamContext<'a, WecquptContext>,
@@ -297,10 +472,10 @@ impl WecquptProvider {
                 .custom_error(format!("HTTP {} error", response.status())));
         }
 
-        let payload: WecquptTimeResponse = response.json().await.map_err(|e| {
-            self.base
-                .custom_error(format!("Failed to parse time response: {}", e))
-        })?;
+        let payload: WecquptTimeResponse = response
+            .json()
+            .await
+            .map_err(|e| self.base.deserialize_error(format!("time: {}", e)))?;
 
         if payload.code != 0 {
             return Err(self.base.custom_error(
@@ -329,14 +504,37 @@ impl WecquptProvider {
         })
     }
 
+    /// 按时间段分批获取整个学期的日程并合并
     async fn fetch_schedule(
         &self,
         semester_start: &DateTime<FixedOffset>,
         token: &WecquptToken,
     ) -> Result<WecquptScheduleResponse> {
-        let start_str = semester_start.format("%Y-%m-%d").to_string();
-        let end_date = *semester_start + chrono::Duration::weeks(SCHEDULE_FETCH_WEEKS)
-            - chrono::Duration::days(1);
+        let mut response = None;
+        let mut windows = Vec::new();
+        for (start, end) in schedule_windows(
+            semester_start.date_naive(),
+            SCHEDULE_FETCH_WEEKS,
+            self.window_weeks,
+        ) {
+            let mut payload = self.fetch_schedule_window(start, end, token).await?;
+            windows.push(std::mem::take(&mut payload.data.schedules));
+            response.get_or_insert(payload);
+        }
+
+        let mut response =
+            response.ok_or_else(|| self.base.custom_error("No schedule window to fetch"))?;
+        response.data.schedules = merge_schedule_windows(windows);
+        Ok(response)
+    }
+
+    async fn fetch_schedule_window(
+        &self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+        token: &WecquptToken,
+    ) -> Result<WecquptScheduleResponse> {
+        let start_str = start_date.format("%Y-%m-%d").to_string();
         let end_str = end_date.format("%Y-%m-%d").to_string();
 
         let response = self
@@ -366,10 +564,10 @@ impl WecquptProvider {
                 .custom_error(format!("HTTP {} error", response.status())));
         }
 
-        let payload: WecquptScheduleResponse = response.json().await.map_err(|e| {
-            self.base
-                .custom_error(format!("Failed to parse schedule response: {}", e))
-        })?;
+        let payload: WecquptScheduleResponse = response
+            .json()
+            .await
+            .map_err(|e| self.base.deserialize_error(format!("schedule: {}", e)))?;
 
         if payload.code != 0 {
             return Err(self.base.custom_error(
@@ -399,11 +597,12 @@ impl WecquptProvider {
         &self,
         items: Vec<WecquptScheduleItem>,
         time_info: &WecquptTimeInfo,
+        lessons: &LessonSchedule,
     ) -> Result<Vec<Course>> {
         let aggregated = self.aggregate_schedule_items(items)?;
         let courses = aggregated
             .into_iter()
-            .map(|(item, acc)| self.build_course(item, acc, time_info))
+            .map(|(item, acc)| self.build_course(item, acc, time_info, lessons))
             .collect::<Result<Vec<_>>>()?;
         Ok(courses)
     }
@@ -413,20 +612,32 @@ impl WecquptProvider {
         item: WecquptScheduleItem,
         acc: ScheduleAccumulator,
         time_info: &WecquptTimeInfo,
+        lessons: &LessonSchedule,
     ) -> Result<Course> {
         let data = item.data.unwrap_or_default();
         let start_time = self.combine_datetime(acc.earliest_date, &item.start_time)?;
         let end_time = self.combine_datetime(acc.earliest_date, &item.end_time)?;
         let weeks = acc.weeks.into_iter().collect::<Vec<_>>();
-        let teacher = Self::normalize_ref(data.teacher_name.as_ref());
+        let teachers = Self::normalize_ref(data.teacher_name.as_ref())
+            .map(|raw| split_teachers(&raw))
+            .unwrap_or_default();
         let code = Self::normalize_ref(data.course_id.as_ref())
             .or_else(|| Self::normalize_ref(item.type_id.as_ref()))
             .or_else(|| Self::normalize_ref(data.class_id.as_ref()));
         let location = Self::normalize_ref(item.location.as_ref());
-        let description = Self::normalize_ref(item.description.as_ref());
+        let description = Self::normalize_ref(item.description.as_ref())
+            .map(|d| html_to_text(&d))
+            .filter(|d| !d.is_empty());
         let exam_type = Self::normalize_ref(data.exam_type.as_ref());
         let seat = Self::normalize_ref(data.seat.as_ref());
         let status = Self::normalize_ref(data.qualification.as_ref());
+        let chief_invigilator = Self::normalize_ref(data.chief_invigilator.as_ref());
+        let deputy_invigilators = data
+            .deputy_invigilators
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|name| Self::normalize_ref(Some(name)))
+            .collect();
 
         let course_type = match item.item_type {
             1 => Self::normalize_ref(data.course_type.as_ref()),
@@ -435,15 +646,17 @@ impl WecquptProvider {
             _ => None,
         };
 
-        let mut begin_lesson = None;
-        if !item.time_slots.is_empty() {
-            begin_lesson = item.time_slots.iter().copied().min();
-        }
-
-        let lesson_duration = if item.time_slots.is_empty() {
-            None
+        // 没有节次数组时按作息表由上下课时间反推
+        let (begin_lesson, lesson_duration) = if item.time_slots.is_empty() {
+            let minutes = |time: DateTime<FixedOffset>| time.hour() * 60 + time.minute();
+            lessons
+                .lessons_between(minutes(start_time), minutes(end_time))
+                .unzip()
         } else {
-            Some(item.time_slots.len() as u32)
+            (
+                item.time_slots.iter().copied().min(),
+                Some(item.time_slots.len() as u32),
+            )
         };
 
         let weekday = acc.earliest_date.weekday().number_from_monday();
@@ -451,7 +664,8 @@ impl WecquptProvider {
         Ok(Course {
             name: item.title,
             code,
-            teacher,
+            teacher: join_teachers(&teachers),
+            teachers,
             location,
             start_time,
             end_time,
@@ -463,8 +677,12 @@ impl WecquptProvider {
             lesson_duration,
             current_week: Some(time_info.current_week),
             exam_type,
+            seats: seat.as_deref().map(parse_seats).unwrap_or_default(),
             seat,
             status,
+            chief_invigilator,
+            deputy_invigilators,
+            credits: data.credit.filter(|credit| *credit > 0.0),
 
             ..Default::default()
         })
@@ -519,10 +737,6 @@ impl Provider for WecquptProvider {
         &self.base.info.description
     }
 
-    fn timezone(&self) -> FixedOffset {
-        FixedOffset::east_opt(8 * 3600).unwrap()
-    }
-
     async fn authenticate<'a>(
         &'a self,
         _context: ParamContext<'_, Self::ContextType>,
@@ -537,7 +751,7 @@ impl Provider for WecquptProvider {
             .header("traefik", "user")
             .form(&LoginForm {
                 cqupt_id: &request.credentials.username,
-                password: &self.encrypt_password(&request.credentials.password)?,
+                password: &self.encrypt_password(request.credentials.password.expose_secret())?,
                 verification_code: None,
             })
             .send()
@@ -549,9 +763,15 @@ impl Provider for WecquptProvider {
         }
 
         if !response.status().is_success() {
-            return Err(self
-                .base
-                .custom_error(format!("HTTP {} error", response.status())));
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(match login_failure_reason(status, &body) {
+                Some(reason) => crate::Error::authentication(
+                    reason,
+                    format!("登录失败 (HTTP {}): {}", status, body.trim()),
+                ),
+                None => self.base.custom_error(format!("HTTP {} error", status)),
+            });
         }
 
         for ck in response.headers().get_all(header::SET_COOKIE) {
@@ -578,6 +798,30 @@ impl Provider for WecquptProvider {
         Ok(!token.x_token.trim().is_empty())
     }
 
+    async fn revoke_token(&self, token: &Self::Token) -> Result<()> {
+        let response = self
+            .base
+            .client
+            .post(self.base_url.join("logout").unwrap())
+            .header("traefik", "user")
+            .header(
+                header::COOKIE,
+                format!("{}; {}", token.x_token, token.refresh_token),
+            )
+            .send()
+            .await
+            .map_err(|e| self.base.handle_error_req(e))?;
+
+        // 会话已失效时同样视为注销成功
+        if response.status().is_success() || response.status() == StatusCode::UNAUTHORIZED {
+            Ok(())
+        } else {
+            Err(self
+                .base
+                .custom_error(format!("Logout failed: HTTP {}", response.status())))
+        }
+    }
+
     async fn get_semester_start<'a, 'b>(
         &'a self,
         context: ParamContext<'b, Self::ContextType>,
@@ -628,12 +872,28 @@ impl Provider for WecquptProvider {
             .clone()
             .ok_or_else(|| self.base.custom_error("Failed to load schedule"))?;
 
-        let courses = self.convert_schedule_to_courses(schedule.data.schedules, &time_info)?;
+        let lessons = request.options.lesson_times.clone().unwrap_or_default();
+        let options = &request.options;
+        // 课程、考试与自定义日程来自同一接口，按选项筛选
+        let courses = self
+            .convert_schedule_to_courses(schedule.data.schedules, &time_info, &lessons)?
+            .into_iter()
+            .filter(|course| {
+                if course.is_exam() {
+                    options.include_exams
+                } else if course.is_custom() {
+                    options.include_custom
+                } else {
+                    options.include_classes
+                }
+            })
+            .collect();
 
         Ok(CourseResponse {
             courses,
             semester: semester.clone(),
             generated_at: Utc::now().with_timezone(&self.timezone()),
+            warnings: Vec::new(),
         })
     }
 
@@ -646,4 +906,336 @@ impl Provider for WecquptProvider {
     fn token_ttl(&self) -> std::time::Duration {
         std::time::Duration::from_secs(3600 * 24 * 20)
     }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            recurrence_native: true,
+        }
+    }
+
+    fn api_root(&self) -> Option<&str> {
+        Some(self.base_url.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProviderOptions, Semester, ics::IcsGenerator, providers::Context};
+
+    const SCHEDULE_JSON: &str = r#"{
+        "code": 0,
+        "msg": null,
+        "data": {
+            "schedules": [
+                {"id": "1", "type": 1, "type_id": "A1", "date": "2025-09-08", "week_num": 1,
+                 "start_time": "08:00", "end_time": "09:40", "time_slots": [1, 2],
+                 "title": "高等数学", "location": "2101"},
+                {"id": "2", "type": 1, "type_id": "A1", "date": "2025-09-15", "week_num": 2,
+                 "start_time": "08:00", "end_time": "09:40", "time_slots": [1, 2],
+                 "title": "高等数学", "location": "2101"},
+                {"id": "3", "type": 1, "type_id": "B2", "date": "2025-09-08", "week_num": 1,
+                 "start_time": "10:15", "end_time": "11:55", "time_slots": [3, 4],
+                 "title": "大学英语", "location": "3208"},
+                {"id": "4", "type": 1, "type_id": "C3", "date": "2025-09-09", "week_num": 1,
+                 "start_time": "14:00", "end_time": "15:40", "time_slots": [5, 6],
+                 "title": "大学物理", "location": "4101"},
+                {"id": "5", "type": 1, "type_id": "D4", "date": "2025-09-10", "week_num": 1,
+                 "start_time": "08:00", "end_time": "09:40", "time_slots": [1, 2],
+                 "title": "程序设计", "location": "综合实验楼B405"},
+                {"id": "6", "type": 3, "type_id": "E5", "date": "2026-01-08", "week_num": 18,
+                 "start_time": "14:00", "end_time": "16:00",
+                 "title": "线性代数", "location": "4101"}
+            ]
+        }
+    }"#;
+
+    fn time_info() -> WecquptTimeInfo {
+        let tz = crate::CQUPT_TZ;
+        WecquptTimeInfo {
+            _term: "2025-2026-1".to_string(),
+            start_date: tz.with_ymd_and_hms(2025, 9, 8, 0, 0, 0).unwrap(),
+            current_week: 1,
+        }
+    }
+
+    /// 按顺序提取事件的 SUMMARY 与 DTSTART
+    fn event_order(ics: &str) -> Vec<&str> {
+        ics.lines()
+            .filter(|line| line.starts_with("SUMMARY:") || line.starts_with("DTSTART:"))
+            .collect()
+    }
+
+    #[test]
+    fn sorted_schedule_is_deterministic() {
+        let provider = WecquptProvider::new();
+        let info = time_info();
+        let generator = IcsGenerator::default();
+
+        let mut outputs = Vec::new();
+        for _ in 0..5 {
+            let schedule: WecquptScheduleResponse = serde_json::from_str(SCHEDULE_JSON).unwrap();
+            let courses = provider
+                .convert_schedule_to_courses(
+                    schedule.data.schedules,
+                    &info,
+                    &LessonSchedule::default(),
+                )
+                .unwrap();
+            let mut response = CourseResponse {
+                courses,
+                semester: Semester {
+                    start_date: info.start_date,
+                    weeks_total: None,
+                },
+                generated_at: info.start_date,
+                warnings: Vec::new(),
+            };
+            response.sort();
+            outputs.push(generator.generate(&response).unwrap());
+        }
+
+        let expected = event_order(&outputs[0]);
+        assert_eq!(expected.len(), 10);
+        assert_eq!(expected[1], "SUMMARY:高等数学 - 2101");
+        for output in &outputs[1..] {
+            assert_eq!(event_order(output), expected);
+        }
+    }
+
+    #[test]
+    fn exam_invigilators_are_rendered() {
+        let provider = WecquptProvider::new();
+        let items: Vec<WecquptScheduleItem> = serde_json::from_str(
+            r#"[{"id": "7", "type": 3, "type_id": "E5", "date": "2026-01-08", "week_num": 18,
+                 "start_time": "14:00", "end_time": "16:00",
+                 "title": "线性代数", "location": "4101",
+                 "data": {"exam_type": "期末", "seat": "12",
+                          "chief_invigilator": "张老师",
+                          "deputy_invigilators": ["李老师", " ", "王老师"]}}]"#,
+        )
+        .unwrap();
+        let course = provider
+            .convert_schedule_to_courses(items, &time_info(), &LessonSchedule::default())
+            .unwrap()
+            .remove(0);
+        assert_eq!(course.chief_invigilator.as_deref(), Some("张老师"));
+        assert_eq!(course.deputy_invigilators, vec!["李老师", "王老师"]);
+
+        let description = IcsGenerator::default().build_exam_description(&course);
+        assert!(description.contains("考试座位号是12"));
+        assert!(description.contains("主考: 张老师，监考: 李老师、王老师，"));
+    }
+
+    #[test]
+    fn credits_accept_numbers_and_strings() {
+        let provider = WecquptProvider::new();
+        let item = |id: u32, credit: &str| {
+            format!(
+                r#"{{"id": "{id}", "type": 1, "type_id": "A{id}", "date": "2025-09-08",
+                    "week_num": 1, "start_time": "08:00", "end_time": "09:40",
+                    "title": "课程{id}", "data": {{"credit": {credit}}}}}"#
+            )
+        };
+        let json = format!(
+            "[{}]",
+            [
+                item(1, "3.5"),
+                item(2, r#""2""#),
+                item(3, r#""未知""#),
+                item(4, "null"),
+                item(5, "[1]"),
+            ]
+            .join(",")
+        );
+        let items: Vec<WecquptScheduleItem> = serde_json::from_str(&json).unwrap();
+        let mut courses = provider
+            .convert_schedule_to_courses(items, &time_info(), &LessonSchedule::default())
+            .unwrap();
+        courses.sort_by(|a, b| a.name.cmp(&b.name));
+        let credits: Vec<_> = courses.iter().map(|course| course.credits).collect();
+        assert_eq!(credits, [Some(3.5), Some(2.0), None, None, None]);
+    }
+
+    #[test]
+    fn lessons_are_derived_without_time_slots() {
+        let provider = WecquptProvider::new();
+        let items: Vec<WecquptScheduleItem> = serde_json::from_str(
+            r#"[{"id": "8", "type": 1, "date": "2025-09-09", "week_num": 1,
+                 "start_time": "10:15", "end_time": "11:00",
+                 "title": "大学物理", "location": "3201"},
+                {"id": "9", "type": 3, "date": "2026-01-08", "week_num": 18,
+                 "start_time": "14:00", "end_time": "16:00",
+                 "title": "线性代数", "location": "4101"}]"#,
+        )
+        .unwrap();
+        let courses = provider
+            .convert_schedule_to_courses(items, &time_info(), &LessonSchedule::default())
+            .unwrap();
+        let physics = courses.iter().find(|c| c.name == "大学物理").unwrap();
+        assert_eq!(physics.begin_lesson, Some(3));
+        assert_eq!(physics.lesson_duration, Some(1));
+        // 考试时间与作息表对不上，不推断节次
+        let exam = courses.iter().find(|c| c.name == "线性代数").unwrap();
+        assert_eq!(exam.begin_lesson, None);
+        assert_eq!(exam.lesson_duration, None);
+    }
+
+    #[test]
+    fn api_root_override() {
+        assert_eq!(
+            WecquptProvider::new().base_url.as_str(),
+            "https://we.cqupt.edu.cn/api/"
+        );
+
+        let provider = WecquptProvider::new()
+            .with_api_root("http://127.0.0.1:8080/mirror")
+            .unwrap();
+        assert_eq!(
+            provider.base_url.join("timetable").unwrap().as_str(),
+            "http://127.0.0.1:8080/mirror/api/timetable"
+        );
+
+        for invalid in [
+            "",
+            "we.cqupt.edu.cn",
+            "ftp://we.cqupt.edu.cn/",
+            "https://x/?a=1",
+        ] {
+            assert!(matches!(
+                WecquptProvider::new().with_api_root(invalid),
+                Err(crate::Error::Config(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn login_failure_reasons() {
+        assert_eq!(
+            login_failure_reason(StatusCode::BAD_REQUEST, r#"{"msg":"账号或密码错误"}"#),
+            Some(AuthFailReason::BadCredentials)
+        );
+        assert_eq!(
+            login_failure_reason(StatusCode::BAD_REQUEST, r#"{"msg":"请输入验证码"}"#),
+            Some(AuthFailReason::CaptchaRequired)
+        );
+        assert_eq!(
+            login_failure_reason(StatusCode::FORBIDDEN, r#"{"msg":"账号已锁定"}"#),
+            Some(AuthFailReason::Locked)
+        );
+        assert_eq!(
+            login_failure_reason(StatusCode::TOO_MANY_REQUESTS, ""),
+            Some(AuthFailReason::Locked)
+        );
+        assert_eq!(
+            login_failure_reason(StatusCode::INTERNAL_SERVER_ERROR, ""),
+            None
+        );
+    }
+
+    #[test]
+    fn html_descriptions_are_stripped() {
+        assert_eq!(
+            html_to_text("<p><b>带计算器</b> &amp; 草稿纸</p><p>提前&nbsp;10&#20998;钟入场</p>"),
+            "带计算器 & 草稿纸\n提前 10分钟入场"
+        );
+        assert_eq!(html_to_text("第一行<br/>第二行"), "第一行\n第二行");
+        // 纯文本与无法识别的实体保持原样
+        assert_eq!(html_to_text("A&B  实验 1 < 2"), "A&B 实验 1 < 2");
+        assert_eq!(html_to_text("闭卷  考试"), "闭卷  考试");
+    }
+
+    #[test]
+    fn schedule_window_ranges() {
+        let start = NaiveDate::from_ymd_opt(2025, 9, 8).unwrap();
+        let windows = schedule_windows(start, 25, 8);
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[0], (start, start + chrono::Duration::days(55)));
+        assert_eq!(windows[1].0, start + chrono::Duration::weeks(8));
+        assert_eq!(
+            windows[3],
+            (
+                start + chrono::Duration::weeks(24),
+                start + chrono::Duration::weeks(25) - chrono::Duration::days(1)
+            )
+        );
+        assert_eq!(schedule_windows(start, 25, 0).len(), 25);
+    }
+
+    #[test]
+    fn overlapping_windows_are_merged_once() {
+        let schedules: WecquptScheduleResponse = serde_json::from_str(SCHEDULE_JSON).unwrap();
+        let items = schedules.data.schedules;
+        // 两个窗口都返回了第 2、3 条日程
+        let first = items[..3].to_vec();
+        let second = items[1..].to_vec();
+
+        let merged = merge_schedule_windows([first, second]);
+        let ids = merged
+            .iter()
+            .map(|item| item.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1", "2", "3", "4", "5", "6"]);
+
+        let courses = WecquptProvider::new()
+            .convert_schedule_to_courses(merged, &time_info(), &LessonSchedule::default())
+            .unwrap();
+        assert_eq!(courses.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn exam_only_request_keeps_exams() {
+        let provider = WecquptProvider::new();
+        let mut ctx = Context::new(WecquptContext {
+            time: Some(time_info()),
+            schedule: Some(serde_json::from_str(SCHEDULE_JSON).unwrap()),
+        });
+        let mut request = CourseRequest {
+            credentials: crate::Credentials {
+                username: "2023000000".to_string(),
+                password: "000000".into(),
+                extra: HashMap::new(),
+            },
+            semester: Some(Semester::from_date_str("2025-09-08").unwrap()),
+            timeout: None,
+            options: ProviderOptions {
+                include_classes: false,
+                include_custom: false,
+                ..Default::default()
+            },
+        };
+
+        let response = provider
+            .get_courses(ctx.as_param(), &mut request, &WecquptToken::default())
+            .await
+            .unwrap();
+
+        assert_eq!(response.courses.len(), 1);
+        assert_eq!(response.courses[0].name, "线性代数");
+        assert!(response.courses[0].is_exam());
+    }
+
+    #[tokio::test]
+    async fn revoke_token_calls_logout_endpoint() {
+        use wiremock::{
+            Mock, MockServer, ResponseTemplate,
+            matchers::{header, method, path},
+        };
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/logout"))
+            .and(header("cookie", "x-token=abc; refresh-token=def"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = WecquptProvider::new().with_api_root(&server.uri()).unwrap();
+        let token = WecquptToken {
+            x_token: "x-token=abc".to_string(),
+            refresh_token: "refresh-token=def".to_string(),
+        };
+        provider.revoke_token(&token).await.unwrap();
+    }
 }
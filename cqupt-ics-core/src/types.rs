@@ -1,7 +1,14 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+};
 
-use chrono::{DateTime, Datelike, FixedOffset};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Timelike, Utc};
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// 学校所在时区（UTC+8），各 provider 与学期计算统一使用
+pub const CQUPT_TZ: FixedOffset = FixedOffset::east_opt(8 * 3600).expect("valid UTC offset");
 
 /// 课程重复规则
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,8 +34,11 @@ pub struct Course {
     pub name: String,
     /// 课程代码
     pub code: Option<String>,
-    /// 教师姓名
+    /// 教师姓名，多位教师时为以"、"连接的展示文本
     pub teacher: Option<String>,
+    /// 教师列表
+    #[serde(default)]
+    pub teachers: Vec<String>,
     /// 上课地点
     pub location: Option<String>,
     /// 开始时间 (第一次上课的时间)
@@ -66,53 +76,476 @@ pub struct Course {
     pub exam_type: Option<String>,
     /// 考试座位号
     pub seat: Option<String>,
+    /// 解析后的座位，`seat` 保留原始文本用于展示
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seats: Vec<Seat>,
     /// 考试状态
     pub status: Option<String>,
+    /// 主考教师
+    #[serde(default)]
+    pub chief_invigilator: Option<String>,
+    /// 监考教师
+    #[serde(default)]
+    pub deputy_invigilators: Vec<String>,
+
+    /// 全天事件，开始与结束时间取当天零点（结束不含）
+    #[serde(default)]
+    pub all_day: bool,
+    /// 节假日调休产生的补课
+    #[serde(default)]
+    pub is_makeup: bool,
+}
+
+/// 拆分多位教师，支持 `,`、`，`、`、`、`;`、`/` 等分隔符
+pub fn split_teachers(raw: &str) -> Vec<String> {
+    raw.split([',', '，', '、', ';', '；', '/'])
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 考试座位的结构化信息
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Seat {
+    /// 区域，如 "A区" 中的 "A"
+    pub zone: Option<String>,
+    /// 排号
+    pub row: Option<String>,
+    /// 座位号
+    pub number: Option<String>,
+    /// 原始文本，无法解析时其余字段均为空
+    pub raw: String,
+}
+
+/// 座位文本的格式，如 "A区12排5座"
+static SEAT_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"^(?:(?P<zone>[^\s区排座号]+)区)?(?:(?P<row>\d+)排)?(?:(?P<number>[A-Za-z]?\d+)[座号]?)?$",
+    )
+    .unwrap()
+});
+
+impl Seat {
+    /// 解析单个座位，如 "A区12排5座"、"12排5号"、"12"
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        let field = |caps: &regex::Captures, name| caps.name(name).map(|m| m.as_str().to_string());
+        match SEAT_RE.captures(raw) {
+            Some(caps) if !raw.is_empty() => Self {
+                zone: field(&caps, "zone"),
+                row: field(&caps, "row"),
+                number: field(&caps, "number"),
+                raw: raw.to_string(),
+            },
+            _ => Self {
+                raw: raw.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// 解析一个或多个座位，分隔符与 [`split_teachers`] 相同
+pub fn parse_seats(raw: &str) -> Vec<Seat> {
+    raw.split([',', '，', '、', ';', '；', '/'])
+        .map(str::trim)
+        .filter(|seat| !seat.is_empty())
+        .map(Seat::parse)
+        .collect()
+}
+
+/// 多位教师的展示文本，没有教师时为 `None`
+pub fn join_teachers(teachers: &[String]) -> Option<String> {
+    (!teachers.is_empty()).then(|| teachers.join("、"))
+}
+
+impl Course {
+    /// 教师列表，未填充 `teachers` 时按 `teacher` 拆分
+    pub fn teacher_names(&self) -> Vec<String> {
+        if self.teachers.is_empty() {
+            self.teacher
+                .as_deref()
+                .map(split_teachers)
+                .unwrap_or_default()
+        } else {
+            self.teachers.clone()
+        }
+    }
+
+    /// 单次课程的时长
+    pub fn duration(&self) -> chrono::Duration {
+        self.end_time - self.start_time
+    }
+
+    /// 判断两门课程是否存在时间重叠的上课时段
+    ///
+    /// 有周次信息的课程按周展开（跳过放假周），否则视为单次事件。
+    pub fn overlaps(&self, other: &Course) -> bool {
+        let others = other.occurrences();
+        self.occurrences()
+            .iter()
+            .any(|(start, end)| others.iter().any(|(s, e)| start < e && s < end))
+    }
+
+    /// 是否是考试
+    pub fn is_exam(&self) -> bool {
+        self.exam_type.is_some()
+            || self
+                .course_type
+                .as_ref()
+                .is_some_and(|t| t.contains("考试"))
+    }
+
+    /// 是否是线上课程，按默认关键字判断地点
+    pub fn is_online(&self) -> bool {
+        self.location.as_deref().is_some_and(|loc| {
+            crate::location::is_online_location(loc, crate::location::DEFAULT_ONLINE_KEYWORDS)
+        })
+    }
+
+    /// 是否是自定义日程
+    pub fn is_custom(&self) -> bool {
+        self.course_type
+            .as_ref()
+            .is_some_and(|t| t.contains("自定义"))
+            || self
+                .note
+                .as_ref()
+                .is_some_and(|n| n.starts_with("自定义日程"))
+    }
+
+    /// 展开每次上课的 (开始, 结束) 时间
+    fn occurrences(&self) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+        let Some(&first_week) = self.weeks.as_ref().and_then(|weeks| weeks.iter().min()) else {
+            return vec![(self.start_time, self.end_time)];
+        };
+        let off_weeks = self.off_weeks.as_deref().unwrap_or_default();
+        self.weeks
+            .iter()
+            .flatten()
+            .filter(|week| !off_weeks.contains(week))
+            .map(|&week| {
+                let offset = chrono::Duration::weeks(week as i64 - first_week as i64);
+                (self.start_time + offset, self.end_time + offset)
+            })
+            .collect()
+    }
+}
+
+/// 解析周次表达式，例如 "1-8,10,12双"、"1-16周单"
+///
+/// 支持逗号（含中文逗号、顿号）分隔的单周与区间，区间可带 单/双 后缀筛选奇偶周，
+/// 结尾的 "周" 与括号会被忽略。结果升序且去重。
+pub fn parse_week_expression(expr: &str) -> crate::Result<Vec<u32>> {
+    let invalid = |reason: &str| {
+        crate::Error::Config(format!("Invalid week expression '{}': {}", expr, reason))
+    };
+
+    let mut weeks = std::collections::BTreeSet::new();
+    for segment in expr.split([',', '，', '、']) {
+        let segment = segment
+            .trim()
+            .trim_end_matches([')', '）'])
+            .trim_end_matches('周');
+        if segment.is_empty() {
+            return Err(invalid("empty segment"));
+        }
+
+        let (range, parity) = if let Some(range) = segment.strip_suffix('单') {
+            (range, Some(1))
+        } else if let Some(range) = segment.strip_suffix('双') {
+            (range, Some(0))
+        } else {
+            (segment, None)
+        };
+        let range = range
+            .trim()
+            .trim_end_matches(['(', '（'])
+            .trim_end_matches('周');
+
+        let parse = |s: &str| {
+            s.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|week| *week > 0)
+                .ok_or_else(|| invalid(&format!("'{}' is not a week number", s)))
+        };
+        let (start, end) = match range.split_once(['-', '~']) {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => {
+                let week = parse(range)?;
+                (week, week)
+            }
+        };
+        if start > end {
+            return Err(invalid(&format!("range {}-{} is reversed", start, end)));
+        }
+
+        weeks.extend((start..=end).filter(|week| parity.is_none_or(|p| week % 2 == p)));
+    }
+
+    if weeks.is_empty() {
+        return Err(invalid("no week matches"));
+    }
+    Ok(weeks.into_iter().collect())
+}
+
+/// 未指定时的学期总周数
+pub const DEFAULT_WEEKS_TOTAL: u32 = 20;
+/// 学期总周数上限，超过视为数据异常
+pub const MAX_WEEKS_TOTAL: u32 = 30;
+
+/// 学期第一周的起始日规则
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeekStartPolicy {
+    /// 回退到给定日期所在周的星期一
+    #[default]
+    AlwaysMonday,
+    /// 保留给定日期，每周从该日开始
+    KeepGivenDay,
+}
+
+/// 计算第 `week` 周中星期 `weekday`（1-7）当天的时间
+///
+/// 周从 `start_date` 当天开始算起，学期不从星期一开始时，
+/// 第一周早于开学日的星期顺延到下一个同名星期。
+pub(crate) fn week_day_date(
+    start_date: &DateTime<FixedOffset>,
+    week: u32,
+    weekday: u32,
+) -> DateTime<FixedOffset> {
+    let start_weekday = start_date.weekday().number_from_monday() as i64;
+    let days = (weekday as i64 - start_weekday).rem_euclid(7);
+    *start_date + chrono::Duration::weeks(week as i64 - 1) + chrono::Duration::days(days)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Semester {
     pub start_date: DateTime<FixedOffset>,
+    /// 学期总周数，未指定时为 [`DEFAULT_WEEKS_TOTAL`]
+    #[serde(default)]
+    pub weeks_total: Option<u32>,
 }
 
 impl Semester {
-    pub fn from_date_str(date_str: &str) -> Result<Self, String> {
-        use chrono::{Datelike, NaiveDate, TimeZone};
+    pub fn from_date_str(date_str: &str) -> crate::Result<Self> {
+        Self::from_date_str_with_policy(date_str, WeekStartPolicy::AlwaysMonday)
+    }
 
+    /// 按指定的第一周规则解析学期开始日期
+    pub fn from_date_str_with_policy(
+        date_str: &str,
+        policy: WeekStartPolicy,
+    ) -> crate::Result<Self> {
         let naive_date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").map_err(|e| {
-            format!(
+            crate::Error::Config(format!(
                 "Invalid date format '{}': {}. Expected format: YYYY-MM-DD",
                 date_str, e
-            )
+            ))
         })?;
 
-        // 找到这一周的星期一
-        let days_since_monday = naive_date.weekday().num_days_from_monday();
-        let first_monday = naive_date - chrono::Duration::days(days_since_monday as i64);
+        let first_day = match policy {
+            // 找到这一周的星期一
+            WeekStartPolicy::AlwaysMonday => {
+                let days_since_monday = naive_date.weekday().num_days_from_monday();
+                naive_date - chrono::Duration::days(days_since_monday as i64)
+            }
+            WeekStartPolicy::KeepGivenDay => naive_date,
+        };
 
         // 转换为UTC+8时间（重庆时间）
-        let start_datetime = first_monday
+        let start_datetime = first_day
             .and_hms_opt(0, 0, 0)
-            .ok_or_else(|| "Failed to create datetime".to_string())?;
+            .ok_or_else(|| crate::Error::Config("Failed to create datetime".to_string()))?;
 
-        let tz = FixedOffset::east_opt(8 * 3600).unwrap(); // UTC+8
+        let tz = CQUPT_TZ;
         let start_date = tz
             .from_local_datetime(&start_datetime)
             .single()
-            .ok_or_else(|| "Failed to convert to timezone".to_string())?;
+            .ok_or_else(|| crate::Error::Config("Failed to convert to timezone".to_string()))?;
+
+        // 学期结束时间须可表示
+        start_date
+            .checked_add_signed(chrono::Duration::weeks(MAX_WEEKS_TOTAL as i64))
+            .ok_or_else(|| {
+                crate::Error::Config(format!("Semester starting {} ends out of range", date_str))
+            })?;
+
+        Ok(Self {
+            start_date,
+            weeks_total: None,
+        })
+    }
 
-        Ok(Self { start_date })
+    /// 设置学期总周数，须在 1..=[`MAX_WEEKS_TOTAL`] 之间
+    pub fn with_weeks_total(mut self, weeks_total: u32) -> crate::Result<Self> {
+        if !(1..=MAX_WEEKS_TOTAL).contains(&weeks_total) {
+            return Err(crate::Error::Config(format!(
+                "Invalid semester length {} weeks, expected 1..={}",
+                weeks_total, MAX_WEEKS_TOTAL
+            )));
+        }
+        self.weeks_total = Some(weeks_total);
+        Ok(self)
+    }
+
+    /// 学期总周数
+    pub fn total_weeks(&self) -> u32 {
+        self.weeks_total.unwrap_or(DEFAULT_WEEKS_TOTAL)
     }
 
-    /// 获取指定周数的星期一日期
+    /// 获取指定周数的第一天，学期从星期一开始时即为该周星期一
     pub fn get_week_start(&self, week: u32) -> DateTime<FixedOffset> {
         self.start_date + chrono::Duration::weeks(week as i64 - 1)
     }
 
+    /// 获取指定周数、星期（1-7）当天零点
+    pub fn occurrence_date(&self, week: u32, weekday: u32) -> DateTime<FixedOffset> {
+        week_day_date(&self.start_date, week, weekday)
+    }
+
     /// 获取学期开始的年份
     pub fn year(&self) -> i32 {
         self.start_date.year()
     }
+
+    /// 学期标识，如 2025-2026-1（秋季）、2025-2026-2（春季）
+    pub fn term_label(&self) -> String {
+        let year = self.year();
+        if self.start_date.month() >= 8 {
+            format!("{}-{}-1", year, year + 1)
+        } else {
+            format!("{}-{}-2", year - 1, year)
+        }
+    }
+}
+
+/// 学期检测器
+///
+/// 在无法从数据源获取学期信息时，根据日期粗略推算当前学期。
+/// 秋季学期按9月1日所在周计算，春季学期按3月1日所在周计算。
+pub struct SemesterDetector;
+
+impl SemesterDetector {
+    /// 推算当前所在学期
+    pub fn create_current_semester() -> Semester {
+        let tz = CQUPT_TZ;
+        Self::create_semester_for(Utc::now().with_timezone(&tz).date_naive())
+    }
+
+    /// 推算指定日期所在学期
+    pub fn create_semester_for(date: NaiveDate) -> Semester {
+        let (year, month) = match date.month() {
+            // 8月至次年1月属于秋季学期
+            8..=12 => (date.year(), 9),
+            1 => (date.year() - 1, 9),
+            // 2月至7月属于春季学期
+            _ => (date.year(), 3),
+        };
+        let anchor = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        Self::semester_starting(
+            anchor - chrono::Duration::days(anchor.weekday().num_days_from_monday() as i64),
+        )
+    }
+
+    /// 推算学年 `year`-`year + 1` 的第 `term` 学期，1 为秋季，2 为春季
+    ///
+    /// 学期从离9月1日（春季为3月1日）最近的星期一开始。
+    pub fn create_semester(year: i32, term: u32) -> crate::Result<Semester> {
+        match term {
+            1 if (1..=9998).contains(&year) => Ok(Self::semester_from_anchor(year, 9)),
+            2 if (1..=9998).contains(&year) => Ok(Self::semester_from_anchor(year + 1, 3)),
+            1 | 2 => Err(crate::Error::Config(format!(
+                "Invalid school year: {}",
+                year
+            ))),
+            _ => Err(crate::Error::Config(format!(
+                "Invalid term number {}, expected 1 or 2",
+                term
+            ))),
+        }
+    }
+
+    /// 解析学期代码，如 `2024-1` 表示 2024-2025 学年秋季学期
+    pub fn from_term_code(code: &str) -> crate::Result<Semester> {
+        let invalid = || {
+            crate::Error::Config(format!(
+                "Invalid term code '{}'. Expected format: YYYY-N, e.g. 2024-1",
+                code
+            ))
+        };
+        let (year, term) = code.trim().split_once('-').ok_or_else(invalid)?;
+        let year = year.parse().map_err(|_| invalid())?;
+        let term = term.parse().map_err(|_| invalid())?;
+        Self::create_semester(year, term)
+    }
+
+    /// 以离 `year` 年 `month` 月 1 日最近的星期一为学期开始
+    ///
+    /// 1 日落在周五至周日时顺延到下周一，避免学期从上个月开始。
+    fn semester_from_anchor(year: i32, month: u32) -> Semester {
+        let anchor = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+        let days_since_monday = anchor.weekday().num_days_from_monday() as i64;
+        Self::semester_starting(if days_since_monday >= 4 {
+            anchor + chrono::Duration::days(7 - days_since_monday)
+        } else {
+            anchor - chrono::Duration::days(days_since_monday)
+        })
+    }
+
+    /// 从 `monday` 零点开始的学期
+    fn semester_starting(monday: NaiveDate) -> Semester {
+        let tz = CQUPT_TZ;
+        let start_date = tz
+            .from_local_datetime(&monday.and_hms_opt(0, 0, 0).unwrap())
+            .single()
+            .unwrap();
+
+        Semester {
+            start_date,
+            weeks_total: None,
+        }
+    }
+}
+
+/// 敏感字符串（如密码）
+///
+/// `Debug` 输出被隐去，释放时清零内存；需要明文时显式调用 [`SecretString::expose_secret`]
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    /// 获取明文，仅在真正需要发送时调用
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self(value.to_string())
+    }
+}
+
+impl std::fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
+}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
 }
 
 /// 用户凭据
@@ -121,7 +554,7 @@ pub struct Credentials {
     /// 用户名/学号
     pub username: String,
     /// 密码
-    pub password: String,
+    pub password: SecretString,
     /// 额外的认证信息
     pub extra: HashMap<String, String>,
 }
@@ -133,6 +566,162 @@ pub struct CourseRequest {
     pub credentials: Credentials,
     /// 学期信息
     pub semester: Option<Semester>,
+    /// 获取课程的超时时间（秒），为空时使用 provider 的默认设置
+    #[serde(default)]
+    pub timeout: Option<u32>,
+    /// 抓取选项
+    #[serde(default)]
+    pub options: ProviderOptions,
+}
+
+/// provider 抓取选项，关闭的部分不会发起对应请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderOptions {
+    /// 是否获取课程表，只需要考试时关闭；无法单独获取考试的 provider 可忽略
+    pub include_classes: bool,
+    /// 是否获取考试安排
+    pub include_exams: bool,
+    /// 是否获取自定义日程
+    pub include_custom: bool,
+    /// 自定义作息时间，为空时使用 provider 默认
+    pub lesson_times: Option<LessonSchedule>,
+}
+
+impl Default for ProviderOptions {
+    fn default() -> Self {
+        Self {
+            include_classes: true,
+            include_exams: true,
+            include_custom: true,
+            lesson_times: None,
+        }
+    }
+}
+
+/// 每天的节次数
+pub const LESSONS_PER_DAY: usize = 12;
+
+/// 相邻两节课间隔达到该分钟数时视为午休、晚饭等长休息
+pub const LONG_BREAK_MINUTES: u32 = 60;
+
+/// 节次作息时间表
+///
+/// JSON 格式为 `[["08:00","08:45"], ...]`，共 [`LESSONS_PER_DAY`] 节，每节上课早于下课
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<[String; 2]>", into = "Vec<[String; 2]>")]
+pub struct LessonSchedule {
+    /// 每节的 (上课, 下课) 时间，为当天分钟数
+    lessons: Vec<(u32, u32)>,
+}
+
+impl LessonSchedule {
+    /// 从JSON加载作息时间表
+    pub fn from_json(json: &str) -> crate::Result<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| crate::Error::Config(format!("Invalid lesson times: {}", e)))
+    }
+
+    /// 第 `lesson` 节（从 1 开始）的上下课时间（当天分钟数）
+    pub fn get(&self, lesson: u32) -> Option<(u32, u32)> {
+        let index = (lesson as usize).checked_sub(1)?;
+        self.lessons.get(index).copied()
+    }
+
+    /// 由上下课时间（当天分钟数）反推 (起始节次, 节数)
+    ///
+    /// 开始时间须与某节上课时间、结束时间须与某节下课时间完全一致，否则返回 `None`。
+    pub fn lessons_between(&self, start: u32, end: u32) -> Option<(u32, u32)> {
+        let first = self.lessons.iter().position(|&(s, _)| s == start)?;
+        let last = self.lessons.iter().position(|&(_, e)| e == end)?;
+        (last >= first).then(|| (first as u32 + 1, (last - first) as u32 + 1))
+    }
+
+    /// 将从 `begin_lesson` 起连上 `period` 节的课按午休、晚饭等长休息拆开
+    ///
+    /// 返回每段的 (起始节次, 节数)；相邻两节间隔达到 [`LONG_BREAK_MINUTES`] 即视为长休息。
+    /// 超出作息表的节次不拆分。
+    pub fn split_at_breaks(&self, begin_lesson: u32, period: u32) -> Vec<(u32, u32)> {
+        let mut segments = vec![(begin_lesson, period.min(1))];
+        for lesson in begin_lesson + 1..begin_lesson + period {
+            let long_break = match (self.get(lesson - 1), self.get(lesson)) {
+                (Some((_, prev_end)), Some((start, _))) => {
+                    start.saturating_sub(prev_end) >= LONG_BREAK_MINUTES
+                }
+                _ => false,
+            };
+            match segments.last_mut() {
+                Some(last) if !long_break => last.1 += 1,
+                _ => segments.push((lesson, 1)),
+            }
+        }
+        segments
+    }
+}
+
+impl Default for LessonSchedule {
+    /// 重邮默认作息
+    fn default() -> Self {
+        const CQUPT: [(u32, u32); LESSONS_PER_DAY] = [
+            (8 * 60, 8 * 60 + 45),        // 第1节: 08:00-08:45
+            (8 * 60 + 55, 9 * 60 + 40),   // 第2节: 08:55-09:40
+            (10 * 60 + 15, 11 * 60),      // 第3节: 10:15-11:00
+            (11 * 60 + 15, 11 * 60 + 55), // 第4节: 11:15-11:55
+            (14 * 60, 14 * 60 + 45),      // 第5节: 14:00-14:45
+            (14 * 60 + 55, 15 * 60 + 40), // 第6节: 14:55-15:40
+            (16 * 60 + 15, 17 * 60),      // 第7节: 16:15-17:00
+            (17 * 60 + 10, 17 * 60 + 55), // 第8节: 17:10-17:55
+            (19 * 60, 19 * 60 + 45),      // 第9节: 19:00-19:45
+            (19 * 60 + 55, 20 * 60 + 40), // 第10节: 19:55-20:40
+            (20 * 60 + 50, 21 * 60 + 35), // 第11节: 20:50-21:35
+            (21 * 60 + 45, 22 * 60 + 30), // 第12节: 21:45-22:30
+        ];
+        Self {
+            lessons: CQUPT.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<Vec<[String; 2]>> for LessonSchedule {
+    type Error = String;
+
+    fn try_from(entries: Vec<[String; 2]>) -> Result<Self, Self::Error> {
+        if entries.len() != LESSONS_PER_DAY {
+            return Err(format!(
+                "expected {} lessons, got {}",
+                LESSONS_PER_DAY,
+                entries.len()
+            ));
+        }
+        let minutes = |time: &str| {
+            chrono::NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map(|t| t.hour() * 60 + t.minute())
+                .map_err(|_| format!("invalid time '{}', expected HH:MM", time))
+        };
+        let lessons = entries
+            .iter()
+            .enumerate()
+            .map(|(i, [start, end])| {
+                let (start, end) = (minutes(start)?, minutes(end)?);
+                if start >= end {
+                    return Err(format!("lesson {} starts after it ends", i + 1));
+                }
+                Ok((start, end))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { lessons })
+    }
+}
+
+impl From<LessonSchedule> for Vec<[String; 2]> {
+    fn from(schedule: LessonSchedule) -> Self {
+        let format = |minutes: u32| format!("{:02}:{:02}", minutes / 60, minutes % 60);
+        schedule
+            .lessons
+            .iter()
+            .map(|&(start, end)| [format(start), format(end)])
+            .collect()
+    }
 }
 
 /// 课程查询响应
@@ -144,6 +733,289 @@ pub struct CourseResponse {
     pub semester: Semester,
     /// 生成时间
     pub generated_at: DateTime<FixedOffset>,
+    /// 获取过程中的非致命问题，如被跳过的异常数据
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl CourseResponse {
+    /// 按 (开始时间, 星期, 开始节次, 课程名) 排序课程
+    ///
+    /// 部分 provider 的聚合顺序不确定，生成ICS前排序可保证输出稳定。
+    pub fn sort(&mut self) {
+        self.courses.sort_by(|a, b| {
+            a.start_time
+                .cmp(&b.start_time)
+                .then_with(|| a.weekday.cmp(&b.weekday))
+                .then_with(|| a.begin_lesson.cmp(&b.begin_lesson))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+    }
+
+    /// 合并另一个 provider 返回的考试，返回新增的考试数量
+    ///
+    /// 只取 `other` 中的考试；与已有考试名称、日期相同的视为同一场（不同 provider
+    /// 给出的时刻可能略有出入），保留本方数据。
+    pub fn merge_exams(&mut self, other: CourseResponse) -> usize {
+        let key = |course: &Course| (course.name.clone(), course.start_time.date_naive());
+        let mut seen: HashSet<_> = self
+            .courses
+            .iter()
+            .filter(|course| course.is_exam())
+            .map(key)
+            .collect();
+        let before = self.courses.len();
+        self.courses.extend(
+            other
+                .courses
+                .into_iter()
+                .filter(|course| course.is_exam() && seen.insert(key(course))),
+        );
+        self.warnings.extend(other.warnings);
+        self.courses.len() - before
+    }
+
+    /// 找出时间重叠的课程，返回下标对 (i, j)，且 i < j
+    pub fn conflicts(&self) -> Vec<(usize, usize)> {
+        let mut conflicts = Vec::new();
+        for (i, a) in self.courses.iter().enumerate() {
+            for (j, b) in self.courses.iter().enumerate().skip(i + 1) {
+                if a.overlaps(b) {
+                    conflicts.push((i, j));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// 找出 `now` 之后最近的一次上课，返回课程及其开始时间
+    ///
+    /// 有周次信息的课程按周展开并跳过放假周，其余课程视为单次事件。
+    pub fn next_occurrence(
+        &self,
+        now: DateTime<FixedOffset>,
+    ) -> Option<(&Course, DateTime<FixedOffset>)> {
+        self.courses
+            .iter()
+            .filter_map(|course| {
+                course
+                    .occurrences()
+                    .into_iter()
+                    .map(|(start, _)| start)
+                    .filter(|&start| start > now)
+                    .min()
+                    .map(|start| (course, start))
+            })
+            .min_by_key(|&(_, start)| start)
+    }
+
+    /// 统计课程、考试与周次覆盖情况
+    pub fn stats(&self) -> ResponseStats {
+        let mut class_names = std::collections::BTreeSet::new();
+        let mut locations = std::collections::BTreeSet::new();
+        let mut stats = ResponseStats {
+            total: self.courses.len(),
+            ..Default::default()
+        };
+        for course in &self.courses {
+            if course.is_exam() {
+                stats.exams += 1;
+            } else if course.is_custom() {
+                stats.custom += 1;
+            } else {
+                class_names.insert(course.name.as_str());
+            }
+            if let Some(location) = course.location.as_deref().filter(|l| !l.is_empty()) {
+                locations.insert(location);
+            }
+        }
+        stats.classes = class_names.len();
+        stats.distinct_locations = locations.len();
+        let weeks = self.courses.iter().flat_map(|c| c.weeks.iter().flatten());
+        stats.week_span = weeks.clone().min().copied().zip(weeks.max().copied());
+        stats
+    }
+}
+
+/// [`CourseResponse::stats`] 的统计结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResponseStats {
+    /// 事件总数
+    pub total: usize,
+    /// 不同课程的门数（按课程名去重，不含考试与自定义日程）
+    pub classes: usize,
+    /// 考试场数
+    pub exams: usize,
+    /// 自定义日程数
+    pub custom: usize,
+    /// 不同上课地点的数量
+    pub distinct_locations: usize,
+    /// 所有课程覆盖的 (最早, 最晚) 周次，没有周次信息时为 `None`
+    pub week_span: Option<(u32, u32)>,
+}
+
+/// 导出内容筛选
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ContentFilter {
+    /// 全部事件
+    #[default]
+    #[serde(rename = "all")]
+    All,
+    /// 仅普通课程
+    #[serde(rename = "classes")]
+    ClassesOnly,
+    /// 仅考试
+    #[serde(rename = "exams")]
+    ExamsOnly,
+    /// 仅自定义日程
+    #[serde(rename = "custom")]
+    CustomOnly,
+}
+
+impl std::str::FromStr for ContentFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "classes" => Ok(Self::ClassesOnly),
+            "exams" => Ok(Self::ExamsOnly),
+            "custom" => Ok(Self::CustomOnly),
+            _ => Err(format!(
+                "Invalid content filter '{}'. Expected one of: all, classes, exams, custom",
+                s
+            )),
+        }
+    }
+}
+
+/// 拆分日历的分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitBy {
+    /// 按事件类别拆分为 classes、exams、custom
+    Kind,
+    /// 按课程类型（如 必修、选修）拆分，缺失时归为 "其他"
+    CourseType,
+}
+
+impl SplitBy {
+    /// 课程所属分组的键
+    pub fn key(&self, course: &Course) -> String {
+        match self {
+            Self::Kind if course.is_exam() => "exams".to_string(),
+            Self::Kind if course.is_custom() => "custom".to_string(),
+            Self::Kind => "classes".to_string(),
+            Self::CourseType => course
+                .course_type
+                .as_deref()
+                .filter(|t| !t.is_empty())
+                .unwrap_or("其他")
+                .to_string(),
+        }
+    }
+}
+
+impl std::str::FromStr for SplitBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "kind" | "type" => Ok(Self::Kind),
+            "course_type" => Ok(Self::CourseType),
+            _ => Err(format!(
+                "Invalid split mode '{}'. Expected one of: type, course-type",
+                s
+            )),
+        }
+    }
+}
+
+/// 课程描述样式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DescriptionStyle {
+    /// 完整描述（课程代码、教师、类型、周次等）
+    #[default]
+    Full,
+    /// 精简描述，仅包含教师与地点
+    Compact,
+    /// 不输出 DESCRIPTION
+    None,
+}
+
+impl std::str::FromStr for DescriptionStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "compact" => Ok(Self::Compact),
+            "none" => Ok(Self::None),
+            _ => Err(format!(
+                "Invalid description style '{}'. Expected one of: full, compact, none",
+                s
+            )),
+        }
+    }
+}
+
+/// 事件颜色的分配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorBy {
+    /// 不输出颜色
+    #[default]
+    None,
+    /// 按课程、考试、自定义日程区分颜色
+    Type,
+    /// 每门课程按名称分配固定的颜色
+    CourseName,
+}
+
+impl std::str::FromStr for ColorBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "none" => Ok(Self::None),
+            "type" => Ok(Self::Type),
+            "course_name" => Ok(Self::CourseName),
+            _ => Err(format!(
+                "Invalid color mode '{}'. Expected one of: none, type, course-name",
+                s
+            )),
+        }
+    }
+}
+
+/// 上课地点的输出详细程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationDetail {
+    /// 仅输出教室号，适合 Android、Outlook 等客户端
+    PlainName,
+    /// 教室号加 GEO 坐标
+    NameWithGeo,
+    /// 建筑名称与地址、GEO 以及 Apple 的 X-APPLE-STRUCTURED-LOCATION
+    #[default]
+    Full,
+}
+
+impl std::str::FromStr for LocationDetail {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "plain_name" => Ok(Self::PlainName),
+            "name_with_geo" => Ok(Self::NameWithGeo),
+            "full" => Ok(Self::Full),
+            _ => Err(format!(
+                "Invalid location detail '{}'. Expected one of: plain-name, name-with-geo, full",
+                s
+            )),
+        }
+    }
 }
 
 /// ICS生成选项
@@ -151,20 +1023,125 @@ pub struct CourseResponse {
 pub struct IcsOptions {
     /// 日历名称
     pub calendar_name: Option<String>,
+    /// 日历描述，输出为 X-WR-CALDESC
+    #[serde(default)]
+    pub calendar_description: Option<String>,
+    /// 日历标识，输出为 X-WR-RELCALID，通常由 [`crate::ics::calendar_id`] 生成
+    #[serde(default)]
+    pub calendar_id: Option<String>,
+    /// 自定义 PRODID，为空时使用默认值
+    #[serde(default)]
+    pub prodid: Option<String>,
     /// 是否包含课程描述
     pub include_description: bool,
+    /// 课程描述样式
+    #[serde(default)]
+    pub description_style: DescriptionStyle,
+    /// 额外输出 HTML 描述（X-ALT-DESC），供支持富文本的客户端使用
+    #[serde(default)]
+    pub html_description: bool,
+    /// 课程没有任何详情时不输出 DESCRIPTION，而非 "暂无课程详情"
+    #[serde(default)]
+    pub omit_empty_description: bool,
     /// 是否包含教师信息
     pub include_teacher: bool,
+    /// 上课地点的输出详细程度
+    #[serde(default)]
+    pub location_detail: LocationDetail,
+    /// 定时事件用 DURATION 代替 DTEND，全天事件不受影响
+    #[serde(default)]
+    pub use_duration: bool,
+    /// 事件颜色，输出为 RFC 7986 的 COLOR
+    #[serde(default)]
+    pub color_by: ColorBy,
+    /// 结束时间不晚于开始时间的事件改用的时长（分钟）
+    #[serde(default = "default_min_duration")]
+    pub min_duration: u32,
+    /// 事件的最长时长（分钟），超出时截断；为空时不限制。全天事件不受影响
+    #[serde(default = "default_max_duration")]
+    pub max_duration: Option<u32>,
     pub reminder_minutes: Option<u32>,
+    /// 多个课程提醒，非空时取代 `reminder_minutes`
+    #[serde(default)]
+    pub reminders: Vec<ReminderSpec>,
+    /// 考试专用提醒，为空时沿用课程提醒
+    #[serde(default)]
+    pub exam_reminders: Vec<ReminderSpec>,
+    /// 是否输出放假日全天事件
+    #[serde(default)]
+    pub include_holiday_events: bool,
+    /// 使用由课程内容派生的稳定 UID，重新导入时订阅端可以更新而非重复添加
+    #[serde(default)]
+    pub stable_uids: bool,
+    /// 只导出指定类型的事件
+    #[serde(default)]
+    pub content_filter: ContentFilter,
+    /// 自定义日程输出为 VTODO 待办（以 DUE 表示时间），而非 VEVENT
+    #[serde(default)]
+    pub custom_as_todo: bool,
+    /// 只导出落在 [开始, 结束] 日期内的上课时段
+    #[serde(default)]
+    pub date_window: Option<(NaiveDate, NaiveDate)>,
+    /// 固定的 DTSTAMP，配合稳定 UID 可使同一课表生成的内容逐字节相同；为空时取当前时间
+    #[serde(default)]
+    pub fixed_dtstamp: Option<DateTime<Utc>>,
+    /// 未设置 `fixed_dtstamp` 时以课表的 `generated_at` 作为 DTSTAMP，同一份课表重复生成时不变
+    #[serde(default)]
+    pub dtstamp_from_response: bool,
+    /// 课程名称别名（原始名称 -> 显示名称），先精确匹配再按最长前缀匹配，仅影响标题
+    #[serde(default)]
+    pub name_aliases: HashMap<String, String>,
+}
+
+fn default_min_duration() -> u32 {
+    45
+}
+
+fn default_max_duration() -> Option<u32> {
+    Some(12 * 60)
+}
+
+/// 单个提醒设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderSpec {
+    /// 提前多少分钟提醒
+    pub minutes_before: u32,
+}
+
+impl ReminderSpec {
+    pub fn minutes(minutes_before: u32) -> Self {
+        Self { minutes_before }
+    }
 }
 
 impl Default for IcsOptions {
     fn default() -> Self {
         Self {
             calendar_name: Some("CQUPT课程表".to_string()),
+            calendar_description: None,
+            calendar_id: None,
+            prodid: None,
             include_description: true,
+            description_style: DescriptionStyle::Full,
+            html_description: false,
+            omit_empty_description: false,
             include_teacher: true,
+            location_detail: LocationDetail::Full,
+            use_duration: false,
+            color_by: ColorBy::None,
+            min_duration: default_min_duration(),
+            max_duration: default_max_duration(),
             reminder_minutes: Some(15),
+            reminders: Vec::new(),
+            exam_reminders: Vec::new(),
+            include_holiday_events: false,
+            stable_uids: false,
+            content_filter: ContentFilter::All,
+            custom_as_todo: false,
+            date_window: None,
+            fixed_dtstamp: None,
+            dtstamp_from_response: false,
+            name_aliases: HashMap::new(),
         }
     }
 }
@@ -183,3 +1160,388 @@ pub struct LocationMapping {
     /// 校区
     pub campus: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course(name: &str, weeks: Vec<u32>, (hour, minute): (u32, u32)) -> Course {
+        let tz = CQUPT_TZ;
+        // 首次上课为 weeks[0] 周的星期一
+        let start = tz.with_ymd_and_hms(2025, 9, 8, hour, minute, 0).unwrap()
+            + chrono::Duration::weeks(weeks[0] as i64 - 1);
+        Course {
+            name: name.to_string(),
+            start_time: start,
+            end_time: start + chrono::Duration::minutes(100),
+            weeks: Some(weeks),
+            weekday: Some(1),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn course_duration() {
+        assert_eq!(
+            course("高等数学", vec![1], (8, 0)).duration(),
+            chrono::Duration::minutes(100)
+        );
+    }
+
+    #[test]
+    fn overlap_on_shared_week() {
+        let math = course("高等数学", vec![1, 2, 3], (8, 0));
+        let english = course("大学英语", vec![3, 4, 5], (9, 0));
+        assert!(math.overlaps(&english));
+        assert!(english.overlaps(&math));
+
+        // 同一时段但周次不相交
+        let physics = course("大学物理", vec![4, 5, 6], (8, 0));
+        assert!(!math.overlaps(&physics));
+        // 周次未排序时仍以最早的周次对应首次上课
+        let unsorted = Course {
+            weeks: Some(vec![6, 4, 5]),
+            ..physics.clone()
+        };
+        assert!(!math.overlaps(&unsorted));
+
+        // 共同周次放假时不算冲突
+        let mut math_off = math.clone();
+        math_off.off_weeks = Some(vec![3]);
+        assert!(!math_off.overlaps(&english));
+
+        // 同一周但时间首尾相接
+        let later = course("程序设计", vec![3], (9, 40));
+        assert!(!math.overlaps(&later));
+    }
+
+    #[test]
+    fn week_expressions() {
+        assert_eq!(
+            parse_week_expression("1-16单").unwrap(),
+            vec![1, 3, 5, 7, 9, 11, 13, 15]
+        );
+        assert_eq!(
+            parse_week_expression("1-8,10-12").unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 10, 11, 12]
+        );
+        assert_eq!(
+            parse_week_expression("1-8,10,12双").unwrap(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 10, 12]
+        );
+        assert_eq!(
+            parse_week_expression("2-6周(双)，9周").unwrap(),
+            vec![2, 4, 6, 9]
+        );
+
+        for malformed in ["", "1-", "a-3", "8-1", "0", "1,,2", "4单"] {
+            assert!(
+                parse_week_expression(malformed).is_err(),
+                "{} should be rejected",
+                malformed
+            );
+        }
+    }
+
+    #[test]
+    fn response_conflicts() {
+        let response = CourseResponse {
+            courses: vec![
+                course("高等数学", vec![1, 2, 3], (8, 0)),
+                course("大学物理", vec![4, 5, 6], (8, 0)),
+                course("大学英语", vec![3, 4, 5], (9, 0)),
+            ],
+            semester: Semester::from_date_str("2025-09-08").unwrap(),
+            generated_at: Utc::now().fixed_offset(),
+            warnings: Vec::new(),
+        };
+        assert_eq!(response.conflicts(), vec![(0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn merge_exams_from_second_provider() {
+        let exam = |name: &str, week: u32, hour: u32| Course {
+            exam_type: Some("期末".to_string()),
+            weeks: None,
+            ..course(name, vec![week], (hour, 0))
+        };
+        let response = |courses: Vec<Course>| CourseResponse {
+            courses,
+            semester: Semester::from_date_str("2025-09-08").unwrap(),
+            generated_at: Utc::now().with_timezone(&CQUPT_TZ),
+            warnings: Vec::new(),
+        };
+        let mut classes = response(vec![
+            course("高等数学", vec![1, 2, 3], (8, 0)),
+            exam("大学英语", 18, 9),
+        ]);
+        let exams = response(vec![
+            course("线性代数", vec![1, 2], (10, 15)),
+            // 同名同日、时刻不同的视为同一场
+            exam("大学英语", 18, 10),
+            exam("大学物理", 18, 9),
+            exam("高等数学", 19, 14),
+        ]);
+
+        assert_eq!(classes.merge_exams(exams), 2);
+        let names: Vec<_> = classes.courses.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["高等数学", "大学英语", "大学物理", "高等数学"]);
+        assert!(classes.courses[3].is_exam());
+        assert_eq!(classes.courses[1].start_time.hour(), 9);
+    }
+
+    #[test]
+    fn response_stats_summary() {
+        let course = |name: &str, weeks: Option<Vec<u32>>, location: &str| Course {
+            name: name.to_string(),
+            weeks,
+            location: Some(location.to_string()),
+            ..Default::default()
+        };
+        let response = CourseResponse {
+            courses: vec![
+                course("高等数学", Some(vec![1, 2, 3]), "2101"),
+                course("高等数学", Some(vec![4, 16]), "2102"),
+                course("大学英语", Some(vec![2, 8]), "2101"),
+                Course {
+                    exam_type: Some("期末".to_string()),
+                    ..course("高等数学", None, "3101")
+                },
+                Course {
+                    note: Some("自定义日程: 社团活动".to_string()),
+                    ..course("社团", None, "")
+                },
+            ],
+            semester: Semester::from_date_str("2025-09-08").unwrap(),
+            generated_at: Utc::now().with_timezone(&CQUPT_TZ),
+            warnings: Vec::new(),
+        };
+
+        assert_eq!(
+            response.stats(),
+            ResponseStats {
+                total: 5,
+                classes: 2,
+                exams: 1,
+                custom: 1,
+                distinct_locations: 3,
+                week_span: Some((1, 16)),
+            }
+        );
+
+        let exams_only = CourseResponse {
+            courses: response.courses[3..4].to_vec(),
+            ..response
+        };
+        assert_eq!(exams_only.stats().week_span, None);
+    }
+
+    #[test]
+    fn next_occurrence_skips_off_weeks() {
+        let tz = CQUPT_TZ;
+        let mut math = course("高等数学", vec![1, 2, 3], (8, 0));
+        math.off_weeks = Some(vec![2]);
+        let response = CourseResponse {
+            courses: vec![math, course("大学英语", vec![2, 3], (10, 0))],
+            semester: Semester::from_date_str("2025-09-08").unwrap(),
+            generated_at: Utc::now().fixed_offset(),
+            warnings: Vec::new(),
+        };
+        let next = |y, m, d, h, min| {
+            response
+                .next_occurrence(tz.with_ymd_and_hms(y, m, d, h, min, 0).unwrap())
+                .map(|(course, start)| (course.name.as_str(), start))
+        };
+
+        // 第二周高数放假，下一节是下周的英语
+        let english = tz.with_ymd_and_hms(2025, 9, 15, 10, 0, 0).unwrap();
+        assert_eq!(next(2025, 9, 8, 9, 0), Some(("大学英语", english)));
+        assert_eq!(next(2025, 9, 14, 23, 59), Some(("大学英语", english)));
+        // 正在开始的课不算
+        assert_eq!(
+            next(2025, 9, 15, 10, 0),
+            Some((
+                "高等数学",
+                tz.with_ymd_and_hms(2025, 9, 22, 8, 0, 0).unwrap()
+            ))
+        );
+        assert_eq!(next(2025, 9, 22, 10, 0), None);
+    }
+
+    #[test]
+    fn credentials_debug_redacts_password() {
+        let request = CourseRequest {
+            credentials: Credentials {
+                username: "2023000000".to_string(),
+                password: "hunter2-secret".into(),
+                extra: HashMap::new(),
+            },
+            semester: None,
+            timeout: None,
+            options: Default::default(),
+        };
+
+        let debug = format!("{:?}", request);
+        assert!(debug.contains("2023000000"));
+        assert!(!debug.contains("hunter2-secret"));
+        assert_eq!(
+            request.credentials.password.expose_secret(),
+            "hunter2-secret"
+        );
+
+        // 反序列化仍接受普通字符串
+        let credentials: Credentials =
+            serde_json::from_str(r#"{"username":"u","password":"p","extra":{}}"#).unwrap();
+        assert_eq!(credentials.password.expose_secret(), "p");
+    }
+
+    #[test]
+    fn parse_seat_formats() {
+        let seat = |zone: Option<&str>, row: Option<&str>, number: Option<&str>, raw: &str| Seat {
+            zone: zone.map(str::to_string),
+            row: row.map(str::to_string),
+            number: number.map(str::to_string),
+            raw: raw.to_string(),
+        };
+
+        assert_eq!(
+            Seat::parse("A区12排5座"),
+            seat(Some("A"), Some("12"), Some("5"), "A区12排5座")
+        );
+        assert_eq!(
+            Seat::parse("12排5号"),
+            seat(None, Some("12"), Some("5"), "12排5号")
+        );
+        assert_eq!(Seat::parse(" 12 "), seat(None, None, Some("12"), "12"));
+        assert_eq!(Seat::parse("B07"), seat(None, None, Some("B07"), "B07"));
+        assert_eq!(
+            parse_seats("A区1排2座，B区3排4座"),
+            vec![
+                seat(Some("A"), Some("1"), Some("2"), "A区1排2座"),
+                seat(Some("B"), Some("3"), Some("4"), "B区3排4座"),
+            ]
+        );
+
+        // 无法解析时只保留原文
+        assert_eq!(
+            Seat::parse("靠窗第二个"),
+            seat(None, None, None, "靠窗第二个")
+        );
+        assert!(parse_seats(" ").is_empty());
+    }
+
+    #[test]
+    fn split_multiple_teachers() {
+        assert_eq!(split_teachers("张三,李四"), vec!["张三", "李四"]);
+        assert_eq!(split_teachers("张三、 李四、"), vec!["张三", "李四"]);
+        assert_eq!(split_teachers("王五"), vec!["王五"]);
+        assert!(split_teachers(" ").is_empty());
+
+        let teachers = split_teachers("张三，李四");
+        assert_eq!(join_teachers(&teachers).as_deref(), Some("张三、李四"));
+        assert_eq!(join_teachers(&[]), None);
+
+        // 未填充 teachers 时从 teacher 拆分
+        let course = Course {
+            teacher: Some("张三/李四".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(course.teacher_names(), vec!["张三", "李四"]);
+    }
+
+    #[test]
+    fn mid_week_semester_start() {
+        let date = |week, weekday| {
+            move |semester: &Semester| {
+                semester
+                    .occurrence_date(week, weekday)
+                    .format("%Y-%m-%d")
+                    .to_string()
+            }
+        };
+        // 2025-09-10 为星期三
+        let monday =
+            Semester::from_date_str_with_policy("2025-09-10", WeekStartPolicy::AlwaysMonday)
+                .unwrap();
+        assert_eq!(date(1, 1)(&monday), "2025-09-08");
+        assert_eq!(date(1, 3)(&monday), "2025-09-10");
+        assert_eq!(date(2, 1)(&monday), "2025-09-15");
+
+        let kept = Semester::from_date_str_with_policy("2025-09-10", WeekStartPolicy::KeepGivenDay)
+            .unwrap();
+        assert_eq!(
+            kept.get_week_start(1).format("%Y-%m-%d").to_string(),
+            "2025-09-10"
+        );
+        // 第一周的星期一、二顺延到开学日之后
+        assert_eq!(date(1, 1)(&kept), "2025-09-15");
+        assert_eq!(date(1, 3)(&kept), "2025-09-10");
+        assert_eq!(date(1, 7)(&kept), "2025-09-14");
+        assert_eq!(date(2, 3)(&kept), "2025-09-17");
+    }
+
+    #[test]
+    fn semester_from_term_code() {
+        let date = |semester: Semester| semester.start_date.format("%Y-%m-%d").to_string();
+        // 2024-09-01 为星期日，顺延到 9 月 2 日
+        assert_eq!(
+            date(SemesterDetector::from_term_code("2024-1").unwrap()),
+            "2024-09-02"
+        );
+        // 2025-03-01 为星期六
+        assert_eq!(
+            date(SemesterDetector::from_term_code("2024-2").unwrap()),
+            "2025-03-03"
+        );
+        // 2025-09-01 为星期一
+        assert_eq!(
+            date(SemesterDetector::from_term_code("2025-1").unwrap()),
+            "2025-09-01"
+        );
+        // 按日期推算时仍取1日所在周的星期一
+        assert_eq!(
+            date(SemesterDetector::create_semester_for(
+                NaiveDate::from_ymd_opt(2024, 10, 1).unwrap()
+            )),
+            "2024-08-26"
+        );
+        assert!(SemesterDetector::from_term_code("2024-3").is_err());
+        assert!(SemesterDetector::from_term_code("2024").is_err());
+        assert!(SemesterDetector::from_term_code("秋季-1").is_err());
+    }
+
+    #[test]
+    fn invalid_semester_date_is_config_error() {
+        match Semester::from_date_str("2025/09/08") {
+            Err(crate::Error::Config(message)) => {
+                assert!(message.contains("2025/09/08"));
+                assert!(message.contains("YYYY-MM-DD"));
+            }
+            other => panic!("expected Error::Config, got {:?}", other),
+        }
+        assert!(Semester::from_date_str("2025-09-08").is_ok());
+    }
+
+    #[test]
+    fn split_periods_at_long_breaks() {
+        let lessons = LessonSchedule::default();
+        // 第4节 11:55 下课，第5节 14:00 上课
+        assert_eq!(lessons.split_at_breaks(4, 2), vec![(4, 1), (5, 1)]);
+        assert_eq!(lessons.split_at_breaks(3, 4), vec![(3, 2), (5, 2)]);
+        // 第8节 17:55 下课，第9节 19:00 上课
+        assert_eq!(lessons.split_at_breaks(7, 4), vec![(7, 2), (9, 2)]);
+        // 课间大休息不拆分
+        assert_eq!(lessons.split_at_breaks(1, 4), vec![(1, 4)]);
+        assert_eq!(lessons.split_at_breaks(11, 4), vec![(11, 4)]);
+        assert_eq!(lessons.split_at_breaks(1, 0), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn default_lesson_schedule_round_trips() {
+        let default = LessonSchedule::default();
+        let json = serde_json::to_string(&default).unwrap();
+        assert!(json.starts_with(r#"[["08:00","08:45"],["08:55","09:40"]"#));
+        assert_eq!(LessonSchedule::from_json(&json).unwrap(), default);
+        assert_eq!(default.get(0), None);
+    }
+}
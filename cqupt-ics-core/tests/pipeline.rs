@@ -0,0 +1,98 @@
+//! 端到端测试：provider -> 节假日调休 -> ICS 生成 -> 解析回读
+
+use std::collections::HashMap;
+
+use cqupt_ics_core::{
+    Course, CourseRequest, Credentials,
+    cache::{CacheManager, MemoryCache},
+    holiday::HolidayCalendar,
+    ics::{self, IcsGenerator},
+    providers::{ProviderWrapper, Wrapper, mock::MockProvider},
+};
+
+/// 2025 年国庆：10-01 至 10-08 放假，09-28（周日）补班
+const HOLIDAY_ICS: &str = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+X-APPLE-UNIVERSAL-ID:national-day\r\n\
+DTSTART;VALUE=DATE:20251001\r\n\
+DTEND;VALUE=DATE:20251009\r\n\
+SUMMARY:国庆节 休\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+X-APPLE-UNIVERSAL-ID:national-day\r\n\
+DTSTART;VALUE=DATE:20250928\r\n\
+DTEND;VALUE=DATE:20250929\r\n\
+SUMMARY:国庆节 班\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+fn find<'a>(courses: &'a [Course], name: &str) -> &'a Course {
+    courses
+        .iter()
+        .find(|course| course.name == name && !course.is_makeup)
+        .unwrap_or_else(|| panic!("missing course {}", name))
+}
+
+#[tokio::test]
+async fn mock_provider_full_pipeline() {
+    let wrapper = Wrapper::new(
+        MockProvider::new(),
+        CacheManager::new(MemoryCache::default()),
+    );
+    let mut request = CourseRequest {
+        credentials: Credentials {
+            username: "2023000000".to_string(),
+            password: Default::default(),
+            extra: HashMap::new(),
+        },
+        semester: None,
+        timeout: None,
+        options: Default::default(),
+    };
+
+    let mut response = wrapper.get_courses(&mut request).await.unwrap();
+    assert_eq!(response.courses.len(), 5);
+    assert_eq!(
+        response.semester.start_date.date_naive().to_string(),
+        "2025-09-08"
+    );
+
+    let calendar = HolidayCalendar::from_bytes(HOLIDAY_ICS).unwrap();
+    calendar.apply_to_response(&mut response);
+    let makeups: Vec<_> = response.courses.iter().filter(|c| c.is_makeup).collect();
+    assert!(!makeups.is_empty());
+    assert!(
+        makeups
+            .iter()
+            .all(|c| c.start_time.date_naive().to_string() == "2025-09-28")
+    );
+    // 第5周周一（10-06）的高数停课
+    let math = find(&response.courses, "高等数学A(上)");
+    assert!(
+        math.off_weeks
+            .as_ref()
+            .is_some_and(|weeks| weeks.contains(&5))
+    );
+
+    let ics = IcsGenerator::default().generate(&response).unwrap();
+    IcsGenerator::validate(&ics).unwrap();
+    assert_eq!(
+        ics.matches("BEGIN:VEVENT").count(),
+        ics.matches("END:VEVENT").count()
+    );
+    assert!(ics.contains("RRULE:FREQ=WEEKLY;UNTIL=20251222T000000Z;BYDAY=MO\r\n"));
+    assert!(ics.contains("EXDATE:20251006T000000Z\r\n"));
+    assert!(ics.contains("CATEGORIES:调休补课\r\n"));
+    assert!(ics.contains("[期末考试] 线性代数 (考试) - 4101"));
+
+    // 解析回读后课程与补课事件都在
+    let parsed = ics::parse(&ics).unwrap();
+    assert_eq!(parsed.courses.len(), ics.matches("BEGIN:VEVENT").count());
+    assert_eq!(
+        parsed.courses.iter().filter(|c| c.is_makeup).count(),
+        makeups.len()
+    );
+    let parsed_math = find(&parsed.courses, "高等数学A(上)");
+    assert_eq!(parsed_math.start_time, math.start_time);
+    assert_eq!(parsed_math.end_time, math.end_time);
+}
@@ -1,24 +1,30 @@
 use axum::{
     Json, Router,
+    body::Body,
     extract::{Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
     routing::get,
 };
 use cqupt_ics_core::{
-    cache::CacheBackend, ics::IcsGenerator, location::LocationManager, prelude::*,
+    AuthFailReason,
+    ics::{self, IcsGenerator},
+    location::LocationManager,
+    prelude::*,
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::{fmt, time::Duration as StdDuration};
+use std::{fmt, io, time::Duration as StdDuration};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 use tower::ServiceBuilder;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
 use crate::cache::RedisCache;
 
-const DEFAULT_HOLIDAY_URL: &str = "https://calendars.icloud.com/holidays/cn_zh.ics";
-const HOLIDAY_CACHE_KEY: &str = "holiday:cn_zh";
 const HOLIDAY_CACHE_TTL: StdDuration = StdDuration::from_secs(60 * 60 * 24 * 30);
+/// ICS 流式输出的缓冲事件数，客户端读取过慢时生成端会等待
+const ICS_STREAM_BUFFER: usize = 16;
 
 /// 应用状态
 #[derive(Clone)]
@@ -38,8 +44,29 @@ struct HealthResponse {
 /// 错误响应
 #[derive(Serialize)]
 struct ErrorResponse {
+    /// 稳定的机器可读错误码，见 [`error_code`]
+    code: &'static str,
     error: String,
     message: String,
+    /// 认证失败原因，仅认证错误时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<AuthFailReason>,
+}
+
+/// 位置列表查询参数
+#[derive(Deserialize, Default)]
+struct LocationsQuery {
+    campus: Option<String>,   // 校区关键字，如 南山
+    contains: Option<String>, // 原始或标准化名称包含的关键字
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// 位置列表响应，total 为分页前的匹配总数
+#[derive(Serialize)]
+struct LocationsPage {
+    total: usize,
+    items: Vec<LocationMapping>,
 }
 
 /// 课程获取请求参数
@@ -49,7 +76,62 @@ struct GetCoursesQuery {
     username: String,
     password: String,
     start_date: Option<String>, // 格式：YYYY-MM-DD，如 2024-03-04，可选
+    term: Option<String>,       // 学期代码，如 2024-1，不能与 start_date 同时提供，可选
     format: Option<String>,     // "json" or "ics"，默认为 "ics"
+    #[serde(default)]
+    description_style: DescriptionStyle, // "full", "compact" or "none"，默认为 "full"
+    #[serde(default)]
+    location_detail: LocationDetail, // "plain_name", "name_with_geo" or "full"，默认为 "full"
+    #[serde(default)]
+    only: ContentFilter, // "all", "classes", "exams" or "custom"，默认为 "all"
+    timeout: Option<u32>,       // 超时时间（秒），不超过服务端上限
+    include_exams: Option<bool>, // 是否获取考试安排，默认为 true
+    include_custom: Option<bool>, // 是否获取自定义日程，默认为 true
+}
+
+/// 课程接口的响应格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CourseFormat {
+    Ics,
+    Json,
+}
+
+/// 确定响应格式：`format` 参数优先，其次按 `Accept` 头协商
+///
+/// 没有 `Accept` 头时默认返回 ICS，`Accept` 中没有支持的类型时返回 None。
+fn negotiate_format(format: Option<&str>, accept: Option<&str>) -> Option<CourseFormat> {
+    if let Some(format) = format {
+        return Some(match format {
+            "json" => CourseFormat::Json,
+            _ => CourseFormat::Ics,
+        });
+    }
+
+    let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+        return Some(CourseFormat::Ics);
+    };
+
+    // 解析媒体类型与 q 值，q=0 表示不接受
+    let mut ranges = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let media = parts.next()?.to_ascii_lowercase();
+            let q = parts
+                .filter_map(|p| p.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            (q > 0.0).then_some((media, q))
+        })
+        .collect::<Vec<_>>();
+    // 稳定排序，q 相同时保持原有顺序
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges.iter().find_map(|(media, _)| match media.as_str() {
+        "text/calendar" | "text/*" | "*/*" => Some(CourseFormat::Ics),
+        "application/json" | "application/*" => Some(CourseFormat::Json),
+        _ => None,
+    })
 }
 
 pub async fn create_app(
@@ -118,11 +200,12 @@ async fn health_handler() -> impl IntoResponse {
 async fn list_providers_handler(State(state): State<AppState>) -> impl IntoResponse {
     let providers: Vec<_> = state
         .registry
-        .list_providers()
-        .map(|(name, description)| {
+        .providers()
+        .map(|provider| {
             serde_json::json!({
-                "name": name,
-                "description": description,
+                "name": provider.name(),
+                "description": provider.description(),
+                "capabilities": provider.capabilities(),
                 "status": "available"
             })
         })
@@ -133,40 +216,100 @@ async fn list_providers_handler(State(state): State<AppState>) -> impl IntoRespo
     }))
 }
 
-/// 列出位置映射
-async fn list_locations_handler(State(state): State<AppState>) -> impl IntoResponse {
-    let mappings: Vec<_> = state
-        .location_manager
-        .get_all_mappings()
-        .values()
+/// 列出位置映射，支持按校区、关键字筛选与分页
+async fn list_locations_handler(
+    Query(params): Query<LocationsQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    Json(filter_locations(
+        state.location_manager.get_all_mappings().values(),
+        &params,
+    ))
+}
+
+/// 按查询参数筛选位置映射，结果按原始名称排序以保证分页稳定
+fn filter_locations<'a>(
+    mappings: impl Iterator<Item = &'a LocationMapping>,
+    params: &LocationsQuery,
+) -> LocationsPage {
+    let mut matched: Vec<_> = mappings
+        .filter(|mapping| {
+            params.campus.as_deref().is_none_or(|campus| {
+                mapping
+                    .campus
+                    .as_deref()
+                    .is_some_and(|value| value.contains(campus))
+            })
+        })
+        .filter(|mapping| {
+            params.contains.as_deref().is_none_or(|keyword| {
+                mapping.original.contains(keyword) || mapping.normalized.contains(keyword)
+            })
+        })
+        .collect();
+    matched.sort_by(|a, b| a.original.cmp(&b.original));
+
+    let total = matched.len();
+    let items = matched
+        .into_iter()
+        .skip(params.offset.unwrap_or(0))
+        .take(params.limit.unwrap_or(usize::MAX))
         .cloned()
         .collect();
-    Json(mappings)
+    LocationsPage { total, items }
 }
 
 /// 获取课程处理器
 async fn get_courses_handler(
     Query(params): Query<GetCoursesQuery>,
     State(state): State<AppState>,
+    headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     use std::collections::HashMap;
 
-    let semester = params
-        .start_date
-        .map(|date_str| {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok());
+    let Some(format) = negotiate_format(params.format.as_deref(), accept) else {
+        let body = Json(ErrorResponse {
+            code: "NOT_ACCEPTABLE",
+            error: "不支持的响应格式".to_string(),
+            message: format!(
+                "Unsupported Accept: {}; expected text/calendar or application/json",
+                accept.unwrap_or_default()
+            ),
+            reason: None,
+        });
+        return Ok((StatusCode::NOT_ACCEPTABLE, body).into_response());
+    };
+
+    let semester = match (params.start_date, params.term) {
+        (Some(_), Some(_)) => {
+            return Err(AppError(cqupt_ics_core::Error::Config(
+                "start_date and term cannot both be specified".to_string(),
+            )));
+        }
+        (Some(date_str), None) => {
             tracing::info!("使用指定的学期开始日期: {}", date_str);
-            Semester::from_date_str(&date_str)
-                .map_err(|e| cqupt_ics_core::Error::Config(format!("Invalid start date: {}", e)))
-        })
-        .transpose()?;
+            Some(Semester::from_date_str(&date_str)?)
+        }
+        (None, Some(term)) => Some(SemesterDetector::from_term_code(&term)?),
+        (None, None) => None,
+    };
     // 创建请求对象
     let mut request = CourseRequest {
         credentials: Credentials {
             username: params.username.clone(),
-            password: params.password,
+            password: params.password.into(),
             extra: HashMap::new(),
         },
         semester,
+        timeout: params.timeout,
+        options: ProviderOptions {
+            include_exams: params.include_exams.unwrap_or(true),
+            include_custom: params.include_custom.unwrap_or(true),
+            ..Default::default()
+        },
     };
 
     // 获取 provider
@@ -184,43 +327,75 @@ async fn get_courses_handler(
     let mut response = provider.get_courses(&mut request).await?;
 
     state.holiday_calendar.apply_to_response(&mut response);
+    response.sort();
 
-    // 根据格式参数返回不同内容，默认为 ics
-    match params.format.as_deref() {
-        Some("json") => {
+    // 根据协商的格式返回不同内容，默认为 ics
+    match format {
+        CourseFormat::Json => {
             // 返回JSON格式
             Ok(Json(response).into_response())
         }
-        _ => {
+        CourseFormat::Ics => {
             // 默认返回ICS格式
             let options = IcsOptions {
                 calendar_name: Some(format!("CQUPT课程表-{}", params.username)),
+                calendar_id: Some(ics::calendar_id(
+                    &params.provider,
+                    &params.username,
+                    &response.semester,
+                )),
+                description_style: params.description_style,
+                location_detail: params.location_detail,
+                content_filter: params.only,
                 include_teacher: true,
                 reminder_minutes: Some(15),
                 ..Default::default()
             };
-            let generator = IcsGenerator::new(options);
-            let ics_content = generator.generate(&response)?;
-
             Ok((
                 StatusCode::OK,
                 [("Content-Type", "text/calendar; charset=utf-8")],
-                ics_content,
+                stream_ics(IcsGenerator::new(options), response),
             )
                 .into_response())
         }
     }
 }
 
+/// 将写入转发到有界 channel，每次写入对应一个 ICS 分块
+struct ChannelWriter {
+    tx: mpsc::Sender<io::Result<Vec<u8>>>,
+}
+
+impl io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tx
+            .blocking_send(Ok(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 在阻塞线程中逐个事件生成 ICS，边生成边发送给客户端
+fn stream_ics(generator: IcsGenerator, response: CourseResponse) -> Body {
+    let (tx, rx) = mpsc::channel(ICS_STREAM_BUFFER);
+    tokio::task::spawn_blocking(move || {
+        let writer = ChannelWriter { tx: tx.clone() };
+        if let Err(e) = generator.generate_to_writer(&response, writer) {
+            tracing::error!("ICS generation failed while streaming: {}", e);
+            let _ = tx.blocking_send(Err(io::Error::other(e.to_string())));
+        }
+    });
+    Body::from_stream(ReceiverStream::new(rx))
+}
+
 async fn load_holiday_calendar(
     client: Client,
     holiday_cache: &RedisCache,
 ) -> Result<HolidayCalendar, AppError> {
-    if let Some(bytes) = holiday_cache.get_raw(HOLIDAY_CACHE_KEY).await? {
-        tracing::debug!("命中节假日调休缓存");
-        return HolidayCalendar::from_bytes(&bytes).map_err(AppError::from);
-    }
-
     let url = std::env::var("HOLIDAY_ICS_URL")
         .ok()
         .map(|s| s.trim().to_string())
@@ -228,33 +403,9 @@ async fn load_holiday_calendar(
         .unwrap_or_else(|| DEFAULT_HOLIDAY_URL.to_string());
 
     tracing::info!("使用节假日调休日历: {}", url);
-
-    let response = client.get(&url).send().await.map_err(|e| {
-        AppError(cqupt_ics_core::Error::Provider {
-            provider: "holiday".to_string(),
-            message: format!("请求节假日ICS失败: {}", e),
-        })
-    })?;
-
-    if !response.status().is_success() {
-        return Err(AppError(cqupt_ics_core::Error::Provider {
-            provider: "holiday".to_string(),
-            message: format!("获取节假日ICS失败: HTTP {}", response.status()),
-        }));
-    }
-
-    let bytes = response.bytes().await.map_err(|e| {
-        AppError(cqupt_ics_core::Error::Provider {
-            provider: "holiday".to_string(),
-            message: format!("读取节假日ICS内容失败: {}", e),
-        })
-    })?;
-    let data = bytes.to_vec();
-
-    holiday_cache
-        .set_raw(HOLIDAY_CACHE_KEY, &data, HOLIDAY_CACHE_TTL)
-        .await?;
-    HolidayCalendar::from_bytes(&data).map_err(AppError::from)
+    HolidayCalendar::from_url(&client, &url, holiday_cache, HOLIDAY_CACHE_TTL)
+        .await
+        .map_err(AppError::from)
 }
 
 /// 应用错误类型
@@ -274,21 +425,62 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match &self.0 {
             cqupt_ics_core::Error::Config(_) => (StatusCode::BAD_REQUEST, "配置错误"),
-            cqupt_ics_core::Error::Authentication(_) => (StatusCode::UNAUTHORIZED, "认证失败"),
+            cqupt_ics_core::Error::Authentication { reason, .. } => match reason {
+                AuthFailReason::BadCredentials => (StatusCode::UNAUTHORIZED, "账号或密码错误"),
+                AuthFailReason::Locked => (StatusCode::LOCKED, "账号已被锁定"),
+                AuthFailReason::CaptchaRequired => {
+                    (StatusCode::PRECONDITION_REQUIRED, "需要验证码")
+                }
+                // 与账号密码错误同为 401，由响应中的 reason 字段区分
+                AuthFailReason::Expired => (StatusCode::UNAUTHORIZED, "凭据已过期"),
+                AuthFailReason::Unknown => (StatusCode::FORBIDDEN, "认证失败"),
+            },
             cqupt_ics_core::Error::Provider { .. } => (StatusCode::BAD_GATEWAY, "provider错误"),
+            cqupt_ics_core::Error::Deserialize { .. } => {
+                (StatusCode::BAD_GATEWAY, "解析上游响应失败，接口可能已变更")
+            }
             cqupt_ics_core::Error::Timeout => (StatusCode::GATEWAY_TIMEOUT, "请求超时"),
+            cqupt_ics_core::Error::CurfewTime(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "学校网络宵禁中，请稍后重试",
+            ),
             _ => (StatusCode::INTERNAL_SERVER_ERROR, "内部服务器错误"),
         };
 
+        let reason = match &self.0 {
+            cqupt_ics_core::Error::Authentication { reason, .. } => Some(*reason),
+            _ => None,
+        };
         let body = Json(ErrorResponse {
+            code: error_code(&self.0),
             error: error_message.to_string(),
             message: self.0.to_string(),
+            reason,
         });
 
         (status, body).into_response()
     }
 }
 
+/// 错误对应的稳定错误码，客户端应以此而非 `error` 文本判断错误类型
+fn error_code(err: &cqupt_ics_core::Error) -> &'static str {
+    use cqupt_ics_core::Error;
+    match err {
+        Error::Config(_) => "BAD_REQUEST",
+        Error::Authentication { .. } => "AUTH_FAILED",
+        Error::Provider { .. } | Error::Http(_) => "PROVIDER_DOWN",
+        Error::Deserialize { .. } => "PROVIDER_BAD_RESPONSE",
+        Error::Timeout => "TIMEOUT",
+        Error::CurfewTime(_) => "CURFEW",
+        Error::Json(_)
+        | Error::DateTime(_)
+        | Error::IcsGeneration(_)
+        | Error::LocationNotFound(_)
+        | Error::Rsa(_)
+        | Error::Internal(_) => "INTERNAL",
+    }
+}
+
 impl<E> From<E> for AppError
 where
     E: Into<cqupt_ics_core::Error>,
@@ -297,3 +489,176 @@ where
         Self(err.into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_header_selects_format() {
+        assert_eq!(
+            negotiate_format(None, Some("application/json")),
+            Some(CourseFormat::Json)
+        );
+        assert_eq!(
+            negotiate_format(None, Some("text/calendar")),
+            Some(CourseFormat::Ics)
+        );
+        assert_eq!(negotiate_format(None, None), Some(CourseFormat::Ics));
+        assert_eq!(negotiate_format(None, Some("*/*")), Some(CourseFormat::Ics));
+        assert_eq!(
+            negotiate_format(None, Some("text/calendar;q=0.5, application/json")),
+            Some(CourseFormat::Json)
+        );
+        assert_eq!(
+            negotiate_format(None, Some("text/html, application/json;q=0.9")),
+            Some(CourseFormat::Json)
+        );
+    }
+
+    #[test]
+    fn unsupported_accept_is_rejected() {
+        assert_eq!(negotiate_format(None, Some("text/csv")), None);
+        assert_eq!(negotiate_format(None, Some("text/html")), None);
+        assert_eq!(negotiate_format(None, Some("application/json;q=0")), None);
+    }
+
+    #[test]
+    fn format_param_overrides_accept() {
+        assert_eq!(
+            negotiate_format(Some("json"), Some("text/calendar")),
+            Some(CourseFormat::Json)
+        );
+        assert_eq!(
+            negotiate_format(Some("ics"), Some("application/json")),
+            Some(CourseFormat::Ics)
+        );
+        assert_eq!(
+            negotiate_format(Some("ics"), Some("text/csv")),
+            Some(CourseFormat::Ics)
+        );
+    }
+
+    fn mapping(original: &str, campus: &str) -> LocationMapping {
+        LocationMapping {
+            original: original.to_string(),
+            normalized: original.to_string(),
+            building: None,
+            room: None,
+            campus: Some(campus.to_string()),
+        }
+    }
+
+    #[test]
+    fn locations_are_filtered_and_paginated() {
+        let mappings = [
+            mapping("二教2101", "南山校区"),
+            mapping("三教3208", "南山校区"),
+            mapping("四教4101", "南山校区"),
+            mapping("仙桃1号楼", "仙桃校区"),
+        ];
+
+        let page = filter_locations(mappings.iter(), &LocationsQuery::default());
+        assert_eq!(page.total, 4);
+        assert_eq!(page.items.len(), 4);
+
+        let query = LocationsQuery {
+            campus: Some("南山".to_string()),
+            contains: Some("教".to_string()),
+            ..Default::default()
+        };
+        let page = filter_locations(mappings.iter(), &query);
+        assert_eq!(page.total, 3);
+
+        let query = LocationsQuery {
+            campus: Some("南山".to_string()),
+            limit: Some(2),
+            offset: Some(1),
+            ..Default::default()
+        };
+        let page = filter_locations(mappings.iter(), &query);
+        assert_eq!(page.total, 3);
+        let names: Vec<_> = page.items.iter().map(|m| m.original.as_str()).collect();
+        assert_eq!(names, ["二教2101", "四教4101"]);
+
+        let query = LocationsQuery {
+            contains: Some("仙桃".to_string()),
+            offset: Some(5),
+            ..Default::default()
+        };
+        let page = filter_locations(mappings.iter(), &query);
+        assert_eq!(page.total, 1);
+        assert!(page.items.is_empty());
+    }
+
+    #[test]
+    fn errors_map_to_stable_codes() {
+        use cqupt_ics_core::Error;
+
+        let http = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let json = serde_json::from_str::<u32>("x").unwrap_err();
+        let date = chrono::NaiveDate::parse_from_str("x", "%Y").unwrap_err();
+        let cases = [
+            (Error::Config("bad start_date".to_string()), "BAD_REQUEST"),
+            (
+                Error::authentication(AuthFailReason::Locked, "locked"),
+                "AUTH_FAILED",
+            ),
+            (
+                Error::Provider {
+                    provider: "redrock".to_string(),
+                    message: "502".to_string(),
+                },
+                "PROVIDER_DOWN",
+            ),
+            (Error::Http(http), "PROVIDER_DOWN"),
+            (
+                Error::Deserialize {
+                    provider: "redrock".to_string(),
+                    context: "missing field".to_string(),
+                },
+                "PROVIDER_BAD_RESPONSE",
+            ),
+            (Error::Timeout, "TIMEOUT"),
+            (Error::CurfewTime(()), "CURFEW"),
+            (Error::Json(json), "INTERNAL"),
+            (Error::DateTime(date), "INTERNAL"),
+            (Error::IcsGeneration("x".to_string()), "INTERNAL"),
+            (Error::LocationNotFound("x".to_string()), "INTERNAL"),
+            (Error::Internal("x".to_string()), "INTERNAL"),
+        ];
+        for (err, code) in cases {
+            assert_eq!(error_code(&err), code, "{}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn deserialize_error_is_bad_gateway() {
+        let err = AppError(cqupt_ics_core::Error::Deserialize {
+            provider: "redrock".to_string(),
+            context: "exam schedule: missing field `data`".to_string(),
+        });
+        let response = err.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "PROVIDER_BAD_RESPONSE");
+        assert!(body["error"].as_str().unwrap().contains("接口可能已变更"));
+        assert!(body["message"].as_str().unwrap().contains("missing field"));
+    }
+
+    #[tokio::test]
+    async fn curfew_is_service_unavailable() {
+        let response = AppError(cqupt_ics_core::Error::CurfewTime(())).into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "CURFEW");
+    }
+}
@@ -1,28 +1,47 @@
+use std::time::Duration;
+
 use crate::cache::RedisCache;
-use cqupt_ics_core::prelude::{redrock::RedrockProvider, wecqupt::WecquptProvider, *};
+use cqupt_ics_core::prelude::*;
+
+/// 单次课程获取的整体超时，避免客户端长时间挂起
+const COURSES_DEADLINE: Duration = Duration::from_secs(45);
+
+/// 读取可选的上游根地址覆盖（用于镜像或反向代理）
+fn api_root_override(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|v| !v.trim().is_empty())
+}
 
 pub(crate) async fn init_with_redis(
     redis_manager: &redis::aio::ConnectionManager,
 ) -> Result<ProviderRegistry, cqupt_ics_core::Error> {
-    let mut p = ProviderRegistry::new();
-
     let redis_cache = RedisCache::new("cqupt-ics".to_string(), redis_manager.clone());
 
-    p.register(
-        Wrapper::new(
-            RedrockProvider::new(),
-            CacheManager::new(redis_cache.clone()),
-        )
-        .into_static(),
-    );
-    
-    p.register(
-        Wrapper::new(
-            WecquptProvider::new(),
-            CacheManager::new(redis_cache.clone()),
-        )
-        .into_static(),
-    );
+    let redrock_api_root = api_root_override("REDROCK_API_ROOT");
+    if let Some(api_root) = &redrock_api_root {
+        tracing::info!("Using redrock API root: {}", api_root);
+    }
+    let wecqupt_api_root = api_root_override("WECQUPT_API_ROOT");
+    if let Some(api_root) = &wecqupt_api_root {
+        tracing::info!("Using wecqupt API root: {}", api_root);
+    }
+
+    let curfew_window = match std::env::var("CQUPT_ICS_CURFEW_WINDOW") {
+        Ok(window) => Some(window.parse()?),
+        Err(_) => None,
+    };
 
-    Ok(p)
+    build_registry(
+        redis_cache,
+        RegistryOptions {
+            deadline: Some(COURSES_DEADLINE),
+            // mock 仅供测试与演示；file provider 不校验密码，知道用户名即可读取导出文件
+            include_mock: false,
+            file_provider: None,
+            redrock_api_root,
+            wecqupt_api_root,
+            // 宵禁期间直接返回 503，不在请求中等待
+            curfew_wait: None,
+            curfew_window,
+        },
+    )
 }